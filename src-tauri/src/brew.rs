@@ -0,0 +1,129 @@
+use crate::executor::CommandExecutor;
+use crate::version;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Default `brew` locations for Apple Silicon and Intel Macs. Apps started
+/// from Finder don't get the shell's PATH, so these are checked as well.
+pub const BREW_PATHS: &[&str] = &["/opt/homebrew/bin/brew", "/usr/local/bin/brew"];
+
+/// Locate `brew`: on PATH first, then the default prefixes.
+pub fn find_brew(on_path: Option<PathBuf>, exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    on_path.or_else(|| {
+        BREW_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .find(|p| exists(p.as_path()))
+    })
+}
+
+pub fn detect(exec: &dyn CommandExecutor) -> Option<PathBuf> {
+    find_brew(exec.which("brew"), |p| exec.exists(p))
+}
+
+/// Parse `brew list --versions`: `cmake 3.29.3` or `python@3.12 3.12.3 3.12.4`,
+/// keeping the last (newest) version of each formula.
+pub fn parse_list_versions(stdout: &str) -> BTreeMap<String, String> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            let version = parts.last()?;
+            Some((name.to_string(), version.to_string()))
+        })
+        .collect()
+}
+
+/// Version of an installed formula, also matching versioned formulae
+/// (`python-tk` matches `python-tk@3.12`, the newest one if several are
+/// installed).
+pub fn installed_version<'a>(
+    installed: &'a BTreeMap<String, String>,
+    formula: &str,
+) -> Option<&'a str> {
+    installed
+        .get(formula)
+        .or_else(|| {
+            installed
+                .iter()
+                .filter_map(|(name, version)| {
+                    let suffix = name.strip_prefix(formula)?.strip_prefix('@')?;
+                    Some((suffix, version))
+                })
+                // python@3.12 is newer than python@3.9
+                .max_by(|(a, _), (b, _)| version::compare(a, b))
+                .map(|(_, version)| version)
+        })
+        .map(|v| v.as_str())
+}
+
+/// Homebrew formulae providing a dependency, following the Zephyr getting
+/// started guide for macOS.
+pub fn formulae_for(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "Git" => &["git"],
+        "CMake" => &["cmake"],
+        "Ninja" => &["ninja"],
+        "Gperf" => &["gperf"],
+        "CCache" => &["ccache"],
+        "DTC" => &["dtc"],
+        "Wget" => &["wget"],
+        "Python 3" => &["python3"],
+        "XZ Utils" => &["xz"],
+        "Dfu-util" => &["dfu-util"],
+        "python-tk" => &["python-tk"],
+        "libmagic" => &["libmagic"],
+        "QEMU" => &["qemu"],
+        "OpenOCD" => &["open-ocd"],
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_brew_on_path_before_default_prefixes() {
+        let on_path = Some(PathBuf::from("/custom/bin/brew"));
+        assert_eq!(
+            find_brew(on_path, |_| true),
+            Some(PathBuf::from("/custom/bin/brew"))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_default_prefixes() {
+        let intel = find_brew(None, |p| p == Path::new("/usr/local/bin/brew"));
+        assert_eq!(intel, Some(PathBuf::from("/usr/local/bin/brew")));
+
+        let apple_silicon = find_brew(None, |_| true);
+        assert_eq!(apple_silicon, Some(PathBuf::from("/opt/homebrew/bin/brew")));
+
+        assert_eq!(find_brew(None, |_| false), None);
+    }
+
+    #[test]
+    fn parses_list_versions() {
+        let installed = parse_list_versions(
+            "cmake 3.29.3\nninja 1.12.1\npython@3.12 3.12.3 3.12.4\npython-tk@3.12 3.12.4\n\n",
+        );
+        assert_eq!(installed.get("cmake").map(String::as_str), Some("3.29.3"));
+        assert_eq!(
+            installed.get("python@3.12").map(String::as_str),
+            Some("3.12.4")
+        );
+        assert_eq!(installed.len(), 4);
+    }
+
+    #[test]
+    fn matches_versioned_formulae() {
+        let installed = parse_list_versions("python-tk@3.11 3.11.9\npython-tk@3.12 3.12.4\n");
+        assert_eq!(installed_version(&installed, "python-tk"), Some("3.12.4"));
+        assert_eq!(installed_version(&installed, "python"), None);
+        // Not by name order, where `python@3.9` sorts last
+        let pythons = parse_list_versions("python@3.12 3.12.4\npython@3.9 3.9.19\n");
+        assert_eq!(installed_version(&pythons, "python"), Some("3.12.4"));
+    }
+}
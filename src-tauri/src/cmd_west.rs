@@ -1,21 +1,42 @@
-use std::io::{BufRead, BufReader};
+use crate::cmd_zephyr;
+use crate::toolchain_manager;
 use std::path::Path;
-use std::process::{Command, Stdio};
-use std::thread;
-use tauri::{AppHandle, Emitter};
+use std::process::Command;
+use tauri::AppHandle;
 
-#[tauri::command]
-pub async fn run_west_command(args: Vec<String>, cwd: Option<String>) -> Result<String, String> {
-    let mut command = Command::new("west");
-    command.args(&args);
+/// Build a `west` invocation that runs with the toolchain bound to the
+/// project at `cwd` (or the default toolchain when there is none).
+pub(crate) fn west_command(app: &AppHandle, args: &[String], cwd: Option<&str>) -> Command {
+    let toolchain = toolchain_manager::resolve_for_app(app, cwd);
+    let program = toolchain
+        .as_ref()
+        .map(|t| t.west_program())
+        .unwrap_or_else(|| "west".to_string());
+
+    let mut command = Command::new(program);
+    command.args(args);
 
+    if let Some(t) = &toolchain {
+        t.apply_env(&mut command);
+    }
     if let Some(path) = cwd {
-        command.current_dir(Path::new(&path));
+        command.current_dir(Path::new(path));
     }
+    command
+}
+
+#[tauri::command]
+pub async fn run_west_command(
+    app: AppHandle,
+    args: Vec<String>,
+    cwd: Option<String>,
+) -> Result<String, String> {
+    let mut command = west_command(&app, &args, cwd.as_deref());
 
-    // TODO: In the future, we might want to stream output instead of waiting for it.
-    // For now, we capture output.
+    // Captured rather than streamed: callers parse the output, and it
+    // should not show up in the terminal
     let output = command.output().map_err(|e| e.to_string())?;
+    cmd_zephyr::record_captured_failure(&command, &output);
 
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
@@ -35,42 +56,8 @@ pub async fn run_west_stream(
     args: Vec<String>,
     cwd: Option<String>,
 ) -> Result<(), String> {
-    let mut command = Command::new("west");
-    command.args(&args);
-
-    if let Some(path) = cwd {
-        command.current_dir(Path::new(&path));
-    }
-
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-
-    let mut child = command.spawn().map_err(|e| e.to_string())?;
-
-    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to open stderr")?;
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(l) = line {
-                let _ = app_handle.emit("term-data", format!("{}\r\n", l));
-            }
-        }
-    });
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(l) = line {
-                let _ = app_handle.emit("term-data", format!("{}\r\n", l));
-            }
-        }
-    });
-
-    let status = child.wait().map_err(|e| e.to_string())?;
+    let command = west_command(&app, &args, cwd.as_deref());
+    let status = cmd_zephyr::stream_command(&app, command)?;
 
     if status.success() {
         Ok(())
@@ -80,9 +67,9 @@ pub async fn run_west_stream(
 }
 
 #[tauri::command]
-pub async fn west_init(url: String, path: String) -> Result<String, String> {
+pub async fn west_init(app: AppHandle, url: String, path: String) -> Result<String, String> {
     // west init -m <url> <path>
     let args = vec!["init".to_string(), "-m".to_string(), url, path];
 
-    run_west_command(args, None).await
+    run_west_command(app, args, None).await
 }
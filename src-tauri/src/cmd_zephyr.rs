@@ -1,9 +1,149 @@
-use std::io::{BufRead, BufReader};
+use crate::toolchain_manager;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter};
 
+/// A streamed command that exited unsuccessfully, kept for diagnostics.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FailedJob {
+    pub command: String,
+    pub cwd: Option<String>,
+    pub status: String,
+    pub finished_at: u64, // Unix timestamp
+    /// Last lines of combined stdout/stderr
+    pub output_tail: Vec<String>,
+}
+
+const MAX_FAILED_JOBS: usize = 10;
+const FAILED_JOB_TAIL_LINES: usize = 200;
+
+static FAILED_JOBS: Mutex<VecDeque<FailedJob>> = Mutex::new(VecDeque::new());
+
+/// Most recent failures first.
+pub(crate) fn recent_failed_jobs() -> Vec<FailedJob> {
+    FAILED_JOBS
+        .lock()
+        .map(|jobs| jobs.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn record_failed_job(command: &Command, status: ExitStatus, output_tail: Vec<String>) {
+    let job = FailedJob {
+        command: command_line(command),
+        cwd: command
+            .get_current_dir()
+            .map(|c| c.to_string_lossy().to_string()),
+        status: status.to_string(),
+        finished_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        output_tail,
+    };
+    if let Ok(mut jobs) = FAILED_JOBS.lock() {
+        if jobs.len() == MAX_FAILED_JOBS {
+            jobs.pop_front();
+        }
+        jobs.push_back(job);
+    }
+}
+
+/// Record a command run with `Command::output` if it failed, without
+/// showing anything in the terminal.
+pub(crate) fn record_captured_failure(command: &Command, output: &Output) {
+    if output.status.success() {
+        return;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<String> = stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::to_string)
+        .collect();
+    let tail = lines[lines.len().saturating_sub(FAILED_JOB_TAIL_LINES)..].to_vec();
+    record_failed_job(command, output.status, tail);
+}
+
+fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|a| a.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Forward a child's output to the terminal, keeping its last lines.
+fn stream_output(
+    app: &AppHandle,
+    output: impl Read + Send + 'static,
+    tail: &Arc<Mutex<VecDeque<String>>>,
+) -> thread::JoinHandle<()> {
+    let app_handle = app.clone();
+    let tail = Arc::clone(tail);
+    thread::spawn(move || {
+        let reader = BufReader::new(output);
+        for l in reader.lines().map_while(Result::ok) {
+            let _ = app_handle.emit("term-data", format!("{}\r\n", l));
+            if let Ok(mut tail) = tail.lock() {
+                if tail.len() == FAILED_JOB_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(l);
+            }
+        }
+    })
+}
+
+/// Run a prepared command, streaming its output to the terminal. Every
+/// unsuccessful exit is kept for the doctor report.
+pub(crate) fn stream_command(app: &AppHandle, mut command: Command) -> Result<ExitStatus, String> {
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::piped());
+
+    // On Windows, we need to hide the console window if we are not using a terminal emulator
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to spawn {}: {}", command_line(&command), e))?;
+
+    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to open stderr")?;
+
+    let tail = Arc::new(Mutex::new(VecDeque::new()));
+    let readers = [
+        stream_output(app, stdout, &tail),
+        stream_output(app, stderr, &tail),
+    ];
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    for reader in readers {
+        let _ = reader.join();
+    }
+
+    if !status.success() {
+        let tail = tail
+            .lock()
+            .map(|t| t.iter().cloned().collect())
+            .unwrap_or_default();
+        record_failed_job(&command, status, tail);
+    }
+
+    Ok(status)
+}
+
 fn setup_pip_source(app: &AppHandle, install_path: &str, venv_python: &str) -> Result<(), String> {
     // First, upgrade pip with the USTC mirror
     emit_log(app, "Upgrading pip with USTC source...");
@@ -47,6 +187,7 @@ pub async fn install_zephyr(
     install_path: String,
     sdk_path: Option<String>,
     shadow_clone: bool,
+    sdk_toolchains: Option<Vec<String>>,
 ) -> Result<(), String> {
     let path = Path::new(&install_path);
     if !path.exists() {
@@ -163,12 +304,18 @@ pub async fn install_zephyr(
     // `west sdk install` has flags:
     // -b BASE (Base directory to SDK install)
     // -d DIR (SDK install destination directory)
+    // -t toolchain_name... (only install these toolchains instead of all of them)
     emit_log(&app, "Installing Zephyr SDK...");
     let mut sdk_args = vec!["sdk", "install"];
     if let Some(ref s) = sdk_path {
         sdk_args.push("-d");
         sdk_args.push(s);
     }
+    let sdk_toolchains = sdk_toolchains.unwrap_or_default();
+    if !sdk_toolchains.is_empty() {
+        sdk_args.push("-t");
+        sdk_args.extend(sdk_toolchains.iter().map(|t| t.as_str()));
+    }
 
     // We need to run this inside zephyrproject/zephyr usually?
     // Guide says: cd ~/zephyrproject/zephyr; west sdk install
@@ -177,20 +324,45 @@ pub async fn install_zephyr(
 
     run_command_stream(&app, &venv_west_str, &sdk_args, Some(&zephyr_repo_path_str))?;
 
+    // 8. Register the new workspace as a toolchain profile
+    if let Some(profile) = toolchain_manager::register_workspace(&app, path, sdk_path)? {
+        emit_log(
+            &app,
+            &format!("Registered toolchain profile: {}", profile.name),
+        );
+    }
+
     emit_log(&app, "Zephyr installation complete!");
     Ok(())
 }
 
-fn emit_log(app: &AppHandle, msg: &str) {
+pub(crate) fn emit_log(app: &AppHandle, msg: &str) {
     let _ = app.emit("term-data", format!("{}\r\n", msg));
 }
 
-fn run_command_stream(
+pub(crate) fn run_command_stream(
     app: &AppHandle,
     cmd: &str,
     args: &[&str],
     cwd: Option<&str>,
 ) -> Result<(), String> {
+    let status = run_command_stream_status(app, cmd, args, cwd)?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Command {} failed with status {}", cmd, status))
+    }
+}
+
+/// Like [`run_command_stream`], but hands the exit status back to callers
+/// that need to tell failure modes apart.
+pub(crate) fn run_command_stream_status(
+    app: &AppHandle,
+    cmd: &str,
+    args: &[&str],
+    cwd: Option<&str>,
+) -> Result<ExitStatus, String> {
     let mut command = Command::new(cmd);
     command.args(args);
 
@@ -226,49 +398,5 @@ fn run_command_stream(
         );
     }
 
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-
-    // On Windows, we need to hide the console window if we are not using a terminal emulator
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-        command.creation_flags(CREATE_NO_WINDOW);
-    }
-
-    let mut child = command
-        .spawn()
-        .map_err(|e| format!("Failed to spawn {}: {}", cmd, e))?;
-
-    let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
-    let stderr = child.stderr.take().ok_or("Failed to open stderr")?;
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(l) = line {
-                let _ = app_handle.emit("term-data", format!("{}\r\n", l));
-            }
-        }
-    });
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(l) = line {
-                let _ = app_handle.emit("term-data", format!("{}\r\n", l));
-            }
-        }
-    });
-
-    let status = child.wait().map_err(|e| e.to_string())?;
-
-    if status.success() {
-        Ok(())
-    } else {
-        Err(format!("Command {} failed with status {}", cmd, status))
-    }
+    stream_command(app, command)
 }
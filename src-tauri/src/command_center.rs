@@ -0,0 +1,351 @@
+use crate::config_manager;
+use crate::frame_codec::{self, FrameSchema};
+use crate::serial_comm;
+use crate::serial_log;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// What the user typed into the Command Center.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandPayload {
+    /// Sent as one line of compact JSON
+    Json {
+        value: Value,
+    },
+    Hex {
+        hex: String,
+    },
+    /// Field values of a schema frame, picked by the frame's name
+    Frame {
+        frame: String,
+        fields: Map<String, Value>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CommandTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub payload: CommandPayload,
+    /// Wrap JSON/hex payloads in a frame with this cmd_id
+    #[serde(default)]
+    pub cmd_id: Option<u16>,
+}
+
+/// Bytes to put on the wire. JSON and hex are sent as-is unless `cmd_id`
+/// asks for a frame; schema frames are always framed.
+pub fn encode_payload(
+    payload: &CommandPayload,
+    cmd_id: Option<u16>,
+    seq: u8,
+    schemas: &[FrameSchema],
+) -> Result<Vec<u8>, String> {
+    let (data, cmd_id) = match payload {
+        CommandPayload::Json { value } => {
+            let mut data = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+            if cmd_id.is_none() {
+                data.push(b'\n');
+            }
+            (data, cmd_id)
+        }
+        CommandPayload::Hex { hex } => (frame_codec::parse_hex(hex)?, cmd_id),
+        CommandPayload::Frame { frame, fields } => {
+            let schema = schemas
+                .iter()
+                .find(|s| s.name == *frame)
+                .ok_or_else(|| format!("No frame schema named {}", frame))?;
+            (
+                frame_codec::encode_fields(schema, fields)?,
+                Some(schema.cmd_id),
+            )
+        }
+    };
+    match cmd_id {
+        Some(cmd_id) => frame_codec::encode_frame(cmd_id, seq, &data),
+        None => Ok(data),
+    }
+}
+
+/// Per-port frame sequence numbers.
+static SEQUENCES: Mutex<BTreeMap<String, u8>> = Mutex::new(BTreeMap::new());
+
+fn next_seq(port: &str) -> u8 {
+    let mut sequences = SEQUENCES.lock().unwrap();
+    let seq = sequences.entry(port.to_string()).or_insert(0);
+    let current = *seq;
+    *seq = seq.wrapping_add(1);
+    current
+}
+
+/// Schemas from `schema_path`, else those of the port's frame decoder.
+fn schemas_for(port: &str, schema_path: Option<String>) -> Result<Vec<FrameSchema>, String> {
+    match schema_path {
+        Some(path) => frame_codec::load_schemas(Path::new(&path)),
+        None => Ok(frame_codec::attached_schemas(port).unwrap_or_default()),
+    }
+}
+
+/// Preview the bytes a command would send.
+#[tauri::command]
+pub fn encode_serial_command(
+    port: String,
+    payload: CommandPayload,
+    cmd_id: Option<u16>,
+    schema_path: Option<String>,
+) -> Result<Vec<u8>, String> {
+    let schemas = schemas_for(&port, schema_path)?;
+    encode_payload(&payload, cmd_id, 0, &schemas)
+}
+
+/// Encode and write a command to an open port, returning the bytes sent.
+#[tauri::command]
+pub fn send_serial_command(
+    port: String,
+    payload: CommandPayload,
+    cmd_id: Option<u16>,
+    schema_path: Option<String>,
+) -> Result<Vec<u8>, String> {
+    let schemas = schemas_for(&port, schema_path)?;
+    let bytes = encode_payload(&payload, cmd_id, next_seq(&port), &schemas)?;
+    serial_comm::write_port(&port, &bytes)?;
+    Ok(bytes)
+}
+
+#[tauri::command]
+pub fn list_command_templates(
+    app: AppHandle,
+    project_path: String,
+) -> Result<Vec<CommandTemplate>, String> {
+    let config = config_manager::get_config(app)?;
+    Ok(config
+        .command_templates
+        .get(&project_path)
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// Add a template to a project, replacing one with the same name.
+#[tauri::command]
+pub fn save_command_template(
+    app: AppHandle,
+    project_path: String,
+    template: CommandTemplate,
+) -> Result<(), String> {
+    if template.name.trim().is_empty() {
+        return Err("Template name is empty".to_string());
+    }
+    let mut config = config_manager::get_config(app.clone())?;
+    let templates = config.command_templates.entry(project_path).or_default();
+    match templates.iter_mut().find(|t| t.name == template.name) {
+        Some(existing) => *existing = template,
+        None => templates.push(template),
+    }
+    config_manager::save_config(app, config)
+}
+
+#[tauri::command]
+pub fn delete_command_template(
+    app: AppHandle,
+    project_path: String,
+    name: String,
+) -> Result<(), String> {
+    let mut config = config_manager::get_config(app.clone())?;
+    if let Some(templates) = config.command_templates.get_mut(&project_path) {
+        templates.retain(|t| t.name != name);
+        if templates.is_empty() {
+            config.command_templates.remove(&project_path);
+        }
+    }
+    config_manager::save_config(app, config)
+}
+
+/// When each record is due, relative to the first, at `speed` times the
+/// original rate.
+pub fn replay_offsets(timestamps: &[u64], speed: f64) -> Vec<Duration> {
+    let start = timestamps.first().copied().unwrap_or(0);
+    timestamps
+        .iter()
+        .map(|&t| Duration::from_secs_f64(t.saturating_sub(start) as f64 / 1000.0 / speed))
+        .collect()
+}
+
+/// Payload of the `replay-status` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReplayStatus {
+    pub port: String,
+    pub sent: usize,
+    pub total: usize,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+static REPLAYS: Mutex<BTreeMap<String, Arc<AtomicBool>>> = Mutex::new(BTreeMap::new());
+
+/// Write a recorded capture to `port`, keeping the original gaps between
+/// chunks divided by `speed` (2.0 = twice as fast).
+#[tauri::command]
+pub fn replay_serial_capture(
+    app: AppHandle,
+    port: String,
+    path: String,
+    speed: Option<f64>,
+) -> Result<(), String> {
+    let speed = speed.unwrap_or(1.0);
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(format!("Invalid replay speed {}", speed));
+    }
+    let records = serial_log::read_capture(Path::new(&path))?;
+    let offsets = replay_offsets(
+        &records.iter().map(|r| r.timestamp).collect::<Vec<_>>(),
+        speed,
+    );
+
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let mut replays = REPLAYS.lock().unwrap();
+        if replays.contains_key(&port) {
+            return Err(format!("A replay is already running on {}", port));
+        }
+        replays.insert(port.clone(), stop.clone());
+    }
+
+    thread::spawn(move || {
+        let total = records.len();
+        let status = |sent: usize, finished: bool, error: Option<String>| ReplayStatus {
+            port: port.clone(),
+            sent,
+            total,
+            finished,
+            error,
+        };
+        let start = Instant::now();
+        let mut sent = 0;
+        let mut error = None;
+
+        for (record, offset) in records.iter().zip(offsets) {
+            let wait = offset.saturating_sub(start.elapsed());
+            serial_comm::wait_unless_stopped(&stop, wait);
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if let Err(e) = serial_comm::write_port(&port, &record.data) {
+                error = Some(e);
+                break;
+            }
+            sent += 1;
+            if sent % 50 == 0 {
+                let _ = app.emit("replay-status", status(sent, false, None));
+            }
+        }
+
+        REPLAYS
+            .lock()
+            .unwrap()
+            .retain(|_, s| !Arc::ptr_eq(s, &stop));
+        let _ = app.emit("replay-status", status(sent, true, error));
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_serial_replay(port: String) -> Result<(), String> {
+    REPLAYS
+        .lock()
+        .unwrap()
+        .get(&port)
+        .map(|stop| stop.store(true, Ordering::Relaxed))
+        .ok_or_else(|| format!("No replay running on {}", port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schemas() -> Vec<FrameSchema> {
+        frame_codec::parse_schemas(
+            r#"{"frames": [{
+                "cmd_id": 260,
+                "name": "gimbal_cmd",
+                "fields": [
+                    { "name": "yaw", "type": "i16", "scale": 0.01 },
+                    { "name": "fire", "type": "bool" }
+                ]
+            }]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn encodes_json_lines_and_framed_json() {
+        let payload = CommandPayload::Json {
+            value: serde_json::json!({"mode": "auto", "speed": 2}),
+        };
+        assert_eq!(
+            encode_payload(&payload, None, 0, &[]).unwrap(),
+            b"{\"mode\":\"auto\",\"speed\":2}\n"
+        );
+
+        let framed = encode_payload(&payload, Some(0x0200), 5, &[]).unwrap();
+        let mut decoder = frame_codec::FrameDecoder::new("COM3", Vec::new());
+        let frames = decoder.push(&framed, 0);
+        assert_eq!(frames[0].cmd_id, 0x0200);
+        assert_eq!(frames[0].seq, 5);
+        assert_eq!(frames[0].data, b"{\"mode\":\"auto\",\"speed\":2}");
+    }
+
+    #[test]
+    fn encodes_hex_as_is() {
+        let payload = CommandPayload::Hex {
+            hex: "01 02 ff".to_string(),
+        };
+        assert_eq!(
+            encode_payload(&payload, None, 0, &[]).unwrap(),
+            [1, 2, 0xFF]
+        );
+    }
+
+    #[test]
+    fn encodes_schema_frames() {
+        let payload: CommandPayload = serde_json::from_str(
+            r#"{"kind": "frame", "frame": "gimbal_cmd", "fields": {"yaw": -12.34, "fire": true}}"#,
+        )
+        .unwrap();
+        let bytes = encode_payload(&payload, None, 9, &schemas()).unwrap();
+
+        let mut decoder = frame_codec::FrameDecoder::new("COM3", schemas());
+        let frames = decoder.push(&bytes, 0);
+        assert_eq!(frames[0].cmd_id, 260);
+        assert_eq!(frames[0].data, [0x2E, 0xFB, 0x01]); // -1234 LE, true
+        assert_eq!(frames[0].fields["fire"], true);
+
+        let unknown = CommandPayload::Frame {
+            frame: "chassis_cmd".to_string(),
+            fields: Map::new(),
+        };
+        assert!(encode_payload(&unknown, None, 0, &schemas()).is_err());
+    }
+
+    #[test]
+    fn scales_replay_timing() {
+        let offsets = replay_offsets(&[1_000, 1_000, 1_500, 3_000], 2.0);
+        assert_eq!(
+            offsets,
+            [
+                Duration::ZERO,
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_millis(1000)
+            ]
+        );
+        assert!(replay_offsets(&[], 1.0).is_empty());
+    }
+}
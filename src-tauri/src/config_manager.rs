@@ -1,8 +1,14 @@
+use crate::cmd_zephyr;
+use crate::command_center::CommandTemplate;
+use crate::executor::SystemExecutor;
+use crate::sdk_manager;
+use crate::toolchain_manager::{self, ToolchainProfile};
+use crate::venv_manager;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::fs::File;
 use std::io::BufRead;
-use std::io::BufReader;
 use std::io::BufReader as StdBufReader;
 use std::path::PathBuf;
 use tauri::AppHandle;
@@ -27,6 +33,19 @@ pub struct UserConfig {
     pub recent_projects: Vec<String>, // Legacy field, keeping for compatibility
     #[serde(default)]
     pub project_history: Vec<ProjectMetadata>,
+    #[serde(default)]
+    pub toolchains: Vec<ToolchainProfile>,
+    #[serde(default)]
+    pub default_toolchain: Option<String>,
+    /// Project path -> toolchain profile id
+    #[serde(default)]
+    pub project_toolchains: BTreeMap<String, String>,
+    /// Extra directories searched for Zephyr SDKs
+    #[serde(default)]
+    pub sdk_search_roots: Vec<String>,
+    /// Project path -> saved Command Center templates
+    #[serde(default)]
+    pub command_templates: BTreeMap<String, Vec<CommandTemplate>>,
 }
 
 #[tauri::command]
@@ -166,10 +185,21 @@ pub async fn create_project(
     project_name: String,
     workspace_path: String,
     shallow_clone: bool,
+    toolchain_id: Option<String>,
 ) -> Result<(), String> {
     // First, save the project information to config
     let mut config = get_config(app.clone()).map_err(|e| format!("获取配置失败: {}", e))?;
 
+    // Bind the new project to the chosen toolchain profile
+    if let Some(id) = toolchain_id {
+        if !config.toolchains.iter().any(|p| p.id == id) {
+            return Err(format!("未知的工具链: {}", id));
+        }
+        config.project_toolchains.insert(workspace_path.clone(), id);
+    }
+    let zephyr_version = toolchain_manager::resolve_for_project(&config, Some(&workspace_path))
+        .and_then(|t| t.zephyr_version);
+
     // Add project to recent projects and history
     if let Some(pos) = config
         .recent_projects
@@ -201,7 +231,7 @@ pub async fn create_project(
             name: project_name.clone(),
             last_opened: timestamp,
             project_type: Some("zephyr".to_string()),
-            zephyr_version: None,
+            zephyr_version,
         });
     }
 
@@ -219,9 +249,6 @@ pub async fn create_project(
     run_create_project_commands(app, workspace_path, shallow_clone).await
 }
 
-use std::process::Stdio;
-use std::thread;
-
 fn emit_log(app: &AppHandle, msg: &str) {
     let _ = app.emit("term-data", format!("{}\r\n", msg));
 }
@@ -233,8 +260,12 @@ async fn run_create_project_commands(
 ) -> Result<(), String> {
     let config = get_config(app.clone()).map_err(|e| format!("获取配置失败: {}", e))?;
 
-    // Check if venv path is configured
-    let venv_path = config.venv_path.ok_or("未配置虚拟环境路径".to_string())?;
+    // Use the toolchain bound to this project, falling back to the global settings
+    let toolchain = toolchain_manager::resolve_for_project(&config, Some(&workspace_path));
+    let venv_path = toolchain
+        .as_ref()
+        .and_then(|t| t.venv_path.clone())
+        .ok_or("未配置虚拟环境路径".to_string())?;
 
     // Determine workspace directory and project name
     let workspace_dir = Path::new(&workspace_path);
@@ -293,65 +324,14 @@ async fn run_create_project_commands(
     init_command.arg("-c").arg(&init_cmd);
 
     init_command.current_dir(workspace_parent);
-    init_command.stdout(Stdio::piped());
-    init_command.stderr(Stdio::piped());
     init_command.env("TERM", "xterm");
+    if let Some(t) = &toolchain {
+        t.apply_env(&mut init_command);
+    }
 
-    let mut init_child = init_command
-        .spawn()
+    let init_status = cmd_zephyr::stream_command(&app, init_command)
         .map_err(|e| format!("启动west init失败: {}", e))?;
 
-    let init_stdout = init_child.stdout.take().ok_or("Failed to open stdout")?;
-    let init_stderr = init_child.stderr.take().ok_or("Failed to open stderr")?;
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let mut reader = BufReader::new(init_stdout);
-        let mut buffer = String::new();
-        loop {
-            match reader.read_line(&mut buffer) {
-                Ok(0) => break, // EOF
-                Ok(_) => {
-                    if buffer.ends_with('\n') || buffer.ends_with('\r') {
-                        let _ = app_handle.emit("term-data", buffer.clone());
-                        buffer.clear();
-                    }
-                }
-                Err(_) => break,
-            }
-        }
-        // 发送剩余内容（如果没有换行符结尾）
-        if !buffer.is_empty() {
-            let _ = app_handle.emit("term-data", buffer.clone());
-        }
-    });
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let mut reader = BufReader::new(init_stderr);
-        let mut buffer = String::new();
-        loop {
-            match reader.read_line(&mut buffer) {
-                Ok(0) => break, // EOF
-                Ok(_) => {
-                    if buffer.ends_with('\n') || buffer.ends_with('\r') {
-                        let _ = app_handle.emit("term-data", buffer.clone());
-                        buffer.clear();
-                    }
-                }
-                Err(_) => break,
-            }
-        }
-        // 发送剩余内容（如果没有换行符结尾）
-        if !buffer.is_empty() {
-            let _ = app_handle.emit("term-data", buffer.clone());
-        }
-    });
-
-    let init_status = init_child
-        .wait()
-        .map_err(|e| format!("等待west init完成失败: {}", e))?;
-
     if !init_status.success() {
         return Err("west init 失败".to_string());
     }
@@ -377,65 +357,14 @@ async fn run_create_project_commands(
     update_command.arg("-c").arg(&update_cmd);
 
     update_command.current_dir(&workspace_full_path);
-    update_command.stdout(Stdio::piped());
-    update_command.stderr(Stdio::piped());
     update_command.env("TERM", "xterm");
+    if let Some(t) = &toolchain {
+        t.apply_env(&mut update_command);
+    }
 
-    let mut update_child = update_command
-        .spawn()
+    let update_status = cmd_zephyr::stream_command(&app, update_command)
         .map_err(|e| format!("启动west update失败: {}", e))?;
 
-    let update_stdout = update_child.stdout.take().ok_or("Failed to open stdout")?;
-    let update_stderr = update_child.stderr.take().ok_or("Failed to open stderr")?;
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let mut reader = BufReader::new(update_stdout);
-        let mut buffer = String::new();
-        loop {
-            match reader.read_line(&mut buffer) {
-                Ok(0) => break, // EOF
-                Ok(_) => {
-                    if buffer.ends_with('\n') || buffer.ends_with('\r') {
-                        let _ = app_handle.emit("term-data", buffer.clone());
-                        buffer.clear();
-                    }
-                }
-                Err(_) => break,
-            }
-        }
-        // 发送剩余内容（如果没有换行符结尾）
-        if !buffer.is_empty() {
-            let _ = app_handle.emit("term-data", buffer.clone());
-        }
-    });
-
-    let app_handle = app.clone();
-    thread::spawn(move || {
-        let mut reader = BufReader::new(update_stderr);
-        let mut buffer = String::new();
-        loop {
-            match reader.read_line(&mut buffer) {
-                Ok(0) => break, // EOF
-                Ok(_) => {
-                    if buffer.ends_with('\n') || buffer.ends_with('\r') {
-                        let _ = app_handle.emit("term-data", buffer.clone());
-                        buffer.clear();
-                    }
-                }
-                Err(_) => break,
-            }
-        }
-        // 发送剩余内容（如果没有换行符结尾）
-        if !buffer.is_empty() {
-            let _ = app_handle.emit("term-data", buffer.clone());
-        }
-    });
-
-    let update_status = update_child
-        .wait()
-        .map_err(|e| format!("等待west update完成失败: {}", e))?;
-
     if !update_status.success() {
         return Err("west update 失败".to_string());
     }
@@ -590,50 +519,34 @@ pub fn get_home_dir() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn detect_zephyr_sdk_path() -> Result<Option<String>, String> {
-    let home_dir = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    let home_path = std::path::Path::new(&home_dir);
-
-    // Look for directories matching zephyr-sdk-* pattern
-    let sdk_dir_pattern = home_path.join("zephyr-sdk-*");
-
-    // Instead of globbing, let's check for common SDK directory patterns
-    // List the contents of the home directory and find zephyr-sdk directories
-    let entries = std::fs::read_dir(home_path)
-        .map_err(|e| format!("无法读取主目录: {}", e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-        let file_name = entry.file_name();
-        let file_name_str = file_name.to_string_lossy();
-
-        if file_name_str.starts_with("zephyr-sdk-") && entry.path().is_dir() {
-            // This is a zephyr-sdk directory, let's check if it looks like a valid SDK
-            let path = entry.path();
-            // Check if there's a top-level directory with executables or other SDK markers
-            if path.join("zephyr-sdk").exists() || path.join("sysroots").exists() || path.join("toolchain").exists() {
-                return Ok(Some(path.to_string_lossy().to_string()));
-            }
-        }
-    }
+pub fn detect_zephyr_sdk_path(app: AppHandle) -> Result<Option<String>, String> {
+    let config = get_config(app)?;
 
-    // If no valid SDK directory found, return None
-    Ok(None)
+    // Candidates are ranked best-first; only hand out one that actually works
+    let best = sdk_manager::discover_sdks(&config)
+        .into_iter()
+        .find(|sdk| sdk.valid)
+        .map(|sdk| sdk.path);
+    Ok(best)
 }
 
 #[tauri::command]
-pub fn detect_venv_path() -> Result<Option<String>, String> {
-    let home_dir = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    let venv_path = std::path::Path::new(&home_dir).join("zephyrproject").join(".venv");
-
-    if venv_path.exists() && venv_path.is_dir() {
-        Ok(Some(venv_path.to_string_lossy().to_string()))
-    } else {
-        Ok(None)
-    }
+pub fn detect_venv_path(
+    app: AppHandle,
+    workspace_path: Option<String>,
+) -> Result<Option<String>, String> {
+    let config = get_config(app)?;
+
+    // Candidates are ranked best-first; only suggest one that can run west
+    let workspace = workspace_path.as_deref().map(Path::new);
+    let best = venv_manager::discover_venvs(&SystemExecutor, &config, workspace)
+        .into_iter()
+        .find(|venv| venv.valid)
+        .map(|venv| venv.path);
+    Ok(best)
 }
 
-fn get_config_path(app: &AppHandle) -> Result<PathBuf, String> {
+pub(crate) fn get_config_path(app: &AppHandle) -> Result<PathBuf, String> {
     app.path()
         .app_config_dir()
         .map(|p| p.join("config.json"))
@@ -0,0 +1,491 @@
+use crate::executor::{CommandExecutor, SystemExecutor};
+use crate::serial_log;
+use crate::toolchain_manager::{self, ToolchainProfile};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MARKER: &str = "#CD:";
+/// Longest line kept while looking for markers
+const MAX_LINE_BYTES: usize = 4096;
+
+/// Collects the hex dump the coredump logging backend prints between
+/// `#CD:BEGIN#` and `#CD:END#`, whatever log prefix precedes the markers.
+#[derive(Default)]
+pub struct CoredumpScanner {
+    line: Vec<u8>,
+    hex: Option<String>,
+}
+
+impl CoredumpScanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns every dump completed by `data`.
+    pub fn push(&mut self, data: &[u8]) -> Vec<Result<Vec<u8>, String>> {
+        let mut dumps = Vec::new();
+        for &b in data {
+            if b != b'\n' {
+                if self.line.len() < MAX_LINE_BYTES {
+                    self.line.push(b);
+                }
+                continue;
+            }
+            let line = String::from_utf8_lossy(&self.line).to_string();
+            self.line.clear();
+            if let Some(dump) = self.line_done(&line) {
+                dumps.push(dump);
+            }
+        }
+        dumps
+    }
+
+    fn line_done(&mut self, line: &str) -> Option<Result<Vec<u8>, String>> {
+        let record = &line[line.find(MARKER)? + MARKER.len()..];
+        if record.starts_with("BEGIN#") {
+            self.hex = Some(String::new());
+            return None;
+        }
+        if record.starts_with("ERROR") {
+            self.hex = None;
+            let message = record.split('#').next().unwrap_or(record);
+            return Some(Err(format!("Target could not dump: {}", message)));
+        }
+        let hex = self.hex.as_mut()?;
+        if record.starts_with("END#") {
+            let hex = self.hex.take().unwrap();
+            return Some(decode_hex(&hex));
+        }
+        // Anything after the digits is the log backend's line ending/colors
+        hex.extend(record.chars().take_while(char::is_ascii_hexdigit));
+        None
+    }
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) {
+        return Err("Coredump is truncated (odd number of hex digits)".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// The `coredump_hdr_t` every dump starts with.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CoredumpHeader {
+    pub version: u16,
+    pub target_code: u16,
+    pub target: String,
+    pub pointer_bits: u32,
+    pub reason: String,
+}
+
+pub fn parse_header(dump: &[u8]) -> Result<CoredumpHeader, String> {
+    if dump.len() < 12 || &dump[..2] != b"ZE" {
+        return Err("Not a Zephyr coredump (missing ZE header)".to_string());
+    }
+    let u16_at = |i: usize| u16::from_le_bytes([dump[i], dump[i + 1]]);
+    let target_code = u16_at(4);
+    let reason = u32::from_le_bytes(dump[8..12].try_into().unwrap());
+    Ok(CoredumpHeader {
+        version: u16_at(2),
+        target_code,
+        target: target_name(target_code).to_string(),
+        pointer_bits: 1 << dump[6].min(6),
+        reason: match reason {
+            0 => "CPU exception".to_string(),
+            1 => "Spurious interrupt".to_string(),
+            2 => "Stack check failure".to_string(),
+            3 => "Kernel oops".to_string(),
+            4 => "Kernel panic".to_string(),
+            other => format!("Reason {}", other),
+        },
+    })
+}
+
+fn target_name(code: u16) -> &'static str {
+    match code {
+        1 => "x86",
+        2 => "x86_64",
+        3 => "arm_cortex_m",
+        4 => "riscv",
+        5 => "xtensa",
+        6 => "arm64",
+        _ => "unknown",
+    }
+}
+
+/// Zephyr SDK toolchain whose gdb understands the target.
+fn gdb_prefix(target_code: u16) -> Option<&'static str> {
+    match target_code {
+        1 | 2 => Some("x86_64-zephyr-elf"),
+        3 => Some("arm-zephyr-eabi"),
+        4 => Some("riscv64-zephyr-elf"),
+        6 => Some("aarch64-zephyr-elf"),
+        _ => None,
+    }
+}
+
+/// The SDK's gdb for the target, else one on PATH (`gdb-multiarch` first).
+pub fn find_gdb(
+    exec: &dyn CommandExecutor,
+    sdk_path: Option<&Path>,
+    target_code: u16,
+) -> Option<String> {
+    let prefix = gdb_prefix(target_code);
+    if let (Some(sdk), Some(prefix)) = (sdk_path, prefix) {
+        let gdb = toolchain_manager::exe_name(&format!("{}-gdb", prefix));
+        // SDK 0.17+ keeps toolchains under gnu/
+        for dir in [sdk.join(prefix), sdk.join("gnu").join(prefix)] {
+            let path = dir.join("bin").join(&gdb);
+            if exec.exists(&path) {
+                return Some(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    prefix
+        .map(|p| format!("{}-gdb", p))
+        .into_iter()
+        .chain(["gdb-multiarch".to_string(), "gdb".to_string()])
+        .find_map(|name| exec.which(&name))
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// `zephyr.elf` given the file, a build directory or a project.
+pub fn find_elf(path: &Path) -> Result<PathBuf, String> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    [
+        path.join("zephyr").join("zephyr.elf"),
+        path.join("build").join("zephyr").join("zephyr.elf"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .ok_or_else(|| format!("No zephyr.elf under {}", path.display()))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RegisterValue {
+    pub name: String,
+    pub value: String,
+    /// gdb's natural rendering, e.g. a symbol for pc
+    pub natural: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CoredumpReport {
+    pub header: CoredumpHeader,
+    pub backtrace: Vec<String>,
+    pub registers: Vec<RegisterValue>,
+    /// Full gdb output
+    pub output: String,
+}
+
+fn parse_gdb_output(output: &str) -> (Vec<String>, Vec<RegisterValue>) {
+    let mut backtrace = Vec::new();
+    let mut registers = Vec::new();
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') && trimmed[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            backtrace.push(trimmed.to_string());
+            continue;
+        }
+        let mut parts = trimmed.split_whitespace();
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if value.starts_with("0x")
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                registers.push(RegisterValue {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    natural: parts.collect::<Vec<_>>().join(" "),
+                });
+            }
+        }
+    }
+    (backtrace, registers)
+}
+
+/// Serve the dump with Zephyr's `coredump_gdbserver.py` over a pipe and ask
+/// gdb for the backtrace and registers.
+pub fn analyze(
+    exec: &dyn CommandExecutor,
+    toolchain: &ToolchainProfile,
+    elf: &Path,
+    dump_path: &Path,
+) -> Result<CoredumpReport, String> {
+    let dump = std::fs::read(dump_path)
+        .map_err(|e| format!("Failed to read {}: {}", dump_path.display(), e))?;
+    let header = parse_header(&dump)?;
+
+    let script = Path::new(&toolchain.zephyr_base)
+        .join("scripts")
+        .join("coredump")
+        .join("coredump_gdbserver.py");
+    if !exec.exists(&script) {
+        return Err(format!("{} not found", script.display()));
+    }
+    let gdb = find_gdb(
+        exec,
+        toolchain.sdk_path.as_deref().map(Path::new),
+        header.target_code,
+    )
+    .ok_or_else(|| format!("No gdb found for {}", header.target))?;
+
+    let elf = elf.to_string_lossy();
+    let target = format!(
+        "target remote | \"{}\" \"{}\" --pipe \"{}\" \"{}\"",
+        toolchain.python_program(),
+        script.display(),
+        elf,
+        dump_path.display()
+    );
+    let output = exec
+        .run(
+            &gdb,
+            &[
+                "-batch",
+                "-nx",
+                &elf,
+                "-ex",
+                &target,
+                "-ex",
+                "bt",
+                "-ex",
+                "info registers",
+            ],
+        )
+        .ok_or_else(|| format!("Failed to start {}", gdb))?;
+    let text = format!("{}{}", output.stdout, output.stderr);
+
+    let (backtrace, registers) = parse_gdb_output(&output.stdout);
+    if backtrace.is_empty() {
+        return Err(format!("gdb produced no backtrace:\n{}", text.trim_end()));
+    }
+    Ok(CoredumpReport {
+        header,
+        backtrace,
+        registers,
+        output: text,
+    })
+}
+
+/// Payload of the `coredump` event.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CapturedCoredump {
+    pub port: String,
+    pub path: Option<String>,
+    pub size: usize,
+    pub header: Option<CoredumpHeader>,
+    pub error: Option<String>,
+}
+
+struct Watcher {
+    app: AppHandle,
+    dir: PathBuf,
+    scanner: CoredumpScanner,
+}
+
+static WATCHERS: Mutex<BTreeMap<String, Watcher>> = Mutex::new(BTreeMap::new());
+
+fn save(dir: &Path, port: &str, dump: &[u8]) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let stem = format!("{}_coredump", serial_log::file_stem(port));
+    let (mut file, path) = serial_log::create_file(dir, &stem, "bin", serial_log::now_millis())
+        .map_err(|e| e.to_string())?;
+    file.write_all(dump).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Look for coredumps in received bytes if the port is watched.
+pub(crate) fn feed(port: &str, data: &[u8]) {
+    let mut watchers = WATCHERS.lock().unwrap();
+    let Some(watcher) = watchers.get_mut(port) else {
+        return;
+    };
+    for dump in watcher.scanner.push(data) {
+        let mut captured = CapturedCoredump {
+            port: port.to_string(),
+            path: None,
+            size: 0,
+            header: None,
+            error: None,
+        };
+        match dump.and_then(|dump| {
+            captured.size = dump.len();
+            captured.header = parse_header(&dump).ok();
+            save(&watcher.dir, port, &dump)
+        }) {
+            Ok(path) => captured.path = Some(path.to_string_lossy().to_string()),
+            Err(e) => captured.error = Some(e),
+        }
+        let _ = watcher.app.emit("coredump", captured);
+    }
+}
+
+/// Save coredumps printed on a port (to `coredumps/` in the app data
+/// directory by default) and announce them with `coredump` events.
+#[tauri::command]
+pub fn watch_coredumps(
+    app: AppHandle,
+    port: String,
+    directory: Option<String>,
+) -> Result<(), String> {
+    let dir = match directory {
+        Some(dir) => PathBuf::from(dir),
+        None => app
+            .path()
+            .app_data_dir()
+            .map(|p| p.join("coredumps"))
+            .map_err(|e| e.to_string())?,
+    };
+    WATCHERS.lock().unwrap().insert(
+        port,
+        Watcher {
+            app,
+            dir,
+            scanner: CoredumpScanner::new(),
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unwatch_coredumps(port: String) -> Result<(), String> {
+    WATCHERS
+        .lock()
+        .unwrap()
+        .remove(&port)
+        .map(|_| ())
+        .ok_or_else(|| format!("Coredumps are not watched on {}", port))
+}
+
+/// Symbolize a saved dump against the project's `zephyr.elf`, using the
+/// project's toolchain. `elf_path` may be the ELF, a build directory or the
+/// project itself.
+#[tauri::command]
+pub async fn analyze_coredump(
+    app: AppHandle,
+    path: String,
+    elf_path: String,
+    project_path: Option<String>,
+) -> Result<CoredumpReport, String> {
+    let toolchain = toolchain_manager::resolve_for_app(&app, project_path.as_deref())
+        .ok_or("No Zephyr toolchain configured")?;
+    let elf = find_elf(Path::new(&elf_path))?;
+    analyze(&SystemExecutor, &toolchain, &elf, Path::new(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::FakeExecutor;
+
+    // ZE, version 1, ARM Cortex-M, 32-bit pointers, kernel panic
+    const HEADER: &str = "5a4501000300050004000000";
+
+    fn log_line(record: &str) -> String {
+        format!(
+            "\x1b[1;31m[00:00:05.120,000] <err> coredump: {}{}\x1b[0m\r\n",
+            MARKER, record
+        )
+    }
+
+    #[test]
+    fn collects_dump_between_markers() {
+        let mut text = "[00:00:05.118,000] <err> os: ***** HARD FAULT *****\r\n".to_string();
+        text.push_str(&log_line("BEGIN#"));
+        text.push_str(&log_line(&HEADER[..10]));
+        text.push_str(&log_line(&HEADER[10..]));
+        text.push_str(&log_line("END#"));
+
+        let mut scanner = CoredumpScanner::new();
+        // Split mid-line like serial reads do
+        let (a, b) = text.as_bytes().split_at(97);
+        assert!(scanner.push(a).is_empty());
+        let dumps = scanner.push(b);
+        assert_eq!(dumps.len(), 1);
+        let dump = dumps[0].as_ref().unwrap();
+        assert_eq!(dump, &decode_hex(HEADER).unwrap());
+
+        let header = parse_header(dump).unwrap();
+        assert_eq!(header.target, "arm_cortex_m");
+        assert_eq!(header.pointer_bits, 32);
+        assert_eq!(header.reason, "Kernel panic");
+    }
+
+    #[test]
+    fn reports_target_errors_and_ignores_stray_records() {
+        let mut scanner = CoredumpScanner::new();
+        assert!(scanner.push(log_line("0011").as_bytes()).is_empty());
+        let dumps = scanner.push(log_line("ERROR CANNOT DUMP#").as_bytes());
+        assert_eq!(
+            dumps,
+            [Err("Target could not dump: ERROR CANNOT DUMP".to_string())]
+        );
+        assert!(parse_header(b"garbage").is_err());
+    }
+
+    #[test]
+    fn runs_gdb_against_gdbserver() {
+        let dir = std::env::temp_dir().join(format!("coredump-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dump = dir.join("COM3_coredump.bin");
+        std::fs::write(&dump, decode_hex(HEADER).unwrap()).unwrap();
+
+        let toolchain = ToolchainProfile {
+            id: "t".to_string(),
+            name: "t".to_string(),
+            zephyr_base: "/zp/zephyr".to_string(),
+            venv_path: None,
+            sdk_path: Some("/sdk".to_string()),
+            zephyr_version: None,
+            sdk_version: None,
+        };
+        let python = toolchain.python_program();
+        let gdb = "/sdk/gnu/arm-zephyr-eabi/bin/arm-zephyr-eabi-gdb";
+        let command = format!(
+            "{} -batch -nx /app/build/zephyr/zephyr.elf -ex target remote | \"{}\" \
+             \"/zp/zephyr/scripts/coredump/coredump_gdbserver.py\" --pipe \
+             \"/app/build/zephyr/zephyr.elf\" \"{}\" -ex bt -ex info registers",
+            gdb,
+            python,
+            dump.display()
+        );
+        let exec = FakeExecutor::new()
+            .file("/zp/zephyr/scripts/coredump/coredump_gdbserver.py", "")
+            .program("arm-zephyr-eabi-gdb", gdb)
+            .output(
+                &command,
+                "0x0800123c in sensor_read (dev=0x0) at src/imu.c:42\n\
+                 #0  0x0800123c in sensor_read (dev=0x0) at src/imu.c:42\n\
+                 #1  0x08000f10 in main () at src/main.c:17\n\
+                 r0             0x0                 0\n\
+                 sp             0x20001f80          0x20001f80 <z_main_stack+1920>\n\
+                 pc             0x800123c           0x800123c <sensor_read+12>\n",
+            );
+
+        let report = analyze(
+            &exec,
+            &toolchain,
+            Path::new("/app/build/zephyr/zephyr.elf"),
+            &dump,
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.header.reason, "Kernel panic");
+        assert_eq!(report.backtrace.len(), 2);
+        assert!(report.backtrace[1].contains("main ()"));
+        assert_eq!(report.registers.len(), 3);
+        assert_eq!(report.registers[2].name, "pc");
+        assert_eq!(report.registers[2].natural, "0x800123c <sensor_read+12>");
+    }
+}
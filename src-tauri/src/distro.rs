@@ -0,0 +1,482 @@
+use crate::executor::CommandExecutor;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Linux distribution families that share a package manager and package names.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DistroFamily {
+    Debian,
+    Fedora,
+    Arch,
+    Suse,
+    Alpine,
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Distro {
+    pub id: String,
+    pub id_like: Vec<String>,
+    pub name: String,
+    pub version_id: Option<String>,
+    pub family: DistroFamily,
+    pub arch: String,
+}
+
+impl Distro {
+    /// 32-bit multilib packages only exist for x86_64 hosts.
+    pub fn supports_multilib(&self) -> bool {
+        self.arch == "x86_64"
+    }
+}
+
+/// Parse `/etc/os-release`. `ID` is tried before `ID_LIKE` so that e.g.
+/// Manjaro (`ID_LIKE=arch`) and Linux Mint (`ID_LIKE="ubuntu debian"`) resolve.
+pub fn parse_os_release(content: &str, arch: &str) -> Distro {
+    let mut id = String::new();
+    let mut id_like = Vec::new();
+    let mut name = String::new();
+    let mut version_id = None;
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').trim_matches('\'');
+        match key.trim() {
+            "ID" => id = value.to_lowercase(),
+            "ID_LIKE" => id_like = value.split_whitespace().map(|s| s.to_lowercase()).collect(),
+            "PRETTY_NAME" => name = value.to_string(),
+            "NAME" if name.is_empty() => name = value.to_string(),
+            "VERSION_ID" => version_id = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let family = std::iter::once(id.as_str())
+        .chain(id_like.iter().map(|s| s.as_str()))
+        .map(family_of)
+        .find(|f| *f != DistroFamily::Unknown)
+        .unwrap_or(DistroFamily::Unknown);
+
+    Distro {
+        id,
+        id_like,
+        name,
+        version_id,
+        family,
+        arch: arch.to_string(),
+    }
+}
+
+fn family_of(id: &str) -> DistroFamily {
+    match id {
+        "debian" | "ubuntu" | "linuxmint" | "pop" | "elementary" | "raspbian" | "deepin"
+        | "uos" | "kali" => DistroFamily::Debian,
+        "fedora" | "rhel" | "centos" | "rocky" | "almalinux" | "nobara" => DistroFamily::Fedora,
+        "arch" | "manjaro" | "endeavouros" | "garuda" | "cachyos" | "artix" => DistroFamily::Arch,
+        "opensuse" | "opensuse-leap" | "opensuse-tumbleweed" | "suse" | "sles" => {
+            DistroFamily::Suse
+        }
+        "alpine" => DistroFamily::Alpine,
+        _ => DistroFamily::Unknown,
+    }
+}
+
+pub fn detect(exec: &dyn CommandExecutor) -> Distro {
+    let content = exec
+        .read_to_string(Path::new("/etc/os-release"))
+        .or_else(|| exec.read_to_string(Path::new("/usr/lib/os-release")))
+        .unwrap_or_default();
+    parse_os_release(&content, std::env::consts::ARCH)
+}
+
+/// Package manager invocation for a family; the program and the arguments
+/// that precede the package list.
+pub fn install_command(family: DistroFamily) -> Option<(&'static str, &'static [&'static str])> {
+    match family {
+        DistroFamily::Debian => Some(("apt", &["install", "-y", "--no-install-recommends"])),
+        DistroFamily::Fedora => Some(("dnf", &["install", "-y"])),
+        DistroFamily::Arch => Some(("pacman", &["-S", "--needed", "--noconfirm"])),
+        DistroFamily::Suse => Some(("zypper", &["--non-interactive", "install"])),
+        DistroFamily::Alpine => Some(("apk", &["add"])),
+        DistroFamily::Unknown => None,
+    }
+}
+
+/// Version of an installed package, asking the family's package database.
+pub fn query_package(
+    exec: &dyn CommandExecutor,
+    family: DistroFamily,
+    package: &str,
+) -> Option<String> {
+    let (program, args): (&str, Vec<&str>) = match family {
+        DistroFamily::Debian => (
+            "dpkg-query",
+            vec!["-W", "-f=${Status}\t${Version}", package],
+        ),
+        DistroFamily::Fedora | DistroFamily::Suse => (
+            "rpm",
+            vec!["-q", "--qf", "%{VERSION}-%{RELEASE}\n", package],
+        ),
+        DistroFamily::Arch => ("pacman", vec!["-Q", package]),
+        DistroFamily::Alpine => ("apk", vec!["list", "--installed", package]),
+        DistroFamily::Unknown => return None,
+    };
+
+    let output = exec.run(program, &args)?;
+    if !output.success {
+        return None;
+    }
+    parse_query_output(family, package, &output.stdout)
+}
+
+/// Interpret the output of the query commands above.
+pub fn parse_query_output(family: DistroFamily, package: &str, stdout: &str) -> Option<String> {
+    let line = stdout.lines().next()?.trim();
+    match family {
+        // Only `install ok installed` counts; removed-but-configured
+        // (`deinstall ok config-files`) does not
+        DistroFamily::Debian => {
+            let (status, version) = line.split_once('\t')?;
+            (status.trim() == "install ok installed").then(|| version.trim().to_string())
+        }
+        // rpm exits non-zero for missing packages, so any output is a version
+        DistroFamily::Fedora | DistroFamily::Suse => {
+            (!line.is_empty() && !line.contains(' ')).then(|| line.to_string())
+        }
+        // `python 3.12.3-1`
+        DistroFamily::Arch => {
+            let (name, version) = line.split_once(' ')?;
+            (name == package).then(|| version.trim().to_string())
+        }
+        // `python3-3.12.3-r1 x86_64 {python3} (PSF-2.0) [installed]`, or
+        // `... [upgradable from: python3-3.12.2-r0]` naming the installed one
+        DistroFamily::Alpine => {
+            let full = match line.split_once("[upgradable from: ") {
+                Some((_, from)) => from.split(']').next()?,
+                None if line.contains("[installed]") => line.split_whitespace().next()?,
+                None => return None,
+            };
+            let version = full.strip_prefix(package)?.strip_prefix('-')?;
+            version
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| version.to_string())
+        }
+        DistroFamily::Unknown => None,
+    }
+}
+
+/// Native package names providing a dependency (named as in the dependency
+/// report) on a distro. `None` means there is no package for it here.
+pub fn packages_for(distro: &Distro, dependency: &str) -> Option<&'static [&'static str]> {
+    if matches!(dependency, "gcc-multilib" | "g++-multilib") && !distro.supports_multilib() {
+        return None;
+    }
+
+    match distro.family {
+        DistroFamily::Debian => debian_packages(dependency),
+        DistroFamily::Fedora => fedora_packages(dependency),
+        DistroFamily::Arch => arch_packages(dependency),
+        DistroFamily::Suse => suse_packages(dependency),
+        DistroFamily::Alpine => alpine_packages(dependency),
+        DistroFamily::Unknown => None,
+    }
+}
+
+fn debian_packages(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "Git" => &["git"],
+        "CMake" => &["cmake"],
+        "Ninja" => &["ninja-build"],
+        "Gperf" => &["gperf"],
+        "CCache" => &["ccache"],
+        "Dfu-util" => &["dfu-util"],
+        "DTC" => &["device-tree-compiler"],
+        "Wget" => &["wget"],
+        "Python 3" => &["python3"],
+        "XZ Utils" => &["xz-utils"],
+        "File" => &["file"],
+        "Make" => &["make"],
+        "GCC" => &["gcc"],
+        "G++" => &["g++"],
+        "python3-dev" => &["python3-dev"],
+        "python3-venv" => &["python3-venv"],
+        "python3-tk" => &["python3-tk"],
+        "libsdl2-dev" => &["libsdl2-dev"],
+        "libmagic1" => &["libmagic1"],
+        "gcc-multilib" => &["gcc-multilib"],
+        "g++-multilib" => &["g++-multilib"],
+        _ => return None,
+    })
+}
+
+fn fedora_packages(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "Git" => &["git"],
+        "CMake" => &["cmake"],
+        "Ninja" => &["ninja-build"],
+        "Gperf" => &["gperf"],
+        "CCache" => &["ccache"],
+        "Dfu-util" => &["dfu-util"],
+        "DTC" => &["dtc"],
+        "Wget" => &["wget"],
+        "Python 3" => &["python3"],
+        "XZ Utils" => &["xz"],
+        "File" => &["file"],
+        "Make" => &["make"],
+        "GCC" => &["gcc"],
+        "G++" => &["gcc-c++"],
+        "python3-dev" => &["python3-devel"],
+        "python3-venv" => &["python3"], // Usually in python3 core or python3-libs
+        "python3-tk" => &["python3-tkinter"],
+        "libsdl2-dev" => &["sdl2-compat-devel"],
+        "libmagic1" => &["file-libs"],
+        "gcc-multilib" => &["glibc-devel.i686"],
+        "g++-multilib" => &["libstdc++-devel.i686"],
+        _ => return None,
+    })
+}
+
+fn arch_packages(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "Git" => &["git"],
+        "CMake" => &["cmake"],
+        "Ninja" => &["ninja"],
+        "Gperf" => &["gperf"],
+        "CCache" => &["ccache"],
+        "Dfu-util" => &["dfu-util"],
+        "DTC" => &["dtc"],
+        "Wget" => &["wget"],
+        "Python 3" => &["python"],
+        "XZ Utils" => &["xz"],
+        "File" => &["file"],
+        "Make" => &["make"],
+        "GCC" | "G++" => &["gcc"],
+        // Headers and venv ship with the main python package
+        "python3-dev" | "python3-venv" => &["python"],
+        "python3-tk" => &["tk"],
+        "libsdl2-dev" => &["sdl2"],
+        "libmagic1" => &["file"],
+        // Requires the [multilib] repository to be enabled
+        "gcc-multilib" => &["lib32-glibc"],
+        "g++-multilib" => &["lib32-gcc-libs"],
+        _ => return None,
+    })
+}
+
+fn suse_packages(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "Git" => &["git"],
+        "CMake" => &["cmake"],
+        "Ninja" => &["ninja"],
+        "Gperf" => &["gperf"],
+        "CCache" => &["ccache"],
+        "Dfu-util" => &["dfu-util"],
+        "DTC" => &["dtc"],
+        "Wget" => &["wget"],
+        "Python 3" | "python3-venv" => &["python3"],
+        "XZ Utils" => &["xz"],
+        "File" => &["file"],
+        "Make" => &["make"],
+        "GCC" => &["gcc"],
+        "G++" => &["gcc-c++"],
+        "python3-dev" => &["python3-devel"],
+        "python3-tk" => &["python3-tk"],
+        "libsdl2-dev" => &["SDL2-devel"],
+        "libmagic1" => &["libmagic1"],
+        "gcc-multilib" => &["glibc-devel-32bit"],
+        "g++-multilib" => &["libstdc++-devel-32bit"],
+        _ => return None,
+    })
+}
+
+fn alpine_packages(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "Git" => &["git"],
+        "CMake" => &["cmake"],
+        "Ninja" => &["samurai"],
+        "Gperf" => &["gperf"],
+        "CCache" => &["ccache"],
+        "Dfu-util" => &["dfu-util"],
+        "DTC" => &["dtc"],
+        "Wget" => &["wget"],
+        "Python 3" | "python3-venv" => &["python3"],
+        "XZ Utils" => &["xz"],
+        "File" => &["file"],
+        "Make" => &["make"],
+        "GCC" => &["gcc"],
+        "G++" => &["g++"],
+        "python3-dev" => &["python3-dev"],
+        "python3-tk" => &["python3-tkinter"],
+        "libsdl2-dev" => &["sdl2-dev"],
+        "libmagic1" => &["libmagic"],
+        // musl has no 32-bit multilib
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARCH: &str = r#"NAME="Arch Linux"
+PRETTY_NAME="Arch Linux"
+ID=arch
+BUILD_ID=rolling
+ANSI_COLOR="38;2;23;147;209"
+HOME_URL="https://archlinux.org/"
+"#;
+
+    const MANJARO: &str = r#"NAME="Manjaro Linux"
+PRETTY_NAME="Manjaro Linux"
+ID=manjaro
+ID_LIKE=arch
+BUILD_ID=rolling
+"#;
+
+    const TUMBLEWEED: &str = r#"NAME="openSUSE Tumbleweed"
+# VERSION="20240801"
+ID="opensuse-tumbleweed"
+ID_LIKE="opensuse suse"
+VERSION_ID="20240801"
+PRETTY_NAME="openSUSE Tumbleweed"
+"#;
+
+    const ALPINE: &str =
+        "NAME=\"Alpine Linux\"\nID=alpine\nVERSION_ID=3.20.2\nPRETTY_NAME=\"Alpine Linux v3.20\"\n";
+
+    const UBUNTU: &str = r#"PRETTY_NAME="Ubuntu 24.04.1 LTS"
+NAME="Ubuntu"
+VERSION_ID="24.04"
+ID=ubuntu
+ID_LIKE=debian
+"#;
+
+    #[test]
+    fn detects_family_from_id_or_id_like() {
+        let arch = parse_os_release(ARCH, "x86_64");
+        assert_eq!(
+            (arch.id.as_str(), arch.family),
+            ("arch", DistroFamily::Arch)
+        );
+        assert_eq!(arch.name, "Arch Linux");
+        assert_eq!(arch.version_id, None);
+
+        let manjaro = parse_os_release(MANJARO, "x86_64");
+        assert_eq!(manjaro.family, DistroFamily::Arch);
+        assert_eq!(manjaro.id_like, vec!["arch"]);
+
+        let tumbleweed = parse_os_release(TUMBLEWEED, "x86_64");
+        assert_eq!(tumbleweed.id, "opensuse-tumbleweed");
+        assert_eq!(tumbleweed.family, DistroFamily::Suse);
+        assert_eq!(tumbleweed.version_id.as_deref(), Some("20240801"));
+
+        let alpine = parse_os_release(ALPINE, "x86_64");
+        assert_eq!(alpine.family, DistroFamily::Alpine);
+        assert_eq!(alpine.name, "Alpine Linux v3.20");
+
+        let unknown = parse_os_release("ID=plan9\nID_LIKE=\"bell labs\"\n", "x86_64");
+        assert_eq!(unknown.family, DistroFamily::Unknown);
+        assert_eq!(parse_os_release("", "x86_64").family, DistroFamily::Unknown);
+    }
+
+    #[test]
+    fn maps_packages_per_host() {
+        let x86 = parse_os_release(UBUNTU, "x86_64");
+        assert_eq!(
+            packages_for(&x86, "gcc-multilib"),
+            Some(&["gcc-multilib"][..])
+        );
+
+        // No 32-bit multilib on an aarch64 host, whatever the distro
+        let arm = parse_os_release(UBUNTU, "aarch64");
+        assert!(!arm.supports_multilib());
+        assert_eq!(packages_for(&arm, "gcc-multilib"), None);
+        assert_eq!(packages_for(&arm, "g++-multilib"), None);
+        assert_eq!(packages_for(&arm, "Ninja"), Some(&["ninja-build"][..]));
+
+        let manjaro = parse_os_release(MANJARO, "x86_64");
+        assert_eq!(
+            packages_for(&manjaro, "gcc-multilib"),
+            Some(&["lib32-glibc"][..])
+        );
+        assert_eq!(
+            install_command(manjaro.family).map(|(program, _)| program),
+            Some("pacman")
+        );
+        assert_eq!(
+            install_command(parse_os_release(ALPINE, "x86_64").family).map(|(p, _)| p),
+            Some("apk")
+        );
+    }
+
+    #[test]
+    fn parses_package_queries() {
+        use DistroFamily::*;
+
+        assert_eq!(
+            parse_query_output(Arch, "python", "python 3.12.4-1\n").as_deref(),
+            Some("3.12.4-1")
+        );
+        assert_eq!(
+            parse_query_output(Arch, "python", "python-pip 24.1-1\n"),
+            None
+        );
+
+        // openSUSE answers through rpm
+        assert_eq!(
+            parse_query_output(Suse, "python311", "3.11.9-1.1\n").as_deref(),
+            Some("3.11.9-1.1")
+        );
+        assert_eq!(
+            parse_query_output(Suse, "dtc", "package dtc is not installed\n"),
+            None
+        );
+
+        assert_eq!(
+            parse_query_output(
+                Alpine,
+                "python3",
+                "python3-3.12.3-r1 x86_64 {python3} (PSF-2.0) [installed]\n"
+            )
+            .as_deref(),
+            Some("3.12.3-r1")
+        );
+        assert_eq!(
+            parse_query_output(
+                Alpine,
+                "python3",
+                "python3-dev-3.12.3-r1 x86_64 {python3} (PSF-2.0) [installed]\n"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_query_output(
+                Alpine,
+                "cmake",
+                "cmake-3.29.3-r0 x86_64 {cmake} (BSD-3-Clause) [upgradable from: cmake-3.29.2-r0]\n"
+            )
+            .as_deref(),
+            Some("3.29.2-r0")
+        );
+        assert_eq!(
+            parse_query_output(
+                Alpine,
+                "cmake",
+                "cmake-3.29.3-r0 x86_64 {cmake} (BSD-3-Clause)\n"
+            ),
+            None
+        );
+
+        assert_eq!(
+            parse_query_output(Debian, "git", "install ok installed\t1:2.43.0-1ubuntu7").as_deref(),
+            Some("1:2.43.0-1ubuntu7")
+        );
+        assert_eq!(
+            parse_query_output(Debian, "git", "deinstall ok config-files\t1:2.43.0"),
+            None
+        );
+    }
+}
@@ -1,14 +1,88 @@
+#[cfg(any(target_os = "macos", test))]
+use crate::brew;
+use crate::cmd_zephyr;
 use crate::config_manager;
+#[cfg(any(target_os = "linux", test))]
+use crate::distro::{self, Distro};
+use crate::executor::{CommandExecutor, SystemExecutor};
+use crate::sdk_manager::{self, SdkCandidate, SdkSource};
+use crate::toolchain_manager;
+use crate::version;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+#[cfg(any(target_os = "macos", test))]
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tauri::AppHandle;
 
+/// Outcome of one environment check. `reason` says why it failed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EnvCheck {
+    pub ok: bool,
+    pub value: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl EnvCheck {
+    fn ok(value: impl Into<String>) -> EnvCheck {
+        EnvCheck {
+            ok: true,
+            value: Some(value.into()),
+            reason: None,
+        }
+    }
+
+    fn failed(value: Option<String>, reason: impl Into<String>) -> EnvCheck {
+        EnvCheck {
+            ok: false,
+            value,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EnvStatus {
     pub git: bool,
     pub python: bool,
     pub west: bool,
+    /// A usable Zephyr SDK was found (same as `zephyr_sdk.ok`)
     pub sdk: bool,
+    /// ZEPHYR_BASE exists and looks like a Zephyr tree
+    #[serde(default)]
+    pub zephyr_base: EnvCheck,
+    #[serde(default)]
+    pub zephyr_version: EnvCheck,
+    /// Path of the SDK that builds will use
+    #[serde(default)]
+    pub zephyr_sdk: EnvCheck,
+    #[serde(default)]
+    pub sdk_version: EnvCheck,
+    /// `west zephyr-export` registered this ZEPHYR_BASE with CMake
+    #[serde(default)]
+    pub cmake_package: EnvCheck,
+    /// Top directory of the west workspace
+    #[serde(default)]
+    pub west_workspace: EnvCheck,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Ok,
+    Missing,
+    TooOld,
+}
+
+/// How much a dependency matters for building Zephyr applications.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyLevel {
+    /// Builds fail without it
+    Required,
+    /// Common workflows (host builds, SDK setup) need it
+    Recommended,
+    /// Only unlocks specific features
+    Optional,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -16,447 +90,1798 @@ pub struct Dependency {
     pub name: String,
     pub installed: bool,
     pub version: Option<String>,
+    /// Same as `level == Required`, kept for older frontends
     pub critical: bool,
+    pub status: DependencyStatus,
+    pub required_version: Option<String>,
+    pub level: DependencyLevel,
+    pub reason: String,
+    pub features: Vec<String>,
+}
+
+impl Dependency {
+    /// A dependency without version information (libraries, python modules).
+    fn presence(name: &str, installed: bool) -> Dependency {
+        Dependency {
+            name: name.to_string(),
+            installed,
+            version: None,
+            critical: true,
+            status: if installed {
+                DependencyStatus::Ok
+            } else {
+                DependencyStatus::Missing
+            },
+            required_version: None,
+            level: DependencyLevel::Required,
+            reason: String::new(),
+            features: Vec::new(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.status == DependencyStatus::Ok
+    }
+
+    /// Fill in level, reason and unlocked features from [`classify`].
+    fn classified(mut self) -> Dependency {
+        let (level, reason, features) = classify(&self.name);
+        self.level = level;
+        self.critical = level == DependencyLevel::Required;
+        self.reason = reason.to_string();
+        self.features = features.iter().map(|f| f.to_string()).collect();
+        self
+    }
 }
 
+/// Why each dependency is needed. Names match the ones used in the
+/// per-platform check lists.
+fn classify(name: &str) -> (DependencyLevel, &'static str, &'static [&'static str]) {
+    use DependencyLevel::*;
+
+    match name {
+        "Git" => (
+            Required,
+            "west fetches Zephyr and its modules with git",
+            &["west update"],
+        ),
+        "CMake" => (Required, "Zephyr's build system is CMake based", &["build"]),
+        "Ninja" => (
+            Required,
+            "Default CMake generator used by west build",
+            &["build"],
+        ),
+        "Gperf" => (
+            Required,
+            "Generates kernel object lookup tables",
+            &["build"],
+        ),
+        "DTC" => (
+            Required,
+            "Compiles and checks devicetree sources",
+            &["build"],
+        ),
+        "Python 3" | "Python 3.12" => (
+            Required,
+            "Runs west and Zephyr's build scripts",
+            &["west", "build"],
+        ),
+        "python3-venv" => (
+            Required,
+            "Creates the isolated environment west is installed into",
+            &["west"],
+        ),
+        "XZ Utils" => (Required, "Extracts Zephyr SDK archives", &["sdk install"]),
+        "7-Zip" => (Required, "Extracts Zephyr SDK archives", &["sdk install"]),
+        "Wget" => (Recommended, "Downloads the Zephyr SDK", &["sdk install"]),
+        "File" => (
+            Recommended,
+            "Used by the SDK setup script to inspect binaries",
+            &["sdk install"],
+        ),
+        "Make" => (
+            Recommended,
+            "Builds host tools and some modules",
+            &["host tools"],
+        ),
+        "GCC" | "G++" => (
+            Recommended,
+            "Host compiler for native_sim and host tools",
+            &["native_sim"],
+        ),
+        "python3-dev" => (
+            Recommended,
+            "Headers for pip packages that compile native extensions",
+            &["pip install"],
+        ),
+        "CCache" => (
+            Optional,
+            "Caches compiler output to speed up rebuilds",
+            &["faster rebuilds"],
+        ),
+        "Dfu-util" => (
+            Optional,
+            "Flashes boards over USB DFU",
+            &["west flash (DFU)"],
+        ),
+        "python3-tk" | "python-tk" => (
+            Optional,
+            "Tk GUI used by west build -t guiconfig",
+            &["guiconfig"],
+        ),
+        "libsdl2-dev" => (
+            Optional,
+            "Display and input emulation for native_sim",
+            &["native_sim display"],
+        ),
+        "libmagic1" | "libmagic" => (
+            Optional,
+            "File type detection used by some Zephyr scripts",
+            &["python-magic"],
+        ),
+        "gcc-multilib" | "g++-multilib" => (
+            Optional,
+            "32-bit host builds of native_sim",
+            &["native_sim (32-bit)"],
+        ),
+        "Homebrew" => (
+            Required,
+            "Installs the other dependencies on macOS",
+            &["install dependencies"],
+        ),
+        "QEMU" => (
+            Optional,
+            "Emulates qemu_* boards for west build -t run",
+            &["qemu boards"],
+        ),
+        "OpenOCD" => (
+            Optional,
+            "Flashes and debugs boards over JTAG/SWD",
+            &["west flash (OpenOCD)", "west debug"],
+        ),
+        _ => (Required, "", &[]),
+    }
+}
+
+/// How to find an executable and the oldest version Zephyr accepts.
+struct ToolSpec {
+    name: &'static str,
+    command: &'static str,
+    version_arg: &'static str,
+    min_version: Option<&'static str>,
+    parse_version: fn(&str) -> Option<String>,
+}
+
+impl ToolSpec {
+    const fn new(name: &'static str, command: &'static str) -> ToolSpec {
+        ToolSpec {
+            name,
+            command,
+            version_arg: "--version",
+            min_version: None,
+            parse_version: version::extract_version,
+        }
+    }
+
+    const fn min(mut self, version: &'static str) -> ToolSpec {
+        self.min_version = Some(version);
+        self
+    }
+
+    const fn parser(mut self, parse: fn(&str) -> Option<String>) -> ToolSpec {
+        self.parse_version = parse;
+        self
+    }
+}
+
+// Minimum versions follow the Zephyr getting started guide
+const MIN_CMAKE: &str = "3.20.5";
+const MIN_PYTHON: &str = "3.10";
+const MIN_DTC: &str = "1.4.6";
+
+/// `file --version` prints `file-5.45`.
+fn parse_file_version(output: &str) -> Option<String> {
+    version::extract_version(&output.replace("file-", "file "))
+}
+
+#[cfg(any(target_os = "linux", test))]
+const LINUX_TOOLS: &[ToolSpec] = &[
+    ToolSpec::new("Git", "git"),
+    ToolSpec::new("CMake", "cmake").min(MIN_CMAKE),
+    ToolSpec::new("Ninja", "ninja"),
+    ToolSpec::new("Gperf", "gperf"),
+    ToolSpec::new("CCache", "ccache"),
+    ToolSpec::new("Dfu-util", "dfu-util"),
+    ToolSpec::new("DTC", "dtc").min(MIN_DTC),
+    ToolSpec::new("Wget", "wget"),
+    ToolSpec::new("Python 3", "python3").min(MIN_PYTHON),
+    ToolSpec::new("XZ Utils", "xz"),
+    ToolSpec::new("File", "file").parser(parse_file_version),
+    ToolSpec::new("Make", "make"),
+    ToolSpec::new("GCC", "gcc"),
+    ToolSpec::new("G++", "g++"),
+];
+
+#[cfg(any(target_os = "windows", test))]
+const WINDOWS_TOOLS: &[ToolSpec] = &[
+    ToolSpec::new("CMake", "cmake").min(MIN_CMAKE),
+    ToolSpec::new("Ninja", "ninja"),
+    ToolSpec::new("Gperf", "gperf"),
+    ToolSpec::new("Python 3.12", "python").min(MIN_PYTHON),
+    ToolSpec::new("Git", "git"),
+    ToolSpec::new("DTC", "dtc").min(MIN_DTC),
+    ToolSpec::new("Wget", "wget"),
+    // 7z usually prints help
+    ToolSpec {
+        version_arg: "--help",
+        ..ToolSpec::new("7-Zip", "7z")
+    },
+];
+
+#[cfg(any(target_os = "macos", test))]
+const MACOS_TOOLS: &[ToolSpec] = &[
+    ToolSpec::new("Git", "git"),
+    ToolSpec::new("CMake", "cmake").min(MIN_CMAKE),
+    ToolSpec::new("Ninja", "ninja"),
+    ToolSpec::new("Gperf", "gperf"),
+    ToolSpec::new("CCache", "ccache"),
+    ToolSpec::new("DTC", "dtc").min(MIN_DTC),
+    ToolSpec::new("Wget", "wget"),
+    ToolSpec::new("Python 3", "python3").min(MIN_PYTHON),
+    ToolSpec::new("XZ Utils", "xz"),
+];
+
+/// Dependencies without a version command, checked through `brew list`.
+#[cfg(any(target_os = "macos", test))]
+const MACOS_FORMULAE: &[(&str, &str)] = &[
+    ("python-tk", "python-tk"),
+    ("libmagic", "libmagic"),
+    ("QEMU", "qemu"),
+    ("OpenOCD", "open-ocd"),
+];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EnvReport {
     pub os: String,
     pub dependencies: Vec<Dependency>,
+    /// Every required dependency is present and recent enough
     pub all_satisfied: bool,
+    /// Recommended / optional dependencies that are not usable
+    pub missing_optional: Vec<String>,
 }
 
-#[tauri::command]
-pub async fn check_environment(app: AppHandle) -> EnvStatus {
-    let config = config_manager::get_config(app).unwrap_or_default();
-
-    // Use configured venv python if available, otherwise system python
-    let python_cmd = if let Some(venv) = config.venv_path {
-        #[cfg(target_os = "windows")]
-        let p = std::path::Path::new(&venv)
-            .join("Scripts")
-            .join("python.exe");
-        #[cfg(not(target_os = "windows"))]
-        let p = std::path::Path::new(&venv).join("bin").join("python");
-
-        if p.exists() {
-            p.to_string_lossy().to_string()
-        } else {
-            "python".to_string()
+impl EnvReport {
+    fn new(os: &str, dependencies: Vec<Dependency>) -> EnvReport {
+        let dependencies: Vec<Dependency> = dependencies
+            .into_iter()
+            .map(Dependency::classified)
+            .collect();
+        let all_satisfied = dependencies
+            .iter()
+            .filter(|d| d.level == DependencyLevel::Required)
+            .all(|d| d.is_ok());
+        let missing_optional = dependencies
+            .iter()
+            .filter(|d| d.level != DependencyLevel::Required && !d.is_ok())
+            .map(|d| d.name.clone())
+            .collect();
+
+        EnvReport {
+            os: os.to_string(),
+            dependencies,
+            all_satisfied,
+            missing_optional,
         }
-    } else {
-        "python".to_string()
-    };
+    }
+}
+
+#[tauri::command]
+pub async fn check_environment(app: AppHandle, project_path: Option<String>) -> EnvStatus {
+    // Check against the toolchain bound to the project (or the default one)
+    let toolchain = toolchain_manager::resolve_for_app(&app, project_path.as_deref());
 
-    let git = check_command("git", &["--version"]);
-    let python = check_command(&python_cmd, &["--version"]);
+    // Use the toolchain's venv python if available, otherwise system python
+    let python_cmd = toolchain
+        .as_ref()
+        .map(|t| t.python_program())
+        .unwrap_or_else(|| "python".to_string());
+
+    let exec = SystemExecutor;
+    let git = check_command(&exec, "git", &["--version"]);
+    let python = check_command(&exec, &python_cmd, &["--version"]);
 
     // Check west using the resolved python
-    let west = check_command(&python_cmd, &["-m", "west", "--version"]);
+    let west = check_command(&exec, &python_cmd, &["-m", "west", "--version"]);
 
-    // Check SDK using configured path or env var
-    let sdk = if let Some(base) = config.zephyr_base {
-        std::path::Path::new(&base).exists()
-    } else {
-        std::env::var("ZEPHYR_BASE").is_ok()
+    // The toolchain's paths win over the environment variables west and
+    // CMake would otherwise fall back to
+    let base_path = toolchain
+        .as_ref()
+        .map(|t| t.zephyr_base.clone())
+        .filter(|b| !b.is_empty())
+        .or_else(|| exec.env_var("ZEPHYR_BASE"))
+        .map(PathBuf::from);
+    let zephyr_base = check_zephyr_base(base_path.as_deref());
+    let valid_base = base_path.as_deref().filter(|_| zephyr_base.ok);
+
+    let zephyr_version = match valid_base {
+        Some(base) => match toolchain_manager::read_zephyr_version(base) {
+            Some(v) => EnvCheck::ok(v),
+            None => EnvCheck::failed(None, "VERSION file could not be parsed"),
+        },
+        None => EnvCheck::failed(None, "No valid ZEPHYR_BASE"),
+    };
+
+    let sdk_candidate = toolchain
+        .as_ref()
+        .and_then(|t| t.sdk_path.clone())
+        .map(|p| (PathBuf::from(p), SdkSource::Configured))
+        .or_else(|| {
+            exec.env_var("ZEPHYR_SDK_INSTALL_DIR")
+                .map(|p| (PathBuf::from(p), SdkSource::EnvVar))
+        })
+        .map(|(path, source)| sdk_manager::inspect_sdk(&path, vec![source]))
+        .or_else(|| {
+            let config = config_manager::get_config(app.clone()).unwrap_or_default();
+            sdk_manager::discover_sdks(&config).into_iter().next()
+        });
+    let zephyr_sdk = check_sdk(sdk_candidate.as_ref());
+    let sdk_version = check_sdk_version(sdk_candidate.as_ref(), valid_base);
+
+    let cmake_package = match valid_base {
+        Some(base) => check_cmake_package(base),
+        None => EnvCheck::failed(None, "No valid ZEPHYR_BASE"),
     };
 
+    // A freestanding application uses the workspace around ZEPHYR_BASE
+    let west_workspace = project_path
+        .as_deref()
+        .map(Path::new)
+        .and_then(find_west_topdir)
+        .or_else(|| base_path.as_deref().and_then(find_west_topdir))
+        .map(|topdir| check_west_workspace(&topdir))
+        .unwrap_or_else(|| {
+            EnvCheck::failed(
+                None,
+                "No west workspace (.west/config) above the project or ZEPHYR_BASE",
+            )
+        });
+
     EnvStatus {
         git,
         python,
         west,
-        sdk,
+        sdk: zephyr_sdk.ok,
+        zephyr_base,
+        zephyr_version,
+        zephyr_sdk,
+        sdk_version,
+        cmake_package,
+        west_workspace,
+    }
+}
+
+fn check_zephyr_base(base: Option<&Path>) -> EnvCheck {
+    let Some(base) = base else {
+        return EnvCheck::failed(None, "ZEPHYR_BASE is not configured");
+    };
+    let value = Some(base.to_string_lossy().to_string());
+
+    if !base.is_dir() {
+        EnvCheck::failed(value, "Directory does not exist")
+    } else if !base.join("VERSION").is_file() {
+        EnvCheck::failed(value, "VERSION file is missing; not a Zephyr tree")
+    } else if !base.join("cmake").is_dir() {
+        EnvCheck::failed(value, "cmake/ directory is missing; not a Zephyr tree")
+    } else {
+        EnvCheck::ok(base.to_string_lossy())
+    }
+}
+
+fn check_sdk(candidate: Option<&SdkCandidate>) -> EnvCheck {
+    match candidate {
+        Some(sdk) if sdk.valid => EnvCheck::ok(sdk.path.clone()),
+        Some(sdk) => EnvCheck::failed(Some(sdk.path.clone()), sdk.problems.join("; ")),
+        None => EnvCheck::failed(
+            None,
+            "No Zephyr SDK found; install one or set ZEPHYR_SDK_INSTALL_DIR",
+        ),
+    }
+}
+
+/// Zephyr's `SDK_VERSION` file holds the oldest SDK it supports.
+fn check_sdk_version(candidate: Option<&SdkCandidate>, zephyr_base: Option<&Path>) -> EnvCheck {
+    let Some(sdk) = candidate else {
+        return EnvCheck::failed(None, "No Zephyr SDK found");
+    };
+    let Some(sdk_version) = sdk.version.clone() else {
+        return EnvCheck::failed(None, "sdk_version file is missing");
+    };
+
+    let minimum = zephyr_base
+        .and_then(|base| std::fs::read_to_string(base.join("SDK_VERSION")).ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    match minimum {
+        Some(min) if !version::at_least(&sdk_version, &min) => EnvCheck::failed(
+            Some(sdk_version),
+            format!("This Zephyr needs SDK {} or newer", min),
+        ),
+        _ => EnvCheck::ok(sdk_version),
+    }
+}
+
+/// `west zephyr-export` registers `<ZEPHYR_BASE>/share/zephyr-package/cmake`
+/// as the `Zephyr` CMake package.
+fn check_cmake_package(zephyr_base: &Path) -> EnvCheck {
+    let expected = zephyr_base
+        .join("share")
+        .join("zephyr-package")
+        .join("cmake");
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+
+    let registered = sdk_manager::cmake_package_registry("Zephyr");
+    if registered
+        .iter()
+        .any(|p| canonical(p) == canonical(&expected))
+    {
+        return EnvCheck::ok(expected.to_string_lossy());
+    }
+
+    if registered.is_empty() {
+        EnvCheck::failed(
+            None,
+            "Zephyr is not registered with CMake; run `west zephyr-export`",
+        )
+    } else {
+        let others: Vec<String> = registered
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        EnvCheck::failed(
+            Some(others.join(", ")),
+            "Registered Zephyr CMake packages belong to another ZEPHYR_BASE; run `west zephyr-export`",
+        )
+    }
+}
+
+/// Nearest directory at or above `start` containing `.west/config`.
+fn find_west_topdir(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".west").join("config").is_file())
+        .map(|dir| dir.to_path_buf())
+}
+
+fn check_west_workspace(topdir: &Path) -> EnvCheck {
+    let value = Some(topdir.to_string_lossy().to_string());
+    let config = std::fs::read_to_string(topdir.join(".west").join("config")).unwrap_or_default();
+
+    // `[manifest]` section, `path = zephyr`
+    let mut section = "";
+    let mut manifest_path = None;
+    for line in config.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim();
+        } else if let Some((key, val)) = line.split_once('=') {
+            if section == "manifest" && key.trim() == "path" {
+                manifest_path = Some(val.trim().to_string());
+            }
+        }
+    }
+
+    match manifest_path {
+        None => EnvCheck::failed(value, ".west/config has no manifest.path"),
+        Some(path) if !topdir.join(&path).join("west.yml").is_file() => EnvCheck::failed(
+            value,
+            format!("Manifest {}/west.yml is missing; run `west init`", path),
+        ),
+        Some(_) => EnvCheck::ok(topdir.to_string_lossy()),
     }
 }
 
 #[tauri::command]
 pub async fn check_dependencies() -> EnvReport {
+    dependency_report(&SystemExecutor)
+}
+
+fn dependency_report(exec: &dyn CommandExecutor) -> EnvReport {
     #[cfg(target_os = "windows")]
     {
-        check_windows_dependencies()
+        windows_report(exec)
     }
     #[cfg(target_os = "linux")]
     {
-        check_linux_dependencies()
+        linux_report(exec, &distro::detect(exec))
     }
-    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    #[cfg(target_os = "macos")]
     {
+        macos_report(exec)
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        let _ = exec;
         EnvReport {
             os: "unsupported".to_string(),
             dependencies: vec![],
             all_satisfied: false,
+            missing_optional: vec![],
         }
     }
 }
 
-#[tauri::command]
-pub async fn install_dependencies(app: AppHandle) -> Result<(), String> {
-    let report = check_dependencies().await;
-    let missing_deps: Vec<&Dependency> = report
+/// A single package manager invocation, run with root privileges.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlannedCommand {
+    /// `KEY=value` pairs set for the command (through `env`)
+    pub env: Vec<String>,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl PlannedCommand {
+    fn new(program: &str, args: &[&str]) -> Self {
+        PlannedCommand {
+            env: Vec::new(),
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Program and arguments as executed, with the environment applied via `env`.
+    pub fn argv(&self) -> Vec<String> {
+        let mut argv = Vec::new();
+        if !self.env.is_empty() {
+            argv.push("env".to_string());
+            argv.extend(self.env.iter().cloned());
+        }
+        argv.push(self.program.clone());
+        argv.extend(self.args.iter().cloned());
+        argv
+    }
+
+    pub fn command_line(&self) -> String {
+        self.argv()
+            .iter()
+            .map(|a| {
+                if a.is_empty() || a.contains(char::is_whitespace) {
+                    format!("'{}'", a)
+                } else {
+                    a.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PackageMapping {
+    pub dependency: String,
+    pub packages: Vec<String>,
+}
+
+/// What `install_dependencies` is going to run, for review before installing.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstallPlan {
+    pub os: String,
+    /// Distribution name on Linux
+    pub distro: Option<String>,
+    pub package_manager: Option<String>,
+    pub commands: Vec<PlannedCommand>,
+    /// Command lines in display form, one per entry of `commands`
+    pub command_lines: Vec<String>,
+    pub mappings: Vec<PackageMapping>,
+    /// Missing dependencies without a package; these need manual action
+    pub unmapped: Vec<String>,
+}
+
+impl InstallPlan {
+    fn new(
+        os: &str,
+        distro: Option<String>,
+        package_manager: Option<&str>,
+        commands: Vec<PlannedCommand>,
+        mappings: Vec<PackageMapping>,
+        unmapped: Vec<String>,
+    ) -> Self {
+        let command_lines = commands.iter().map(|c| c.command_line()).collect();
+        InstallPlan {
+            os: os.to_string(),
+            distro,
+            package_manager: package_manager.map(|p| p.to_string()),
+            commands,
+            command_lines,
+            mappings,
+            unmapped,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Split missing dependencies into package mappings and unmapped names, with
+/// the de-duplicated package list in order of first appearance.
+fn map_packages<'a>(
+    missing: &[&Dependency],
+    lookup: impl Fn(&str) -> Option<&'a [&'a str]>,
+) -> (Vec<PackageMapping>, Vec<String>, Vec<&'a str>) {
+    let mut mappings = Vec::new();
+    let mut unmapped = Vec::new();
+    let mut packages: Vec<&str> = Vec::new();
+
+    for dep in missing {
+        match lookup(&dep.name).filter(|p| !p.is_empty()) {
+            Some(pkgs) => {
+                for pkg in pkgs {
+                    if !packages.contains(pkg) {
+                        packages.push(pkg);
+                    }
+                }
+                mappings.push(PackageMapping {
+                    dependency: dep.name.clone(),
+                    packages: pkgs.iter().map(|p| p.to_string()).collect(),
+                });
+            }
+            None => unmapped.push(dep.name.clone()),
+        }
+    }
+
+    (mappings, unmapped, packages)
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn linux_install_plan(distro: &Distro, missing: &[&Dependency]) -> InstallPlan {
+    let name = Some(distro.name.clone());
+    let Some((cmd, base_args)) = distro::install_command(distro.family) else {
+        let unmapped = missing.iter().map(|d| d.name.clone()).collect();
+        return InstallPlan::new("linux", name, None, vec![], vec![], unmapped);
+    };
+
+    let (mappings, unmapped, packages) =
+        map_packages(missing, |dep| distro::packages_for(distro, dep));
+
+    let mut commands = Vec::new();
+    if !packages.is_empty() {
+        let mut args = base_args.to_vec();
+        args.extend(packages);
+        let mut command = PlannedCommand::new(cmd, &args);
+        if cmd == "apt" {
+            // Keep debconf from prompting; there is no terminal to answer it
+            command
+                .env
+                .push("DEBIAN_FRONTEND=noninteractive".to_string());
+        }
+        commands.push(command);
+    }
+
+    InstallPlan::new("linux", name, Some(cmd), commands, mappings, unmapped)
+}
+
+#[cfg(any(target_os = "windows", test))]
+fn winget_packages(dependency: &str) -> Option<&'static [&'static str]> {
+    Some(match dependency {
+        "CMake" => &["Kitware.CMake"],
+        "Ninja" => &["Ninja-build.Ninja"],
+        "Gperf" => &["oss-winget.gperf"],
+        "Python 3.12" => &["Python.Python.3.12"],
+        "Git" => &["Git.Git"],
+        "DTC" => &["oss-winget.dtc"],
+        "Wget" => &["wget"],
+        "7-Zip" => &["7zip.7zip"],
+        _ => return None,
+    })
+}
+
+#[cfg(any(target_os = "windows", test))]
+fn windows_install_plan(missing: &[&Dependency]) -> InstallPlan {
+    let (mappings, unmapped, packages) = map_packages(missing, winget_packages);
+
+    let mut commands = Vec::new();
+    if !packages.is_empty() {
+        commands.push(PlannedCommand::new(
+            "winget",
+            &["source", "remove", "winget"],
+        ));
+        commands.push(PlannedCommand::new(
+            "winget",
+            &[
+                "source",
+                "add",
+                "winget",
+                "https://mirrors.ustc.edu.cn/winget-source",
+                "--trust-level",
+                "trusted",
+            ],
+        ));
+        for pkg in packages {
+            commands.push(PlannedCommand::new("winget", &["install", pkg]));
+        }
+    }
+
+    InstallPlan::new(
+        "windows",
+        None,
+        Some("winget"),
+        commands,
+        mappings,
+        unmapped,
+    )
+}
+
+/// Dependencies the installer takes care of: required ones, and recommended
+/// ones only when asked for. Optional ones are left to the user.
+fn planned_dependencies(report: &EnvReport, include_recommended: bool) -> Vec<&Dependency> {
+    let max_level = if include_recommended {
+        DependencyLevel::Recommended
+    } else {
+        DependencyLevel::Required
+    };
+    // Too-old tools are handed to the package manager as well, to upgrade them
+    report
         .dependencies
         .iter()
-        .filter(|d| !d.installed)
-        .collect();
+        .filter(|d| d.level <= max_level && !d.is_ok())
+        .collect()
+}
 
-    if missing_deps.is_empty() {
-        return Ok(());
-    }
+fn install_plan_for(
+    exec: &dyn CommandExecutor,
+    report: &EnvReport,
+    include_recommended: bool,
+) -> InstallPlan {
+    let missing = planned_dependencies(report, include_recommended);
 
     #[cfg(target_os = "linux")]
     {
-        install_linux_dependencies(app, &missing_deps).await
+        linux_install_plan(&distro::detect(exec), &missing)
     }
     #[cfg(target_os = "windows")]
     {
-        install_windows_dependencies(app, &missing_deps).await
+        windows_install_plan(&missing)
     }
-    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    #[cfg(target_os = "macos")]
     {
-        Err("Unsupported operating system".to_string())
+        let brew = brew::detect(exec);
+        let installed = brew
+            .as_deref()
+            .map(|b| brew_installed_formulae(exec, b))
+            .unwrap_or_default();
+        macos_install_plan(brew.as_deref(), &installed, &missing)
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        let _ = exec;
+        let unmapped = missing.iter().map(|d| d.name.clone()).collect();
+        InstallPlan::new(&report.os, None, None, vec![], vec![], unmapped)
     }
 }
 
-#[cfg(target_os = "linux")]
-async fn install_linux_dependencies(
-    _app: AppHandle,
+/// Homebrew formulae are installed by name; ones that are present but too
+/// old are upgraded instead.
+#[cfg(any(target_os = "macos", test))]
+fn macos_install_plan(
+    brew: Option<&Path>,
+    installed: &BTreeMap<String, String>,
     missing: &[&Dependency],
-) -> Result<(), String> {
-    // Detect distro
-    let release = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
-    let is_fedora = release.to_lowercase().contains("fedora");
-
-    let mut packages = Vec::new();
+) -> InstallPlan {
+    let Some(brew) = brew else {
+        // Homebrew's own installer is interactive and has to be run by hand
+        let unmapped = missing.iter().map(|d| d.name.clone()).collect();
+        return InstallPlan::new("macos", None, None, vec![], vec![], unmapped);
+    };
 
-    for dep in missing {
-        let pkgs: Option<Vec<&str>> = if is_fedora {
-            match dep.name.as_str() {
-                "Git" => Some(vec!["git"]),
-                "CMake" => Some(vec!["cmake"]),
-                "Ninja" => Some(vec!["ninja-build"]),
-                "Gperf" => Some(vec!["gperf"]),
-                "CCache" => Some(vec!["ccache"]),
-                "Dfu-util" => Some(vec!["dfu-util"]),
-                "DTC" => Some(vec!["dtc"]),
-                "Wget" => Some(vec!["wget"]),
-                "Python 3" => Some(vec!["python3"]),
-                "XZ Utils" => Some(vec!["xz"]),
-                "File" => Some(vec!["file"]),
-                "Make" => Some(vec!["make"]),
-                "GCC" => Some(vec!["gcc"]),
-                "G++" => Some(vec!["gcc-c++"]),
-                "python3-dev" => Some(vec!["python3-devel"]),
-                "python3-venv" => Some(vec!["python3"]), // Usually in python3 core or python3-libs
-                "python3-tk" => Some(vec!["python3-tkinter"]),
-                "libsdl2-dev" => Some(vec!["sdl2-compat-devel"]),
-                "libmagic1" => Some(vec!["file-libs"]),
-                "gcc-multilib" => Some(vec!["glibc-devel.i686"]),
-                "g++-multilib" => Some(vec!["libstdc++-devel.i686"]),
-                _ => None,
-            }
-        } else {
-            // Ubuntu/Debian
-            match dep.name.as_str() {
-                "Git" => Some(vec!["git"]),
-                "CMake" => Some(vec!["cmake"]),
-                "Ninja" => Some(vec!["ninja-build"]),
-                "Gperf" => Some(vec!["gperf"]),
-                "CCache" => Some(vec!["ccache"]),
-                "Dfu-util" => Some(vec!["dfu-util"]),
-                "DTC" => Some(vec!["device-tree-compiler"]),
-                "Wget" => Some(vec!["wget"]),
-                "Python 3" => Some(vec!["python3"]),
-                "XZ Utils" => Some(vec!["xz-utils"]),
-                "File" => Some(vec!["file"]),
-                "Make" => Some(vec!["make"]),
-                "GCC" => Some(vec!["gcc"]),
-                "G++" => Some(vec!["g++"]),
-                "python3-dev" => Some(vec!["python3-dev"]),
-                "python3-venv" => Some(vec!["python3-venv"]),
-                "python3-tk" => Some(vec!["python3-tk"]),
-                "libsdl2-dev" => Some(vec!["libsdl2-dev"]),
-                "libmagic1" => Some(vec!["libmagic1"]),
-                "gcc-multilib" => Some(vec!["gcc-multilib"]),
-                "g++-multilib" => Some(vec!["g++-multilib"]),
-                _ => None,
-            }
-        };
+    let (mappings, unmapped, formulae) = map_packages(missing, brew::formulae_for);
+    let (upgrade, install): (Vec<&str>, Vec<&str>) = formulae
+        .into_iter()
+        .partition(|f| brew::installed_version(installed, f).is_some());
 
-        if let Some(p) = pkgs {
-            packages.extend(p.iter().map(|s| s.to_string()));
+    let program = brew.to_string_lossy();
+    let mut commands = Vec::new();
+    for (verb, formulae) in [("install", install), ("upgrade", upgrade)] {
+        if !formulae.is_empty() {
+            let mut args = vec![verb];
+            args.extend(formulae);
+            let mut command = PlannedCommand::new(&program, &args);
+            command.env.push("HOMEBREW_NO_AUTO_UPDATE=1".to_string());
+            commands.push(command);
         }
     }
 
-    if packages.is_empty() {
-        return Ok(());
+    InstallPlan::new("macos", None, Some("brew"), commands, mappings, unmapped)
+}
+
+/// Dry run of `install_dependencies`: nothing is installed.
+#[tauri::command]
+pub async fn plan_dependency_install(include_recommended: Option<bool>) -> InstallPlan {
+    let exec = SystemExecutor;
+    install_plan_for(
+        &exec,
+        &dependency_report(&exec),
+        include_recommended.unwrap_or(false),
+    )
+}
+
+#[tauri::command]
+pub async fn install_dependencies(
+    app: AppHandle,
+    method: Option<InstallMethod>,
+    include_recommended: Option<bool>,
+) -> Result<InstallResult, String> {
+    let include_recommended = include_recommended.unwrap_or(false);
+    let exec = SystemExecutor;
+    let report = dependency_report(&exec);
+    let plan = install_plan_for(&exec, &report, include_recommended);
+
+    if plan.is_empty() {
+        if plan.unmapped.is_empty() {
+            return Ok(InstallResult {
+                method: InstallMethod::Auto,
+                completed: true,
+                report,
+            });
+        }
+        return Err(format!(
+            "No packages available for: {}. Please install them manually.",
+            plan.unmapped.join(", ")
+        ));
     }
 
-    let cmd = if is_fedora { "dnf" } else { "apt" };
-    let mut args = if is_fedora {
-        vec!["install", "-y"]
-    } else {
-        vec!["install", "-y", "--no-install-recommends"]
+    #[cfg(target_os = "linux")]
+    let used = install_linux_dependencies(&app, &exec, &plan, method.unwrap_or_default())?;
+    #[cfg(target_os = "windows")]
+    let used = {
+        let _ = method;
+        install_windows_dependencies(&exec, &plan)?;
+        InstallMethod::Terminal
+    };
+    #[cfg(target_os = "macos")]
+    let used = {
+        let _ = method;
+        install_macos_dependencies(&app, &plan)?
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    let used = {
+        let _ = method;
+        return Err("Unsupported operating system".to_string());
     };
 
-    let pkg_refs: Vec<&str> = packages.iter().map(|s| s.as_str()).collect();
-    args.extend(pkg_refs);
+    // An external terminal runs on its own; the user re-checks when it is done
+    let completed = used != InstallMethod::Terminal;
+    let report = if completed {
+        let report = dependency_report(&exec);
+        let still_missing: Vec<&str> = planned_dependencies(&report, include_recommended)
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        if still_missing.is_empty() {
+            cmd_zephyr::emit_log(&app, "All dependencies are installed.");
+        } else {
+            cmd_zephyr::emit_log(
+                &app,
+                &format!("Still missing after install: {}", still_missing.join(", ")),
+            );
+        }
+        report
+    } else {
+        report
+    };
 
-    // Construct the full command string for display
-    let full_cmd = format!("sudo {} {}", cmd, args.join(" "));
+    Ok(InstallResult {
+        method: used,
+        completed,
+        report,
+    })
+}
 
-    // Try to launch a terminal to run the command with sudo
-    let terminals = ["gnome-terminal", "konsole", "xfce4-terminal", "xterm"];
+/// How package installation gets root privileges.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallMethod {
+    /// pkexec, then sudo with an askpass helper, then an external terminal
+    #[default]
+    Auto,
+    /// polkit's pkexec; output is streamed into the app terminal
+    Pkexec,
+    /// `sudo -A` with a graphical askpass helper; output is streamed
+    Sudo,
+    /// Run `sudo` in an external terminal emulator
+    Terminal,
+    /// Run as the current user with streamed output; Homebrew refuses root
+    User,
+}
 
-    for term in terminals {
-        if which::which(term).is_ok() {
-            let mut command = Command::new(term);
-
-            match term {
-                "gnome-terminal" | "xfce4-terminal" => {
-                    command.args(&[
-                        "--",
-                        "bash",
-                        "-c",
-                        &format!("{}; echo 'Press Enter to close...'; read", full_cmd),
-                    ]);
-                }
-                "konsole" => {
-                    command.args(&[
-                        "-e",
-                        "bash",
-                        "-c",
-                        &format!("{}; echo 'Press Enter to close...'; read", full_cmd),
-                    ]);
-                }
-                "xterm" => {
-                    command.args(&[
-                        "-e",
-                        "bash",
-                        "-c",
-                        &format!("{}; echo 'Press Enter to close...'; read", full_cmd),
-                    ]);
-                }
-                _ => continue,
-            }
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstallResult {
+    pub method: InstallMethod,
+    /// False when the install was handed to an external terminal and may
+    /// still be running; `report` is then the state before installing
+    pub completed: bool,
+    pub report: EnvReport,
+}
 
-            return command
-                .spawn()
-                .map(|_| ())
-                .map_err(|e| format!("Failed to launch terminal: {}", e));
+#[cfg(target_os = "linux")]
+fn install_linux_dependencies(
+    app: &AppHandle,
+    exec: &dyn CommandExecutor,
+    plan: &InstallPlan,
+    method: InstallMethod,
+) -> Result<InstallMethod, String> {
+    let mut used = method;
+    for (i, command) in plan.commands.iter().enumerate() {
+        used = run_privileged(app, exec, command, method)?;
+        if used == InstallMethod::Terminal {
+            // Hand this and the remaining commands over in one go
+            let full_cmd = plan.commands[i..]
+                .iter()
+                .map(|c| format!("sudo {}", c.command_line()))
+                .collect::<Vec<_>>()
+                .join(" && ");
+            cmd_zephyr::emit_log(app, &format!("Opening a terminal to run: {}", full_cmd));
+            launch_in_terminal(exec, &full_cmd)?;
+            break;
         }
     }
-
-    Err("No supported terminal emulator found. Please install dependencies manually.".to_string())
+    Ok(used)
 }
 
-#[cfg(target_os = "windows")]
-async fn install_windows_dependencies(
-    _app: AppHandle,
-    missing: &[&Dependency],
-) -> Result<(), String> {
-    let mut packages = Vec::new();
+/// Run one planned command as root with streamed output. Returns
+/// `InstallMethod::Terminal` without running anything when only the
+/// external-terminal fallback is left.
+#[cfg(target_os = "linux")]
+fn run_privileged(
+    app: &AppHandle,
+    exec: &dyn CommandExecutor,
+    command: &PlannedCommand,
+    method: InstallMethod,
+) -> Result<InstallMethod, String> {
+    let argv = command.argv();
+    let argv: Vec<&str> = argv.iter().map(|a| a.as_str()).collect();
 
-    for dep in missing {
-        let pkg = match dep.name.as_str() {
-            "CMake" => Some("Kitware.CMake"),
-            "Ninja" => Some("Ninja-build.Ninja"),
-            "Gperf" => Some("oss-winget.gperf"),
-            "Python 3.12" => Some("Python.Python.3.12"),
-            "Git" => Some("Git.Git"),
-            "DTC" => Some("oss-winget.dtc"),
-            "Wget" => Some("wget"),
-            "7-Zip" => Some("7zip.7zip"),
-            _ => None,
-        };
+    let try_pkexec = matches!(method, InstallMethod::Auto | InstallMethod::Pkexec);
+    if try_pkexec && exec.which("pkexec").is_some() && has_display(exec) {
+        // pkexec clears the environment and wants an absolute path; `env` finds
+        // the program on root's PATH
+        let mut pkexec_args = vec!["env"];
+        if command.env.is_empty() {
+            pkexec_args.extend(&argv);
+        } else {
+            pkexec_args.extend(&argv[1..]);
+        }
+        cmd_zephyr::emit_log(app, &format!("Running: pkexec {}", command.command_line()));
 
-        if let Some(p) = pkg {
-            packages.push(p);
+        let status = cmd_zephyr::run_command_stream_status(app, "pkexec", &pkexec_args, None)?;
+        match status.code() {
+            Some(0) => return Ok(InstallMethod::Pkexec),
+            // The authentication dialog was dismissed
+            Some(126) => return Err("Authorization was cancelled".to_string()),
+            // No polkit agent running or authentication failed
+            Some(127) => cmd_zephyr::emit_log(app, "pkexec could not authenticate"),
+            _ => return Err(format!("{} failed with status {}", command.program, status)),
         }
+    } else if method == InstallMethod::Pkexec {
+        return Err("pkexec is not available".to_string());
     }
 
-    if packages.is_empty() {
-        return Ok(());
+    let try_sudo = matches!(method, InstallMethod::Auto | InstallMethod::Sudo);
+    if let Some(askpass) = find_askpass(exec).filter(|_| try_sudo && has_display(exec)) {
+        let askpass_env = format!("SUDO_ASKPASS={}", askpass);
+        let mut sudo_args = vec![askpass_env.as_str(), "sudo", "-A"];
+        sudo_args.extend(&argv);
+        cmd_zephyr::emit_log(app, &format!("Running: sudo -A {}", command.command_line()));
+
+        let status = cmd_zephyr::run_command_stream_status(app, "env", &sudo_args, None)?;
+        if status.success() {
+            return Ok(InstallMethod::Sudo);
+        }
+        return Err(format!("{} failed with status {}", command.program, status));
+    } else if method == InstallMethod::Sudo {
+        return Err("No sudo askpass helper found".to_string());
     }
 
-    let install_cmds: Vec<String> = packages
+    // Fall back to an external terminal where sudo can prompt normally
+    Ok(InstallMethod::Terminal)
+}
+
+/// Graphical privilege prompts need a desktop session.
+#[cfg(target_os = "linux")]
+fn has_display(exec: &dyn CommandExecutor) -> bool {
+    exec.env_var("DISPLAY").is_some() || exec.env_var("WAYLAND_DISPLAY").is_some()
+}
+
+/// A graphical askpass helper for `sudo -A`, honouring `SUDO_ASKPASS`.
+#[cfg(target_os = "linux")]
+fn find_askpass(exec: &dyn CommandExecutor) -> Option<String> {
+    if let Some(askpass) = exec.env_var("SUDO_ASKPASS") {
+        if exec.exists(Path::new(&askpass)) {
+            return Some(askpass);
+        }
+    }
+
+    let in_path = ["ksshaskpass", "ssh-askpass", "lxqt-openssh-askpass"]
         .iter()
-        .map(|p| format!("winget install {}", p))
-        .collect();
+        .find_map(|name| exec.which(name));
+    let known_paths = [
+        "/usr/lib/ssh/ssh-askpass",
+        "/usr/libexec/openssh/gnome-ssh-askpass",
+        "/usr/libexec/openssh/ssh-askpass",
+        "/usr/lib/openssh/gnome-ssh-askpass",
+    ];
+    in_path
+        .or_else(|| {
+            known_paths
+                .iter()
+                .map(std::path::PathBuf::from)
+                .find(|p| exec.exists(p))
+        })
+        .map(|p| p.to_string_lossy().to_string())
+}
 
-    let install_script = install_cmds.join("; ");
+#[cfg(target_os = "linux")]
+fn launch_in_terminal(exec: &dyn CommandExecutor, full_cmd: &str) -> Result<(), String> {
+    // Try to launch a terminal to run the command with sudo
+    let terminals = ["gnome-terminal", "konsole", "xfce4-terminal", "xterm"];
+    let script = format!("{}; echo 'Press Enter to close...'; read", full_cmd);
 
-    let ps_command = format!(
-        "winget source remove winget; winget source add winget https://mirrors.ustc.edu.cn/winget-source --trust-level trusted; {}; Read-Host 'Press Enter to exit'",
-        install_script
-    );
+    for term in terminals {
+        if exec.which(term).is_some() {
+            let separator = match term {
+                "gnome-terminal" | "xfce4-terminal" => "--",
+                _ => "-e",
+            };
 
-    Command::new("powershell")
-        .args(&[
+            return exec
+                .spawn(term, &[separator, "bash", "-c", &script])
+                .map_err(|e| format!("Failed to launch terminal: {}", e));
+        }
+    }
+
+    Err("No supported terminal emulator found. Please install dependencies manually.".to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn install_macos_dependencies(
+    app: &AppHandle,
+    plan: &InstallPlan,
+) -> Result<InstallMethod, String> {
+    for command in &plan.commands {
+        let argv = command.argv();
+        let args: Vec<&str> = argv[1..].iter().map(|a| a.as_str()).collect();
+        cmd_zephyr::emit_log(app, &format!("Running: {}", command.command_line()));
+        cmd_zephyr::run_command_stream(app, &argv[0], &args, None)?;
+    }
+    Ok(InstallMethod::User)
+}
+
+#[cfg(target_os = "windows")]
+fn install_windows_dependencies(
+    exec: &dyn CommandExecutor,
+    plan: &InstallPlan,
+) -> Result<(), String> {
+    let install_script = plan.command_lines.join("; ");
+
+    let ps_command = format!("{}; Read-Host 'Press Enter to exit'", install_script);
+
+    exec.spawn(
+        "powershell",
+        &[
             "Start-Process",
             "powershell",
             "-Verb",
             "RunAs",
             "-ArgumentList",
             &format!("\"-NoExit -Command {}\"", ps_command),
-        ])
-        .spawn()
-        .map(|_| ())
-        .map_err(|e| format!("Failed to launch PowerShell: {}", e))
-}
-
-fn check_command(cmd: &str, args: &[&str]) -> bool {
-    Command::new(cmd)
-        .args(args)
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
-}
-
-fn check_command_version(cmd: &str, args: &[&str]) -> Option<String> {
-    Command::new(cmd)
-        .args(args)
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
-            } else {
-                None
-            }
-        })
+        ],
+    )
+    .map_err(|e| format!("Failed to launch PowerShell: {}", e))
 }
 
-fn check_binary(name: &str, cmd: &str, version_arg: &str) -> Dependency {
-    let version = check_command_version(cmd, &[version_arg]);
+fn check_command(exec: &dyn CommandExecutor, cmd: &str, args: &[&str]) -> bool {
+    exec.run(cmd, args).is_some_and(|output| output.success)
+}
+
+/// Combined stdout and stderr of a successful run; some tools (older
+/// Pythons, 7z) print their version on stderr or after a blank line.
+fn check_command_version(exec: &dyn CommandExecutor, cmd: &str, args: &[&str]) -> Option<String> {
+    exec.run(cmd, args)
+        .filter(|output| output.success)
+        .map(|output| format!("{}\n{}", output.stdout, output.stderr))
+}
+
+fn check_tool(exec: &dyn CommandExecutor, spec: &ToolSpec) -> Dependency {
+    tool_dependency(
+        spec,
+        check_command_version(exec, spec.command, &[spec.version_arg]),
+    )
+}
+
+/// Build a tool's dependency entry from the output of its version command
+/// (`None` when it could not be run).
+fn tool_dependency(spec: &ToolSpec, output: Option<String>) -> Dependency {
+    let required_version = spec.min_version.map(|v| v.to_string());
+
+    let Some(output) = output else {
+        return Dependency {
+            name: spec.name.to_string(),
+            installed: false,
+            version: None,
+            critical: true,
+            status: DependencyStatus::Missing,
+            required_version,
+            level: DependencyLevel::Required,
+            reason: String::new(),
+            features: Vec::new(),
+        };
+    };
+
+    let version = (spec.parse_version)(&output).or_else(|| {
+        output
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .map(|l| l.to_string())
+    });
+
+    // An unparseable version is given the benefit of the doubt
+    let too_old = match (&version, spec.min_version) {
+        (Some(v), Some(min)) => !version::version_key(v).is_empty() && !version::at_least(v, min),
+        _ => false,
+    };
+
     Dependency {
-        name: name.to_string(),
-        installed: version.is_some(),
+        name: spec.name.to_string(),
+        installed: true,
         version,
         critical: true,
+        status: if too_old {
+            DependencyStatus::TooOld
+        } else {
+            DependencyStatus::Ok
+        },
+        required_version,
+        level: DependencyLevel::Required,
+        reason: String::new(),
+        features: Vec::new(),
     }
 }
 
-#[cfg(target_os = "linux")]
-fn check_dpkg(package: &str) -> Dependency {
-    let installed = Command::new("dpkg-query")
-        .args(&["-W", "-f='${Status}'", package])
-        .output()
-        .ok()
-        .map(|output| String::from_utf8_lossy(&output.stdout).contains("install ok installed"))
-        .unwrap_or(false);
+/// Dependency list for macOS. `brew` is the Homebrew executable if found and
+/// `installed` its `brew list --versions`.
+#[cfg(any(target_os = "macos", test))]
+fn macos_dependencies(
+    exec: &dyn CommandExecutor,
+    brew: Option<&Path>,
+    installed: &BTreeMap<String, String>,
+) -> Vec<Dependency> {
+    let run = |program: &str, arg: &str| check_command_version(exec, program, &[arg]);
 
-    Dependency {
-        name: package.to_string(),
-        installed,
-        version: None,
-        critical: true,
+    let brew_spec = ToolSpec::new("Homebrew", "brew");
+    let brew_output = brew.and_then(|b| run(&b.to_string_lossy(), brew_spec.version_arg));
+    let mut dependencies = vec![tool_dependency(&brew_spec, brew_output)];
+
+    // Formula binaries are not on PATH for apps started from Finder
+    let brew_bin = brew.and_then(|b| b.parent());
+    for spec in MACOS_TOOLS {
+        let output = brew_bin
+            .and_then(|bin| run(&bin.join(spec.command).to_string_lossy(), spec.version_arg))
+            .or_else(|| run(spec.command, spec.version_arg));
+        dependencies.push(tool_dependency(spec, output));
+    }
+
+    for (name, formula) in MACOS_FORMULAE {
+        let version = brew::installed_version(installed, formula);
+        let mut dep = Dependency::presence(name, version.is_some());
+        dep.version = version.map(|v| v.to_string());
+        dependencies.push(dep);
     }
+
+    dependencies
 }
 
-#[cfg(target_os = "linux")]
-fn check_shell_dependency(name: &str, cmd: &str) -> Dependency {
-    let installed = Command::new("sh")
-        .arg("-c")
-        .arg(cmd)
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false);
+#[cfg(any(target_os = "macos", test))]
+fn brew_installed_formulae(exec: &dyn CommandExecutor, brew: &Path) -> BTreeMap<String, String> {
+    check_command_version(
+        exec,
+        &brew.to_string_lossy(),
+        &["list", "--versions", "--formula"],
+    )
+    .map(|out| brew::parse_list_versions(&out))
+    .unwrap_or_default()
+}
 
-    Dependency {
-        name: name.to_string(),
-        installed,
-        version: None,
-        critical: true,
+#[cfg(any(target_os = "macos", test))]
+fn macos_report(exec: &dyn CommandExecutor) -> EnvReport {
+    let brew = brew::detect(exec);
+    let installed = brew
+        .as_deref()
+        .map(|b| brew_installed_formulae(exec, b))
+        .unwrap_or_default();
+    EnvReport::new(
+        "macos",
+        macos_dependencies(exec, brew.as_deref(), &installed),
+    )
+}
+
+/// A way to tell a library is usable without asking the package manager,
+/// for distros whose package names we do not know.
+#[cfg(any(target_os = "linux", test))]
+enum LibraryProbe {
+    /// `pkg-config --exists <module>`
+    PkgConfig(&'static str),
+    /// Shared object listed by `ldconfig -p`
+    SharedObject(&'static str),
+    /// Python can import the module
+    PythonModule(&'static str),
+    /// `Python.h` exists in the interpreter's include directory
+    PythonHeader,
+    /// The compiler resolves a file for the given flag (`gcc -m32 -print-file-name=crt1.o`)
+    CompilerFile(&'static str, &'static str, &'static str),
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl LibraryProbe {
+    fn is_present(&self, exec: &dyn CommandExecutor) -> bool {
+        match self {
+            LibraryProbe::PkgConfig(module) => {
+                check_command(exec, "pkg-config", &["--exists", module])
+            }
+            LibraryProbe::SharedObject(name) => exec
+                .run("ldconfig", &["-p"])
+                .map(|o| {
+                    o.stdout
+                        .lines()
+                        .any(|l| l.trim_start().starts_with(&format!("{} ", name)))
+                })
+                .unwrap_or(false),
+            LibraryProbe::PythonModule(module) => {
+                check_command(exec, "python3", &["-c", &format!("import {}", module)])
+            }
+            LibraryProbe::PythonHeader => check_command_version(
+                exec,
+                "python3",
+                &[
+                    "-c",
+                    "import sysconfig; print(sysconfig.get_paths()['include'])",
+                ],
+            )
+            .and_then(|out| out.lines().next().map(|l| l.trim().to_string()))
+            .map(|dir| exec.exists(&Path::new(&dir).join("Python.h")))
+            .unwrap_or(false),
+            LibraryProbe::CompilerFile(compiler, flag, file) => exec
+                .run(compiler, &[flag, &format!("-print-file-name={}", file)])
+                .filter(|o| o.success)
+                // An unresolved file is echoed back without a directory
+                .map(|o| o.stdout.trim().contains('/'))
+                .unwrap_or(false),
+        }
     }
 }
 
-#[cfg(target_os = "linux")]
-fn check_linux_dependencies() -> EnvReport {
+/// A library dependency, with a package-manager independent fallback check.
+/// The packages providing it come from [`distro::packages_for`].
+#[cfg(any(target_os = "linux", test))]
+struct LibrarySpec {
+    name: &'static str,
+    probe: LibraryProbe,
+}
+
+#[cfg(any(target_os = "linux", test))]
+const LINUX_LIBRARIES: &[LibrarySpec] = &[
+    LibrarySpec {
+        name: "python3-dev",
+        probe: LibraryProbe::PythonHeader,
+    },
+    LibrarySpec {
+        name: "python3-tk",
+        probe: LibraryProbe::PythonModule("tkinter"),
+    },
+    LibrarySpec {
+        name: "libsdl2-dev",
+        probe: LibraryProbe::PkgConfig("sdl2"),
+    },
+    LibrarySpec {
+        name: "libmagic1",
+        probe: LibraryProbe::SharedObject("libmagic.so.1"),
+    },
+    LibrarySpec {
+        name: "gcc-multilib",
+        probe: LibraryProbe::CompilerFile("gcc", "-m32", "crt1.o"),
+    },
+    LibrarySpec {
+        name: "g++-multilib",
+        probe: LibraryProbe::CompilerFile("g++", "-m32", "libstdc++.so"),
+    },
+];
+
+#[cfg(any(target_os = "linux", test))]
+fn check_library(exec: &dyn CommandExecutor, distro: &Distro, spec: &LibrarySpec) -> Dependency {
+    // Installed when every package providing it is installed
+    let packages = distro::packages_for(distro, spec.name).unwrap_or_default();
+    let versions: Vec<String> = packages
+        .iter()
+        .map_while(|pkg| distro::query_package(exec, distro.family, pkg))
+        .collect();
+
+    if !packages.is_empty() && versions.len() == packages.len() {
+        let mut dep = Dependency::presence(spec.name, true);
+        dep.version = versions.into_iter().next();
+        dep
+    } else {
+        Dependency::presence(spec.name, spec.probe.is_present(exec))
+    }
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn linux_report(exec: &dyn CommandExecutor, distro: &Distro) -> EnvReport {
     let mut deps = Vec::new();
 
     // Executables
-    deps.push(check_binary("Git", "git", "--version"));
-    deps.push(check_binary("CMake", "cmake", "--version"));
-    deps.push(check_binary("Ninja", "ninja", "--version"));
-    deps.push(check_binary("Gperf", "gperf", "--version"));
-    deps.push(check_binary("CCache", "ccache", "--version"));
-    deps.push(check_binary("Dfu-util", "dfu-util", "--version"));
-    deps.push(check_binary("DTC", "dtc", "--version"));
-    deps.push(check_binary("Wget", "wget", "--version"));
-    deps.push(check_binary("Python 3", "python3", "--version"));
-    deps.push(check_binary("XZ Utils", "xz", "--version"));
-    deps.push(check_binary("File", "file", "--version"));
-    deps.push(check_binary("Make", "make", "--version"));
-    deps.push(check_binary("GCC", "gcc", "--version"));
-    deps.push(check_binary("G++", "g++", "--version"));
-
-    // Libraries / Packages
-    // python3-dev: Try dpkg (Debian/Ubuntu) or rpm (Fedora/RHEL)
-    deps.push(check_shell_dependency(
-        "python3-dev",
-        "dpkg -l | grep python3-dev || rpm -qa | grep python3-devel",
-    ));
+    deps.extend(LINUX_TOOLS.iter().map(|spec| check_tool(exec, spec)));
 
     // python3-venv: Check by running module help
-    let venv_installed = check_command("python3", &["-m", "venv", "--help"]);
-    deps.push(Dependency {
-        name: "python3-venv".to_string(),
-        installed: venv_installed,
-        version: None,
-        critical: true,
-    });
+    let venv_installed = check_command(exec, "python3", &["-m", "venv", "--help"]);
+    deps.push(Dependency::presence("python3-venv", venv_installed));
 
-    // python3-tk: Try dpkg or rpm
-    deps.push(check_shell_dependency(
-        "python3-tk",
-        "dpkg -l | grep python3-tk || rpm -qa | grep python3-tkinter",
-    ));
-
-    // Other libraries
-    deps.push(check_shell_dependency(
-        "libsdl2-dev",
-        "dpkg -l | grep libsdl2-dev || rpm -qa | grep sdl2-compat-devel",
-    ));
-    deps.push(check_shell_dependency(
-        "libmagic1",
-        "dpkg -l | grep libmagic1 || rpm -qa | grep file-libs",
-    ));
-    deps.push(check_shell_dependency(
-        "gcc-multilib",
-        "dpkg -l | grep gcc-multilib || rpm -qa | grep \"glibc-devel.*i686\"",
-    ));
-    deps.push(check_shell_dependency(
-        "g++-multilib",
-        "dpkg -l | grep g++-multilib || rpm -qa | grep \"libstdc++-devel.*i686\"",
-    ));
-
-    let all_satisfied = deps.iter().all(|d| d.installed);
-
-    EnvReport {
-        os: "linux".to_string(),
-        dependencies: deps,
-        all_satisfied,
-    }
+    // Libraries: exact package queries, falling back to looking for the files.
+    // Multilib packages do not exist on aarch64 hosts, so they are not checked there.
+    deps.extend(
+        LINUX_LIBRARIES
+            .iter()
+            .filter(|lib| {
+                distro.supports_multilib() || !matches!(lib.name, "gcc-multilib" | "g++-multilib")
+            })
+            .map(|lib| check_library(exec, distro, lib)),
+    );
+
+    EnvReport::new("linux", deps)
 }
 
-#[cfg(target_os = "windows")]
-fn check_windows_dependencies() -> EnvReport {
+#[cfg(any(target_os = "windows", test))]
+fn windows_report(exec: &dyn CommandExecutor) -> EnvReport {
     let mut deps = Vec::new();
 
     // Executables
-    deps.push(check_binary("CMake", "cmake", "--version"));
-    deps.push(check_binary("Ninja", "ninja", "--version"));
-    deps.push(check_binary("Gperf", "gperf", "--version"));
-    deps.push(check_binary("Python 3.12", "python", "--version"));
-    deps.push(check_binary("Git", "git", "--version"));
-    deps.push(check_binary("DTC", "dtc", "--version"));
-    deps.push(check_binary("Wget", "wget", "--version"));
-    deps.push(check_binary("7-Zip", "7z", "--help")); // 7z usually prints help
-
-    let all_satisfied = deps.iter().all(|d| d.installed);
-
-    EnvReport {
-        os: "windows".to_string(),
-        dependencies: deps,
-        all_satisfied,
+    deps.extend(WINDOWS_TOOLS.iter().map(|spec| check_tool(exec, spec)));
+
+    EnvReport::new("windows", deps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::FakeExecutor;
+
+    fn find<'a>(report: &'a EnvReport, name: &str) -> &'a Dependency {
+        report
+            .dependencies
+            .iter()
+            .find(|d| d.name == name)
+            .unwrap_or_else(|| panic!("{} not in report", name))
+    }
+
+    fn missing(report: &EnvReport) -> Vec<&Dependency> {
+        report.dependencies.iter().filter(|d| !d.is_ok()).collect()
+    }
+
+    const UBUNTU_OS_RELEASE: &str = "PRETTY_NAME=\"Ubuntu 24.04 LTS\"\nNAME=\"Ubuntu\"\n\
+        VERSION_ID=\"24.04\"\nID=ubuntu\nID_LIKE=debian\n";
+
+    fn ubuntu() -> FakeExecutor {
+        FakeExecutor::new()
+            .file("/etc/os-release", UBUNTU_OS_RELEASE)
+            .output("git --version", "git version 2.43.0")
+            .output(
+                "cmake --version",
+                "cmake version 3.28.3\n\nCMake suite maintained by Kitware",
+            )
+            .output("ninja --version", "1.11.1")
+            .output("gperf --version", "GNU gperf 3.1")
+            .output("dtc --version", "Version: DTC 1.7.0")
+            .output("wget --version", "GNU Wget 1.21.4 built on linux-gnu.")
+            .output("python3 --version", "Python 3.12.3")
+            .output("xz --version", "xz (XZ Utils) 5.4.5\nliblzma 5.4.5")
+            .output("file --version", "file-5.45\nmagic file from /etc/magic")
+            .output("make --version", "GNU Make 4.3")
+            .output("gcc --version", "gcc (Ubuntu 13.2.0-23ubuntu4) 13.2.0")
+            .output("python3 -m venv --help", "usage: venv")
+            .output(
+                "dpkg-query -W -f=${Status}\t${Version} python3-dev",
+                "install ok installed\t3.12.3-0ubuntu1",
+            )
+            .failure(
+                "dpkg-query -W -f=${Status}\t${Version} python3-tk",
+                1,
+                "dpkg-query: no packages found matching python3-tk",
+            )
+            .failure("python3 -c import tkinter", 1, "ModuleNotFoundError")
+            // Removed, but its configuration files are left behind
+            .output(
+                "dpkg-query -W -f=${Status}\t${Version} libsdl2-dev",
+                "deinstall ok config-files\t2.30.0+dfsg-1build3",
+            )
+            .output("pkg-config --exists sdl2", "")
+            .output(
+                "dpkg-query -W -f=${Status}\t${Version} libmagic1",
+                "install ok installed\t1:5.45-3build1",
+            )
+            .output("gcc -m32 -print-file-name=crt1.o", "crt1.o")
+    }
+
+    #[test]
+    fn detects_distro_through_executor() {
+        let exec = ubuntu();
+        let distro = distro::detect(&exec);
+        assert_eq!(distro.family, distro::DistroFamily::Debian);
+        assert_eq!(distro.name, "Ubuntu 24.04 LTS");
+        assert_eq!(distro.version_id.as_deref(), Some("24.04"));
+    }
+
+    #[test]
+    fn ubuntu_report() {
+        let exec = ubuntu();
+        let distro = distro::parse_os_release(UBUNTU_OS_RELEASE, "x86_64");
+        let report = linux_report(&exec, &distro);
+
+        assert_eq!(report.os, "linux");
+        assert!(report.all_satisfied);
+
+        let cmake = find(&report, "CMake");
+        assert!(cmake.is_ok());
+        assert_eq!(cmake.version.as_deref(), Some("3.28.3"));
+        assert_eq!(find(&report, "GCC").version.as_deref(), Some("13.2.0"));
+        assert_eq!(find(&report, "File").version.as_deref(), Some("5.45"));
+        assert!(find(&report, "python3-venv").is_ok());
+
+        let ccache = find(&report, "CCache");
+        assert_eq!(ccache.status, DependencyStatus::Missing);
+        assert_eq!(ccache.level, DependencyLevel::Optional);
+
+        // Package database first, file probes as a fallback
+        let dev = find(&report, "python3-dev");
+        assert!(dev.is_ok());
+        assert_eq!(dev.version.as_deref(), Some("3.12.3-0ubuntu1"));
+        let sdl = find(&report, "libsdl2-dev");
+        assert!(sdl.is_ok());
+        assert_eq!(sdl.version, None);
+        assert!(!find(&report, "python3-tk").is_ok());
+        assert!(!find(&report, "gcc-multilib").is_ok());
+
+        assert_eq!(
+            report.missing_optional,
+            vec![
+                "CCache",
+                "Dfu-util",
+                "G++",
+                "python3-tk",
+                "gcc-multilib",
+                "g++-multilib"
+            ]
+        );
+
+        let calls = exec.calls();
+        assert!(calls.contains(&"pkg-config --exists sdl2".to_string()));
+        // Installed according to dpkg, so the header probe is skipped
+        assert!(!calls.iter().any(|c| c.contains("sysconfig")));
+    }
+
+    #[test]
+    fn ubuntu_install_plan() {
+        let exec = ubuntu();
+        let distro = distro::parse_os_release(UBUNTU_OS_RELEASE, "x86_64");
+        let report = linux_report(&exec, &distro);
+        let plan = linux_install_plan(&distro, &missing(&report));
+
+        assert_eq!(plan.distro.as_deref(), Some("Ubuntu 24.04 LTS"));
+        assert_eq!(plan.package_manager.as_deref(), Some("apt"));
+        assert_eq!(
+            plan.command_lines,
+            vec![
+                "env DEBIAN_FRONTEND=noninteractive apt install -y --no-install-recommends \
+                 ccache dfu-util g++ python3-tk gcc-multilib g++-multilib"
+            ]
+        );
+        assert_eq!(plan.commands[0].program, "apt");
+        assert_eq!(plan.mappings.len(), 6);
+        assert!(plan.unmapped.is_empty());
+    }
+
+    #[test]
+    fn default_plan_leaves_out_optional_dependencies() {
+        let exec = ubuntu();
+        let distro = distro::parse_os_release(UBUNTU_OS_RELEASE, "x86_64");
+        let report = linux_report(&exec, &distro);
+
+        // Only optional tools and G++ (recommended) are missing
+        let plan = linux_install_plan(&distro, &planned_dependencies(&report, false));
+        assert!(plan.is_empty());
+        assert!(plan.unmapped.is_empty());
+
+        let plan = linux_install_plan(&distro, &planned_dependencies(&report, true));
+        assert_eq!(
+            plan.command_lines,
+            vec!["env DEBIAN_FRONTEND=noninteractive apt install -y --no-install-recommends g++"]
+        );
+
+        let exec = fedora();
+        let distro = distro::parse_os_release(FEDORA_OS_RELEASE, "x86_64");
+        let report = linux_report(&exec, &distro);
+        let plan = linux_install_plan(&distro, &planned_dependencies(&report, false));
+        assert_eq!(plan.command_lines, vec!["dnf install -y cmake ninja-build"]);
+    }
+
+    const FEDORA_OS_RELEASE: &str = "NAME=\"Fedora Linux\"\nVERSION_ID=40\nID=fedora\n\
+        PRETTY_NAME=\"Fedora Linux 40 (Workstation Edition)\"\n";
+
+    fn rpm_query(package: &str) -> String {
+        format!("rpm -q --qf %{{VERSION}}-%{{RELEASE}}\n {}", package)
+    }
+
+    fn fedora() -> FakeExecutor {
+        FakeExecutor::new()
+            .file("/etc/os-release", FEDORA_OS_RELEASE)
+            .output("git --version", "git version 2.45.2")
+            .output("cmake --version", "cmake version 3.18.4")
+            .output("gperf --version", "GNU gperf 3.1")
+            .output("ccache --version", "ccache version 4.9.1")
+            .output("dtc --version", "Version: DTC 1.7.0")
+            .output(
+                "wget --version",
+                "GNU Wget2 2.1.0 - multithreaded metalink/file/website downloader",
+            )
+            .output("python3 --version", "Python 3.12.4")
+            .output("xz --version", "xz (XZ Utils) 5.4.6")
+            .output("file --version", "file-5.45")
+            .output("make --version", "GNU Make 4.4.1")
+            .output(
+                "gcc --version",
+                "gcc (GCC) 14.1.1 20240620 (Red Hat 14.1.1-6)",
+            )
+            .output("python3 -m venv --help", "usage: venv")
+            .output(&rpm_query("python3-devel"), "3.12.4-1.fc40\n")
+            .failure(&rpm_query("python3-tkinter"), 1, "")
+            .failure(&rpm_query("sdl2-compat-devel"), 1, "")
+            .output(&rpm_query("file-libs"), "5.45-4.fc40\n")
+            .output(&rpm_query("glibc-devel.i686"), "2.39-17.fc40\n")
+            .failure(&rpm_query("libstdc++-devel.i686"), 1, "")
+    }
+
+    #[test]
+    fn fedora_report() {
+        let exec = fedora();
+        let distro = distro::detect(&exec);
+        assert_eq!(distro.family, distro::DistroFamily::Fedora);
+        let distro = distro::parse_os_release(FEDORA_OS_RELEASE, "x86_64");
+        let report = linux_report(&exec, &distro);
+
+        assert!(!report.all_satisfied);
+        let cmake = find(&report, "CMake");
+        assert_eq!(cmake.status, DependencyStatus::TooOld);
+        assert_eq!(cmake.version.as_deref(), Some("3.18.4"));
+        assert_eq!(find(&report, "Ninja").status, DependencyStatus::Missing);
+        assert_eq!(find(&report, "Wget").version.as_deref(), Some("2.1.0"));
+
+        assert_eq!(
+            find(&report, "libmagic1").version.as_deref(),
+            Some("5.45-4.fc40")
+        );
+        assert!(find(&report, "gcc-multilib").is_ok());
+        assert!(!find(&report, "g++-multilib").is_ok());
+        assert!(!find(&report, "libsdl2-dev").is_ok());
+    }
+
+    #[test]
+    fn fedora_install_plan() {
+        let exec = fedora();
+        let distro = distro::parse_os_release(FEDORA_OS_RELEASE, "x86_64");
+        let report = linux_report(&exec, &distro);
+        let plan = linux_install_plan(&distro, &missing(&report));
+
+        assert_eq!(plan.package_manager.as_deref(), Some("dnf"));
+        assert_eq!(
+            plan.command_lines,
+            vec![
+                "dnf install -y cmake ninja-build dfu-util gcc-c++ python3-tkinter \
+                 sdl2-compat-devel libstdc++-devel.i686"
+            ]
+        );
+        let sdl = plan
+            .mappings
+            .iter()
+            .find(|m| m.dependency == "libsdl2-dev")
+            .unwrap();
+        assert_eq!(sdl.packages, vec!["sdl2-compat-devel"]);
+    }
+
+    #[test]
+    fn multilib_is_not_checked_on_aarch64() {
+        let distro = distro::parse_os_release(FEDORA_OS_RELEASE, "aarch64");
+        let report = linux_report(&fedora(), &distro);
+        assert!(!report
+            .dependencies
+            .iter()
+            .any(|d| d.name.ends_with("-multilib")));
+    }
+
+    #[test]
+    fn unknown_distro_needs_manual_install() {
+        let distro = distro::parse_os_release("ID=plan9\nNAME=Plan 9\n", "x86_64");
+        let report = linux_report(&FakeExecutor::new(), &distro);
+        let plan = linux_install_plan(&distro, &missing(&report));
+
+        assert!(plan.is_empty());
+        assert_eq!(plan.package_manager, None);
+        assert_eq!(plan.unmapped.len(), report.dependencies.len());
+    }
+
+    fn windows() -> FakeExecutor {
+        FakeExecutor::new()
+            .output("cmake --version", "cmake version 3.29.2")
+            .output("python --version", "Python 3.12.3")
+            .output("git --version", "git version 2.45.1.windows.1")
+            .output(
+                "7z --help",
+                "\n7-Zip 23.01 (x64) : Copyright (c) 1999-2023 Igor Pavlov : 2023-06-20\n\nUsage: 7z <command>",
+            )
+    }
+
+    #[test]
+    fn windows_report_and_plan() {
+        let report = windows_report(&windows());
+
+        assert_eq!(report.os, "windows");
+        assert!(!report.all_satisfied);
+        assert!(find(&report, "Python 3.12").is_ok());
+        assert_eq!(find(&report, "7-Zip").version.as_deref(), Some("23.01"));
+        assert_eq!(find(&report, "Ninja").status, DependencyStatus::Missing);
+
+        let plan = windows_install_plan(&missing(&report));
+        assert_eq!(plan.package_manager.as_deref(), Some("winget"));
+        assert_eq!(
+            plan.command_lines,
+            vec![
+                "winget source remove winget",
+                "winget source add winget https://mirrors.ustc.edu.cn/winget-source --trust-level trusted",
+                "winget install Ninja-build.Ninja",
+                "winget install oss-winget.gperf",
+                "winget install oss-winget.dtc",
+                "winget install wget",
+            ]
+        );
+        assert!(plan.unmapped.is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn terminal_fallback_spawns_first_available_terminal() {
+        let exec = FakeExecutor::new().program("konsole", "/usr/bin/konsole");
+        launch_in_terminal(&exec, "sudo apt install -y git").unwrap();
+        assert_eq!(
+            exec.calls(),
+            vec![
+                "konsole -e bash -c sudo apt install -y git; echo 'Press Enter to close...'; read"
+            ]
+        );
+
+        assert!(launch_in_terminal(&FakeExecutor::new(), "sudo apt install -y git").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn askpass_lookup() {
+        let exec = FakeExecutor::new()
+            .env("SUDO_ASKPASS", "/home/me/bin/askpass")
+            .file("/home/me/bin/askpass", "");
+        assert_eq!(find_askpass(&exec).as_deref(), Some("/home/me/bin/askpass"));
+
+        let exec = FakeExecutor::new().file("/usr/lib/ssh/ssh-askpass", "");
+        assert_eq!(
+            find_askpass(&exec).as_deref(),
+            Some("/usr/lib/ssh/ssh-askpass")
+        );
+        assert_eq!(find_askpass(&FakeExecutor::new()), None);
+    }
+
+    const BREW: &str = "/opt/homebrew/bin/brew";
+
+    fn macos(with_brew: bool) -> FakeExecutor {
+        let exec = FakeExecutor::new()
+            // Xcode's git, only reachable through PATH
+            .output("git --version", "git version 2.39.3 (Apple Git-146)")
+            .output("dtc --version", "Version: DTC 1.7.0");
+        if !with_brew {
+            return exec;
+        }
+        // Not on PATH, as for an app started from Finder
+        exec.file(BREW, "")
+            .output(&format!("{} --version", BREW), "Homebrew 4.3.5")
+            .output(
+                &format!("{} list --versions --formula", BREW),
+                "cmake 3.18.0\npython@3.12 3.12.4\npython-tk@3.12 3.12.4\nqemu 9.0.1\n",
+            )
+            .output(
+                "/opt/homebrew/bin/cmake --version",
+                "cmake version 3.18.0\n\nCMake suite maintained by Kitware",
+            )
+            .output("/opt/homebrew/bin/python3 --version", "Python 3.12.4")
+    }
+
+    #[test]
+    fn macos_detection_uses_brew_prefix_and_path() {
+        let report = macos_report(&macos(true));
+
+        let homebrew = find(&report, "Homebrew");
+        assert!(homebrew.is_ok());
+        assert_eq!(homebrew.version.as_deref(), Some("4.3.5"));
+
+        let cmake = find(&report, "CMake");
+        assert_eq!(cmake.status, DependencyStatus::TooOld);
+        assert_eq!(cmake.required_version.as_deref(), Some(MIN_CMAKE));
+
+        assert!(find(&report, "Git").is_ok());
+        assert!(find(&report, "Python 3").is_ok());
+        assert_eq!(find(&report, "DTC").version.as_deref(), Some("1.7.0"));
+        assert_eq!(find(&report, "Ninja").status, DependencyStatus::Missing);
+
+        let tk = find(&report, "python-tk");
+        assert!(tk.is_ok());
+        assert_eq!(tk.version.as_deref(), Some("3.12.4"));
+        assert!(find(&report, "QEMU").is_ok());
+
+        let openocd = find(&report, "OpenOCD");
+        assert!(!openocd.is_ok());
+        assert_eq!(openocd.level, DependencyLevel::Optional);
+
+        assert!(!report.all_satisfied);
+        assert!(report.missing_optional.contains(&"OpenOCD".to_string()));
+    }
+
+    #[test]
+    fn macos_detection_without_homebrew() {
+        let report = macos_report(&macos(false));
+
+        let homebrew = find(&report, "Homebrew");
+        assert_eq!(homebrew.status, DependencyStatus::Missing);
+        assert_eq!(homebrew.level, DependencyLevel::Required);
+        // Tools on PATH are still found
+        assert!(find(&report, "Git").is_ok());
+        assert!(!find(&report, "Python 3").is_ok());
+    }
+
+    #[test]
+    fn macos_plan_installs_missing_and_upgrades_outdated() {
+        let exec = macos(true);
+        let report = macos_report(&exec);
+        let installed = brew_installed_formulae(&exec, Path::new(BREW));
+        let plan = macos_install_plan(Some(Path::new(BREW)), &installed, &missing(&report));
+
+        assert_eq!(plan.package_manager.as_deref(), Some("brew"));
+        assert_eq!(
+            plan.command_lines,
+            vec![
+                "env HOMEBREW_NO_AUTO_UPDATE=1 /opt/homebrew/bin/brew install ninja gperf ccache wget xz libmagic open-ocd",
+                "env HOMEBREW_NO_AUTO_UPDATE=1 /opt/homebrew/bin/brew upgrade cmake",
+            ]
+        );
+        assert!(plan.unmapped.is_empty());
+
+        let cmake = plan
+            .mappings
+            .iter()
+            .find(|m| m.dependency == "CMake")
+            .unwrap();
+        assert_eq!(cmake.packages, vec!["cmake"]);
+    }
+
+    #[test]
+    fn macos_plan_without_homebrew_is_manual() {
+        let report = macos_report(&macos(false));
+        let plan = macos_install_plan(None, &BTreeMap::new(), &missing(&report));
+
+        assert!(plan.is_empty());
+        assert!(plan.package_manager.is_none());
+        assert!(plan.unmapped.contains(&"Homebrew".to_string()));
+        assert!(plan.unmapped.contains(&"CMake".to_string()));
     }
 }
@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Result of a command that ran to completion.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Everything the environment checks and installers ask of the host system,
+/// so they can run against canned outputs in tests.
+pub trait CommandExecutor {
+    /// Run a program to completion; `None` when it could not be started.
+    fn run(&self, program: &str, args: &[&str]) -> Option<CommandOutput>;
+
+    /// Start a program without waiting for it.
+    fn spawn(&self, program: &str, args: &[&str]) -> Result<(), String>;
+
+    /// Locate a program on PATH.
+    fn which(&self, program: &str) -> Option<PathBuf>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    fn read_to_string(&self, path: &Path) -> Option<String>;
+
+    fn env_var(&self, key: &str) -> Option<String>;
+}
+
+/// The real system.
+pub struct SystemExecutor;
+
+impl CommandExecutor for SystemExecutor {
+    fn run(&self, program: &str, args: &[&str]) -> Option<CommandOutput> {
+        let output = Command::new(program).args(args).output().ok()?;
+        Some(CommandOutput {
+            success: output.status.success(),
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    fn spawn(&self, program: &str, args: &[&str]) -> Result<(), String> {
+        Command::new(program)
+            .args(args)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        which::which(program).ok()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn read_to_string(&self, path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    fn env_var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+}
+
+/// Canned outputs keyed by command line (`program arg1 arg2`), recording
+/// every call made through it.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeExecutor {
+    outputs: std::collections::HashMap<String, CommandOutput>,
+    programs: std::collections::HashMap<String, PathBuf>,
+    files: std::collections::HashMap<PathBuf, String>,
+    env: std::collections::HashMap<String, String>,
+    calls: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl FakeExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn command_line(program: &str, args: &[&str]) -> String {
+        std::iter::once(program)
+            .chain(args.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A command that succeeds with `stdout`. Its program is also put on PATH.
+    pub fn output(mut self, command_line: &str, stdout: &str) -> Self {
+        let program = command_line.split(' ').next().unwrap_or_default();
+        if !program.contains('/') {
+            self.programs
+                .insert(program.to_string(), Path::new("/usr/bin").join(program));
+        }
+        self.outputs.insert(
+            command_line.to_string(),
+            CommandOutput {
+                success: true,
+                code: Some(0),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            },
+        );
+        self
+    }
+
+    /// A command that exits with `code`.
+    pub fn failure(mut self, command_line: &str, code: i32, stderr: &str) -> Self {
+        self.outputs.insert(
+            command_line.to_string(),
+            CommandOutput {
+                success: false,
+                code: Some(code),
+                stdout: String::new(),
+                stderr: stderr.to_string(),
+            },
+        );
+        self
+    }
+
+    pub fn program(mut self, name: &str, path: &str) -> Self {
+        self.programs.insert(name.to_string(), PathBuf::from(path));
+        self
+    }
+
+    pub fn file(mut self, path: &str, content: &str) -> Self {
+        self.files.insert(PathBuf::from(path), content.to_string());
+        self
+    }
+
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Command lines run or spawned so far, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[cfg(test)]
+impl CommandExecutor for FakeExecutor {
+    fn run(&self, program: &str, args: &[&str]) -> Option<CommandOutput> {
+        let line = Self::command_line(program, args);
+        let output = self.outputs.get(&line).cloned();
+        self.record(line);
+        output
+    }
+
+    fn spawn(&self, program: &str, args: &[&str]) -> Result<(), String> {
+        self.record(Self::command_line(program, args));
+        match self.which(program) {
+            Some(_) => Ok(()),
+            None => Err(format!("{} not found", program)),
+        }
+    }
+
+    fn which(&self, program: &str) -> Option<PathBuf> {
+        self.programs.get(program).cloned()
+    }
+
+    /// Directories exist when a fake file or program lies under them.
+    fn exists(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .chain(self.programs.values())
+            .any(|p| p.starts_with(path))
+    }
+
+    fn read_to_string(&self, path: &Path) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    fn env_var(&self, key: &str) -> Option<String> {
+        self.env.get(key).cloned()
+    }
+}
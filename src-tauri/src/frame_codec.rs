@@ -0,0 +1,811 @@
+use crate::plot_buffer;
+use crate::serial_log;
+use crate::thread_stats;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+// RoboMaster referee-system framing:
+// SOF(0xA5) | data_length u16 | seq u8 | CRC8 | cmd_id u16 | data | CRC16
+// All multi-byte header fields are little-endian.
+pub const SOF: u8 = 0xA5;
+const HEADER_LEN: usize = 5;
+const CMD_LEN: usize = 2;
+const TAIL_LEN: usize = 2;
+/// Longer lengths are treated as corruption rather than waited for.
+pub const MAX_DATA_LEN: usize = 1024;
+
+/// CRC-8/MAXIM reflected polynomial with initial value 0xFF, as used for the
+/// frame header.
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0xFFu8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8C
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-16/MCRF4XX (reflected 0x1021, initial value 0xFFFF) over the whole
+/// frame.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// Build a complete frame around `data`.
+pub fn encode_frame(cmd_id: u16, seq: u8, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() > MAX_DATA_LEN {
+        return Err(format!(
+            "Payload of {} bytes exceeds the {} byte limit",
+            data.len(),
+            MAX_DATA_LEN
+        ));
+    }
+    let mut frame = Vec::with_capacity(HEADER_LEN + CMD_LEN + data.len() + TAIL_LEN);
+    frame.push(SOF);
+    frame.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    frame.push(seq);
+    frame.push(crc8(&frame));
+    frame.extend_from_slice(&cmd_id.to_le_bytes());
+    frame.extend_from_slice(data);
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    Ok(frame)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    Bool,
+    /// Fixed number of bytes given by `length`, shown as hex
+    Bytes,
+}
+
+impl FieldType {
+    fn size(self) -> Option<usize> {
+        Some(match self {
+            FieldType::U8 | FieldType::I8 | FieldType::Bool => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+            FieldType::Bytes => return None,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: FieldType,
+    /// Falls back to the frame's endianness
+    #[serde(default)]
+    pub endian: Option<Endian>,
+    /// Only for `bytes`
+    #[serde(default)]
+    pub length: Option<usize>,
+    /// Decoded value is `raw * scale + offset`
+    #[serde(default)]
+    pub scale: Option<f64>,
+    #[serde(default)]
+    pub offset: Option<f64>,
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+impl FieldSchema {
+    fn size(&self) -> usize {
+        self.kind.size().unwrap_or(self.length.unwrap_or(0))
+    }
+
+    fn endian(&self, frame: &FrameSchema) -> Endian {
+        self.endian.unwrap_or(frame.endian)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FrameSchema {
+    pub cmd_id: u16,
+    pub name: String,
+    #[serde(default)]
+    pub endian: Endian,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl FrameSchema {
+    pub fn data_len(&self) -> usize {
+        self.fields.iter().map(|f| f.size()).sum()
+    }
+}
+
+/// Contents of a schema file.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SchemaFile {
+    pub frames: Vec<FrameSchema>,
+}
+
+pub fn parse_schemas(content: &str) -> Result<Vec<FrameSchema>, String> {
+    let file: SchemaFile =
+        serde_json::from_str(content).map_err(|e| format!("Invalid schema file: {}", e))?;
+
+    let mut seen = std::collections::HashSet::new();
+    for frame in &file.frames {
+        if !seen.insert(frame.cmd_id) {
+            return Err(format!("Duplicate cmd_id 0x{:04X}", frame.cmd_id));
+        }
+        for field in &frame.fields {
+            if field.kind == FieldType::Bytes && field.length.is_none() {
+                return Err(format!(
+                    "{}.{}: bytes fields need a length",
+                    frame.name, field.name
+                ));
+            }
+        }
+        if frame.data_len() > MAX_DATA_LEN {
+            return Err(format!(
+                "{}: frame is larger than {} bytes",
+                frame.name, MAX_DATA_LEN
+            ));
+        }
+    }
+    Ok(file.frames)
+}
+
+pub fn load_schemas(path: &Path) -> Result<Vec<FrameSchema>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_schemas(&content)
+}
+
+fn read_array<const N: usize>(bytes: &[u8], endian: Endian) -> [u8; N] {
+    let mut array: [u8; N] = bytes[..N].try_into().unwrap();
+    if endian == Endian::Big {
+        array.reverse();
+    }
+    array
+}
+
+fn decode_field(field: &FieldSchema, bytes: &[u8], endian: Endian) -> Value {
+    // Native representation first, then scaling as f64
+    let raw: Value = match field.kind {
+        FieldType::U8 => bytes[0].into(),
+        FieldType::I8 => (bytes[0] as i8).into(),
+        FieldType::U16 => u16::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::I16 => i16::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::U32 => u32::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::I32 => i32::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::U64 => u64::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::I64 => i64::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::F32 => (f32::from_le_bytes(read_array(bytes, endian)) as f64).into(),
+        FieldType::F64 => f64::from_le_bytes(read_array(bytes, endian)).into(),
+        FieldType::Bool => (bytes[0] != 0).into(),
+        FieldType::Bytes => bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+            .into(),
+    };
+
+    if field.scale.is_none() && field.offset.is_none() {
+        return raw;
+    }
+    match raw.as_f64() {
+        Some(v) => (v * field.scale.unwrap_or(1.0) + field.offset.unwrap_or(0.0)).into(),
+        None => raw,
+    }
+}
+
+/// `a5 0b 00`, `0xA5,0x0B`, `A50B00` and `a5:0b:00` are all accepted.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+        .map(|part| {
+            part.strip_prefix("0x")
+                .or_else(|| part.strip_prefix("0X"))
+                .unwrap_or(part)
+        })
+        .collect();
+    if !digits.is_ascii() {
+        return Err(format!("Invalid hex \"{}\"", text.trim()));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("Odd number of hex digits in \"{}\"", text.trim()));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("Invalid hex \"{}\"", &digits[i..i + 2]))
+        })
+        .collect()
+}
+
+/// The raw integer for a field value, undoing `scale`/`offset`.
+fn int_value<T: TryFrom<i128>>(field: &FieldSchema, value: &Value) -> Result<T, String> {
+    let scaled = field.scale.is_some() || field.offset.is_some();
+    let raw = match (value.as_i64(), value.as_u64()) {
+        (Some(n), _) if !scaled => n as i128,
+        (_, Some(n)) if !scaled => n as i128,
+        _ => {
+            let v = value
+                .as_f64()
+                .or_else(|| value.as_bool().map(|b| b as u8 as f64))
+                .ok_or_else(|| format!("{}: expected a number", field.name))?;
+            let raw = ((v - field.offset.unwrap_or(0.0)) / field.scale.unwrap_or(1.0)).round();
+            if !raw.is_finite() {
+                return Err(format!("{}: {} is not a finite number", field.name, v));
+            }
+            raw as i128
+        }
+    };
+    T::try_from(raw).map_err(|_| {
+        format!(
+            "{}: {} is out of range for {:?}",
+            field.name, raw, field.kind
+        )
+    })
+}
+
+fn float_value(field: &FieldSchema, value: &Value) -> Result<f64, String> {
+    let v = value
+        .as_f64()
+        .ok_or_else(|| format!("{}: expected a number", field.name))?;
+    Ok((v - field.offset.unwrap_or(0.0)) / field.scale.unwrap_or(1.0))
+}
+
+fn put<const N: usize>(out: &mut Vec<u8>, mut bytes: [u8; N], endian: Endian) {
+    if endian == Endian::Big {
+        bytes.reverse();
+    }
+    out.extend_from_slice(&bytes);
+}
+
+/// Encode field values (by field name) into a payload; the inverse of
+/// `decode_fields`.
+pub fn encode_fields(schema: &FrameSchema, values: &Map<String, Value>) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(schema.data_len());
+    for field in &schema.fields {
+        let value = values
+            .get(&field.name)
+            .ok_or_else(|| format!("{}: missing field {}", schema.name, field.name))?;
+        let endian = field.endian(schema);
+        match field.kind {
+            FieldType::U8 => put(
+                &mut out,
+                int_value::<u8>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::I8 => put(
+                &mut out,
+                int_value::<i8>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::U16 => put(
+                &mut out,
+                int_value::<u16>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::I16 => put(
+                &mut out,
+                int_value::<i16>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::U32 => put(
+                &mut out,
+                int_value::<u32>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::I32 => put(
+                &mut out,
+                int_value::<i32>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::U64 => put(
+                &mut out,
+                int_value::<u64>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::I64 => put(
+                &mut out,
+                int_value::<i64>(field, value)?.to_le_bytes(),
+                endian,
+            ),
+            FieldType::F32 => put(
+                &mut out,
+                (float_value(field, value)? as f32).to_le_bytes(),
+                endian,
+            ),
+            FieldType::F64 => put(&mut out, float_value(field, value)?.to_le_bytes(), endian),
+            FieldType::Bool => {
+                let on = value
+                    .as_bool()
+                    .or_else(|| value.as_f64().map(|v| v != 0.0))
+                    .ok_or_else(|| format!("{}: expected true or false", field.name))?;
+                out.push(on as u8);
+            }
+            FieldType::Bytes => {
+                let bytes = parse_hex(
+                    value
+                        .as_str()
+                        .ok_or_else(|| format!("{}: expected a hex string", field.name))?,
+                )?;
+                if bytes.len() != field.size() {
+                    return Err(format!(
+                        "{}: expected {} bytes, got {}",
+                        field.name,
+                        field.size(),
+                        bytes.len()
+                    ));
+                }
+                out.extend(bytes);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Decode a payload by its schema. Fields that don't fit are left out and
+/// reported in the error.
+pub fn decode_fields(schema: &FrameSchema, data: &[u8]) -> (Map<String, Value>, Option<String>) {
+    let mut fields = Map::new();
+    let mut offset = 0;
+    for field in &schema.fields {
+        let size = field.size();
+        let Some(bytes) = data.get(offset..offset + size) else {
+            return (
+                fields,
+                Some(format!(
+                    "Payload is {} bytes, {} expects {}",
+                    data.len(),
+                    schema.name,
+                    schema.data_len()
+                )),
+            );
+        };
+        fields.insert(
+            field.name.clone(),
+            decode_field(field, bytes, field.endian(schema)),
+        );
+        offset += size;
+    }
+    (fields, None)
+}
+
+/// Payload of the `frame-data` event.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct DecodedFrame {
+    pub port: String,
+    pub cmd_id: u16,
+    pub seq: u8,
+    /// Schema name, `None` for cmd_ids without a schema
+    pub name: Option<String>,
+    pub fields: Map<String, Value>,
+    pub data: Vec<u8>,
+    pub timestamp: u64, // Unix time in milliseconds
+    pub error: Option<String>,
+}
+
+/// Running counters, also the payload of the `frame-stats` event.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FrameStats {
+    pub port: String,
+    pub frames: u64,
+    pub crc8_errors: u64,
+    pub crc16_errors: u64,
+    pub unknown_cmd_ids: u64,
+    /// Bytes skipped while searching for a frame start
+    pub discarded_bytes: u64,
+}
+
+/// Streaming decoder for one port; bytes may arrive split at any point.
+pub struct FrameDecoder {
+    port: String,
+    schemas: BTreeMap<u16, FrameSchema>,
+    buffer: Vec<u8>,
+    stats: FrameStats,
+}
+
+impl FrameDecoder {
+    pub fn new(port: &str, schemas: Vec<FrameSchema>) -> Self {
+        Self {
+            port: port.to_string(),
+            schemas: schemas.into_iter().map(|s| (s.cmd_id, s)).collect(),
+            buffer: Vec::new(),
+            stats: FrameStats {
+                port: port.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    pub fn schemas(&self) -> impl Iterator<Item = &FrameSchema> {
+        self.schemas.values()
+    }
+
+    pub fn push(&mut self, bytes: &[u8], timestamp: u64) -> Vec<DecodedFrame> {
+        self.buffer.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+
+        loop {
+            // Drop everything before the next SOF
+            let start = self
+                .buffer
+                .iter()
+                .position(|&b| b == SOF)
+                .unwrap_or(self.buffer.len());
+            if start > 0 {
+                self.stats.discarded_bytes += start as u64;
+                self.buffer.drain(..start);
+            }
+            if self.buffer.len() < HEADER_LEN {
+                break;
+            }
+
+            let header = &self.buffer[..HEADER_LEN];
+            let data_len = u16::from_le_bytes([header[1], header[2]]) as usize;
+            if crc8(&header[..4]) != header[4] || data_len > MAX_DATA_LEN {
+                self.stats.crc8_errors += 1;
+                self.skip_sof();
+                continue;
+            }
+
+            let frame_len = HEADER_LEN + CMD_LEN + data_len + TAIL_LEN;
+            if self.buffer.len() < frame_len {
+                break;
+            }
+
+            let frame = &self.buffer[..frame_len];
+            let expected = u16::from_le_bytes([frame[frame_len - 2], frame[frame_len - 1]]);
+            if crc16(&frame[..frame_len - TAIL_LEN]) != expected {
+                self.stats.crc16_errors += 1;
+                self.skip_sof();
+                continue;
+            }
+
+            let seq = frame[3];
+            let cmd_id = u16::from_le_bytes([frame[5], frame[6]]);
+            let data = frame[HEADER_LEN + CMD_LEN..frame_len - TAIL_LEN].to_vec();
+            self.buffer.drain(..frame_len);
+            self.stats.frames += 1;
+
+            let (name, fields, error) = match self.schemas.get(&cmd_id) {
+                Some(schema) => {
+                    let (fields, error) = decode_fields(schema, &data);
+                    (Some(schema.name.clone()), fields, error)
+                }
+                None => {
+                    self.stats.unknown_cmd_ids += 1;
+                    (None, Map::new(), None)
+                }
+            };
+            frames.push(DecodedFrame {
+                port: self.port.clone(),
+                cmd_id,
+                seq,
+                name,
+                fields,
+                data,
+                timestamp,
+                error,
+            });
+        }
+
+        frames
+    }
+
+    /// Resynchronise after a corrupt frame: the SOF was a data byte.
+    fn skip_sof(&mut self) {
+        self.buffer.drain(..1);
+        self.stats.discarded_bytes += 1;
+    }
+}
+
+struct AttachedDecoder {
+    app: AppHandle,
+    decoder: FrameDecoder,
+    /// Off when only the plot buffers are wanted, e.g. for kHz streams
+    emit_frames: bool,
+}
+
+static DECODERS: Mutex<BTreeMap<String, AttachedDecoder>> = Mutex::new(BTreeMap::new());
+
+/// Decode received bytes if a decoder is attached to the port.
+pub(crate) fn feed(port: &str, data: &[u8]) {
+    let mut decoders = DECODERS.lock().unwrap();
+    let Some(attached) = decoders.get_mut(port) else {
+        return;
+    };
+
+    let errors_before = attached.decoder.stats.crc8_errors + attached.decoder.stats.crc16_errors;
+    for frame in attached.decoder.push(data, serial_log::now_millis()) {
+        plot_buffer::record_frame(&frame);
+        thread_stats::record_frame(&frame);
+        if attached.emit_frames {
+            let _ = attached.app.emit("frame-data", frame);
+        }
+    }
+    let stats = attached.decoder.stats();
+    if stats.crc8_errors + stats.crc16_errors != errors_before {
+        let _ = attached.app.emit("frame-stats", stats.clone());
+    }
+}
+
+/// Schemas of the decoder attached to a port, if any.
+pub(crate) fn attached_schemas(port: &str) -> Option<Vec<FrameSchema>> {
+    DECODERS
+        .lock()
+        .unwrap()
+        .get(port)
+        .map(|attached| attached.decoder.schemas().cloned().collect())
+}
+
+#[tauri::command]
+pub fn load_frame_schemas(path: String) -> Result<Vec<FrameSchema>, String> {
+    load_schemas(Path::new(&path))
+}
+
+/// Start decoding frames on a port. Without a schema file every frame is
+/// reported undecoded. Decoded numeric fields always go to the plot buffers;
+/// `emit_frames: false` skips the per-frame `frame-data` events.
+#[tauri::command]
+pub fn attach_frame_decoder(
+    app: AppHandle,
+    port: String,
+    schema_path: Option<String>,
+    emit_frames: Option<bool>,
+) -> Result<(), String> {
+    let schemas = match schema_path {
+        Some(path) => load_schemas(Path::new(&path))?,
+        None => Vec::new(),
+    };
+    let decoder = FrameDecoder::new(&port, schemas);
+    DECODERS.lock().unwrap().insert(
+        port,
+        AttachedDecoder {
+            app,
+            decoder,
+            emit_frames: emit_frames.unwrap_or(true),
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn detach_frame_decoder(port: String) -> Result<FrameStats, String> {
+    DECODERS
+        .lock()
+        .unwrap()
+        .remove(&port)
+        .map(|attached| attached.decoder.stats)
+        .ok_or_else(|| format!("No frame decoder on {}", port))
+}
+
+#[tauri::command]
+pub fn get_frame_stats(port: String) -> Result<FrameStats, String> {
+    DECODERS
+        .lock()
+        .unwrap()
+        .get(&port)
+        .map(|attached| attached.decoder.stats.clone())
+        .ok_or_else(|| format!("No frame decoder on {}", port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMAS: &str = r#"{
+        "frames": [
+            {
+                "cmd_id": 1,
+                "name": "game_status",
+                "fields": [
+                    { "name": "game_progress", "type": "u8" },
+                    { "name": "stage_remain_time", "type": "u16", "unit": "s" },
+                    { "name": "sync_time_stamp", "type": "u64" }
+                ]
+            },
+            {
+                "cmd_id": 769,
+                "name": "chassis",
+                "endian": "big",
+                "fields": [
+                    { "name": "voltage", "type": "u16", "scale": 0.001, "unit": "V" },
+                    { "name": "yaw", "type": "f32", "endian": "little" },
+                    { "name": "armed", "type": "bool" },
+                    { "name": "id", "type": "bytes", "length": 2 }
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn computes_reference_crcs() {
+        assert_eq!(crc16(b"123456789"), 0x6F91);
+        assert_eq!(crc8(b"123456789"), 0x0B);
+    }
+
+    #[test]
+    fn encodes_referee_frame() {
+        let mut data = vec![0x14];
+        data.extend_from_slice(&420u16.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        let frame = encode_frame(0x0001, 7, &data).unwrap();
+        assert_eq!(
+            frame,
+            [
+                0xA5, 0x0B, 0x00, 0x07, 0x81, 0x01, 0x00, 0x14, 0xA4, 0x01, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0x00, 0xEF, 0xDE
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_frames_split_across_reads() {
+        let schemas = parse_schemas(SCHEMAS).unwrap();
+        let mut decoder = FrameDecoder::new("COM3", schemas);
+
+        let mut data = vec![0x14];
+        data.extend_from_slice(&420u16.to_le_bytes());
+        data.extend_from_slice(&99u64.to_le_bytes());
+        let frame = encode_frame(1, 3, &data).unwrap();
+
+        // Leading noise, then the frame in two pieces
+        assert!(decoder.push(&[0x00, 0x13], 0).is_empty());
+        assert!(decoder.push(&frame[..6], 0).is_empty());
+        let frames = decoder.push(&frame[6..], 42);
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].name.as_deref(), Some("game_status"));
+        assert_eq!(frames[0].seq, 3);
+        assert_eq!(frames[0].timestamp, 42);
+        assert_eq!(frames[0].fields["stage_remain_time"], 420);
+        assert_eq!(frames[0].fields["sync_time_stamp"], 99);
+        assert_eq!(decoder.stats().discarded_bytes, 2);
+    }
+
+    #[test]
+    fn applies_endianness_and_scaling() {
+        let schemas = parse_schemas(SCHEMAS).unwrap();
+        let mut decoder = FrameDecoder::new("COM3", schemas);
+
+        let mut data = 24_500u16.to_be_bytes().to_vec();
+        data.extend_from_slice(&1.5f32.to_le_bytes());
+        data.extend_from_slice(&[1, 0xBE, 0xEF]);
+        let frames = decoder.push(&encode_frame(769, 0, &data).unwrap(), 0);
+
+        let fields = &frames[0].fields;
+        assert!((fields["voltage"].as_f64().unwrap() - 24.5).abs() < 1e-9);
+        assert_eq!(fields["yaw"], 1.5);
+        assert_eq!(fields["armed"], true);
+        assert_eq!(fields["id"], "beef");
+        assert!(frames[0].error.is_none());
+    }
+
+    #[test]
+    fn counts_crc_errors_and_resyncs() {
+        let mut decoder = FrameDecoder::new("COM3", Vec::new());
+        let good = encode_frame(0x0301, 1, &[1, 2, 3]).unwrap();
+
+        let mut bad_header = good.clone();
+        bad_header[4] ^= 0xFF;
+        let mut bad_body = good.clone();
+        bad_body[8] ^= 0xFF;
+
+        let mut stream = bad_header;
+        stream.extend_from_slice(&bad_body);
+        stream.extend_from_slice(&good);
+        let frames = decoder.push(&stream, 0);
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].cmd_id, 0x0301);
+        assert_eq!(frames[0].data, [1, 2, 3]);
+        assert!(frames[0].name.is_none());
+
+        let stats = decoder.stats();
+        assert_eq!(stats.crc8_errors, 1);
+        assert_eq!(stats.crc16_errors, 1);
+        assert_eq!(stats.unknown_cmd_ids, 1);
+        assert_eq!(stats.frames, 1);
+    }
+
+    #[test]
+    fn reports_short_payloads() {
+        let schemas = parse_schemas(SCHEMAS).unwrap();
+        let mut decoder = FrameDecoder::new("COM3", schemas);
+        let frames = decoder.push(&encode_frame(1, 0, &[0x14, 0xA4]).unwrap(), 0);
+        assert_eq!(frames[0].fields.len(), 1);
+        assert!(frames[0].error.as_deref().unwrap().contains("expects 11"));
+    }
+
+    #[test]
+    fn encodes_fields_as_decoded() {
+        let schemas = parse_schemas(SCHEMAS).unwrap();
+        let chassis = &schemas[1];
+        let values: Map<String, Value> =
+            serde_json::from_str(r#"{"voltage": 24.5, "yaw": 1.5, "armed": true, "id": "be ef"}"#)
+                .unwrap();
+
+        let data = encode_fields(chassis, &values).unwrap();
+        assert_eq!(data[..2], 24_500u16.to_be_bytes());
+        let (fields, error) = decode_fields(chassis, &data);
+        assert!(error.is_none());
+        assert_eq!(fields["yaw"], 1.5);
+        assert_eq!(fields["id"], "beef");
+
+        let mut too_big = values.clone();
+        too_big.insert("voltage".into(), 70.0.into());
+        assert!(encode_fields(chassis, &too_big)
+            .unwrap_err()
+            .contains("out of range"));
+        let mut missing = values;
+        missing.remove("armed");
+        assert!(encode_fields(chassis, &missing).is_err());
+    }
+
+    #[test]
+    fn parses_hex_notations() {
+        assert_eq!(parse_hex("a5 0b 00").unwrap(), [0xA5, 0x0B, 0x00]);
+        assert_eq!(parse_hex("0xA5,0x0B").unwrap(), [0xA5, 0x0B]);
+        assert_eq!(parse_hex("a5:0B:00").unwrap(), [0xA5, 0x0B, 0x00]);
+        assert_eq!(parse_hex("A50B").unwrap(), [0xA5, 0x0B]);
+        assert!(parse_hex("a5 0").is_err());
+        assert!(parse_hex("zz").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_schemas() {
+        let duplicate = r#"{"frames": [
+            {"cmd_id": 1, "name": "a", "fields": []},
+            {"cmd_id": 1, "name": "b", "fields": []}
+        ]}"#;
+        assert!(parse_schemas(duplicate).is_err());
+
+        let no_length = r#"{"frames": [
+            {"cmd_id": 2, "name": "a", "fields": [{"name": "x", "type": "bytes"}]}
+        ]}"#;
+        assert!(parse_schemas(no_length).is_err());
+    }
+}
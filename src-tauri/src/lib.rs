@@ -1,9 +1,29 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 
+#[cfg(any(target_os = "macos", test))]
+mod brew;
 mod cmd_west;
 mod cmd_zephyr;
+mod command_center;
 mod config_manager;
+mod coredump;
+#[cfg(any(target_os = "linux", test))]
+mod distro;
+mod doctor;
 mod env_manager;
+mod executor;
+mod frame_codec;
+mod log_dictionary;
+mod plot_buffer;
+mod rtt;
+mod sdk_manager;
+mod serial_comm;
+mod serial_log;
+mod thread_stats;
+mod toolchain_manager;
+mod venv_manager;
+mod version;
+mod zephyr_shell;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,7 +35,9 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             env_manager::check_environment,
             env_manager::check_dependencies,
+            env_manager::plan_dependency_install,
             env_manager::install_dependencies,
+            doctor::generate_doctor_report,
             cmd_west::run_west_command,
             cmd_west::run_west_stream,
             cmd_west::west_init,
@@ -35,7 +57,61 @@ pub fn run() {
             config_manager::get_home_dir,
             config_manager::detect_zephyr_sdk_path,
             config_manager::detect_venv_path,
-            cmd_zephyr::install_zephyr
+            cmd_zephyr::install_zephyr,
+            toolchain_manager::list_toolchains,
+            toolchain_manager::scan_toolchains,
+            toolchain_manager::save_toolchain,
+            toolchain_manager::remove_toolchain,
+            toolchain_manager::set_default_toolchain,
+            toolchain_manager::bind_project_toolchain,
+            toolchain_manager::get_project_toolchain,
+            sdk_manager::discover_zephyr_sdks,
+            sdk_manager::inspect_zephyr_sdk,
+            sdk_manager::list_installed_sdks,
+            sdk_manager::install_zephyr_sdk,
+            sdk_manager::uninstall_zephyr_sdk,
+            venv_manager::discover_python_envs,
+            venv_manager::check_python_requirements,
+            venv_manager::install_missing_requirements,
+            serial_comm::list_serial_ports,
+            serial_comm::open_serial_port,
+            serial_comm::close_serial_port,
+            serial_comm::write_serial,
+            serial_comm::list_open_serial_ports,
+            serial_log::start_serial_capture,
+            serial_log::stop_serial_capture,
+            serial_log::list_serial_captures,
+            serial_log::open_serial_capture,
+            frame_codec::load_frame_schemas,
+            frame_codec::attach_frame_decoder,
+            frame_codec::detach_frame_decoder,
+            frame_codec::get_frame_stats,
+            zephyr_shell::shell_detect_prompt,
+            zephyr_shell::shell_exec,
+            zephyr_shell::shell_command_tree,
+            zephyr_shell::shell_complete,
+            plot_buffer::list_plot_channels,
+            plot_buffer::query_plot_data,
+            plot_buffer::export_plot_csv,
+            plot_buffer::clear_plot_data,
+            command_center::encode_serial_command,
+            command_center::send_serial_command,
+            command_center::list_command_templates,
+            command_center::save_command_template,
+            command_center::delete_command_template,
+            command_center::replay_serial_capture,
+            command_center::stop_serial_replay,
+            rtt::open_rtt,
+            log_dictionary::find_log_dictionary,
+            log_dictionary::attach_log_decoder,
+            log_dictionary::set_log_filter,
+            log_dictionary::detach_log_decoder,
+            log_dictionary::decode_log_file,
+            thread_stats::start_thread_stats,
+            thread_stats::stop_thread_stats,
+            coredump::watch_coredumps,
+            coredump::unwatch_coredumps,
+            coredump::analyze_coredump
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,992 @@
+use crate::serial_log;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+/// Printed by the UART backend before dictionary output starts, so the
+/// stream can be picked up after a bootloader or a reset.
+pub const LOG_SEPARATOR: &[u8] = b"##ZLOGV1##";
+const MSG_NORMAL: u8 = 0;
+const MSG_DROPPED: u8 = 1;
+const INT_SIZE: usize = 4;
+/// Largest printf width or precision honoured; both come from untrusted
+/// bytes and size the padding allocations.
+const MAX_FIELD: usize = 4096;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    /// Level 0, e.g. printk routed through logging
+    Raw,
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    fn from_id(id: u8) -> Self {
+        match id {
+            1 => LogLevel::Error,
+            2 => LogLevel::Warning,
+            3 => LogLevel::Info,
+            4 => LogLevel::Debug,
+            _ => LogLevel::Raw,
+        }
+    }
+}
+
+/// `build/zephyr/log_dictionary.json` of the running firmware.
+#[derive(Debug, Clone)]
+pub struct LogDictionary {
+    little_endian: bool,
+    pointer_size: usize,
+    timestamp_size: usize,
+    /// Stack alignment of 64-bit arguments in a package
+    align_64: usize,
+    strings: BTreeMap<u64, String>,
+    sources: BTreeMap<u64, String>,
+}
+
+/// Keys are addresses/ids, written in decimal by Zephyr's database
+/// generator; hex is accepted too.
+fn parse_key(key: &str) -> Option<u64> {
+    match key.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => key.parse().ok(),
+    }
+}
+
+impl LogDictionary {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let db: Value =
+            serde_json::from_str(content).map_err(|e| format!("Invalid log dictionary: {}", e))?;
+        let version = db["version"].as_u64().unwrap_or(0);
+        if version < 3 {
+            return Err(format!(
+                "Unsupported log dictionary version {} (Zephyr 3.2 or newer is needed)",
+                version
+            ));
+        }
+
+        let bits = db["target"]["bits"].as_u64().unwrap_or(32);
+        let pointer_size = if bits == 64 { 8 } else { 4 };
+        let timestamp_64 = db["kconfigs"]
+            .get("CONFIG_LOG_TIMESTAMP_64BIT")
+            .is_some_and(|v| !v.is_null() && v != "n");
+        let arch = db["arch"]
+            .as_str()
+            .or_else(|| db["arch"]["arch"].as_str())
+            .unwrap_or_default();
+
+        let strings = db["string_mappings"]
+            .as_object()
+            .ok_or("Log dictionary has no string_mappings")?
+            .iter()
+            .filter_map(|(address, s)| Some((parse_key(address)?, s.as_str()?.to_string())))
+            .collect();
+        let sources = db["log_subsys"]["log_instances"]
+            .as_object()
+            .map(|instances| {
+                instances
+                    .iter()
+                    .filter_map(|(id, instance)| {
+                        Some((parse_key(id)?, instance["name"].as_str()?.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            little_endian: db["target"]["little_endianness"].as_bool().unwrap_or(true),
+            pointer_size,
+            timestamp_size: if timestamp_64 { 8 } else { 4 },
+            align_64: if arch == "x86" && bits == 32 { 4 } else { 8 },
+            strings,
+            sources,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&content)
+    }
+
+    fn uint(&self, bytes: &[u8], offset: usize, size: usize) -> Option<u64> {
+        let bytes = bytes.get(offset..offset + size)?;
+        let fold = |v: u64, b: &u8| v << 8 | *b as u64;
+        Some(if self.little_endian {
+            bytes.iter().rev().fold(0, fold)
+        } else {
+            bytes.iter().fold(0, fold)
+        })
+    }
+}
+
+/// Look for the dictionary given a project, a build directory or the file
+/// itself.
+pub fn find_dictionary(path: &Path) -> Result<PathBuf, String> {
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    [
+        path.join("zephyr").join("log_dictionary.json"),
+        path.join("build")
+            .join("zephyr")
+            .join("log_dictionary.json"),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.is_file())
+    .ok_or_else(|| {
+        format!(
+            "No log_dictionary.json under {}; build with CONFIG_LOG_DICTIONARY_SUPPORT=y",
+            path.display()
+        )
+    })
+}
+
+/// Reads the arguments of a cbprintf package in order.
+struct Args<'a> {
+    dictionary: &'a LogDictionary,
+    package: &'a [u8],
+    offset: usize,
+    /// Strings copied into the package, by their word index
+    strings: BTreeMap<usize, String>,
+}
+
+impl Args<'_> {
+    fn next(&mut self, size: usize, align: usize) -> Result<u64, String> {
+        self.offset = self.offset.next_multiple_of(align);
+        let value = self
+            .dictionary
+            .uint(self.package, self.offset, size)
+            .ok_or("Log message has fewer arguments than its format string")?;
+        self.offset += size;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let size = self.dictionary.pointer_size;
+        self.offset = self.offset.next_multiple_of(size);
+        let index = self.offset / INT_SIZE;
+        let pointer = self.next(size, size)?;
+        Ok(self
+            .dictionary
+            .strings
+            .get(&pointer)
+            .or_else(|| self.strings.get(&index))
+            .cloned()
+            .unwrap_or_else(|| format!("<string@0x{:x}>", pointer)))
+    }
+}
+
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    zero: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn pad(&self, body: String) -> String {
+        let len = body.chars().count();
+        if len >= self.width {
+            return body;
+        }
+        let fill = self.width - len;
+        if self.left {
+            format!("{}{}", body, " ".repeat(fill))
+        } else if self.zero {
+            // Zeros go between the sign/prefix and the digits
+            let digits = body.trim_start_matches(['-', '+', ' ']);
+            let digits = digits
+                .strip_prefix("0x")
+                .or_else(|| digits.strip_prefix("0X"))
+                .unwrap_or(digits);
+            let split = body.len() - digits.len();
+            format!("{}{}{}", &body[..split], "0".repeat(fill), digits)
+        } else {
+            format!("{}{}", " ".repeat(fill), body)
+        }
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        match (negative, self.plus, self.space) {
+            (true, _, _) => "-",
+            (false, true, _) => "+",
+            (false, false, true) => " ",
+            _ => "",
+        }
+    }
+
+    fn digits(&self, digits: String) -> String {
+        match self.precision {
+            Some(p) if digits.len() < p => format!("{}{}", "0".repeat(p - digits.len()), digits),
+            _ => digits,
+        }
+    }
+}
+
+/// `1.5e3` -> `1.500000e+03`
+fn c_exponent(v: f64, precision: usize, upper: bool) -> String {
+    let s = format!("{:.*e}", precision, v);
+    let (mantissa, exponent) = s.split_once('e').unwrap_or((&s, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let e = if upper { 'E' } else { 'e' };
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}{}{}{:02}", mantissa, e, sign, exponent.abs())
+}
+
+fn c_general(v: f64, precision: usize, alt: bool, upper: bool) -> String {
+    let precision = precision.max(1);
+    let exponent = if v == 0.0 {
+        0
+    } else {
+        v.abs().log10().floor() as i32
+    };
+    let s = if exponent < -4 || exponent >= precision as i32 {
+        c_exponent(v, precision - 1, upper)
+    } else {
+        format!("{:.*}", (precision as i32 - 1 - exponent) as usize, v)
+    };
+    if alt {
+        return s;
+    }
+    // Trailing zeros of the fraction are dropped
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => s.split_at(i),
+        None => (s.as_str(), ""),
+    };
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{}{}", mantissa, exponent)
+}
+
+/// Render a printf format string with arguments taken from a package.
+fn format_message(fmt: &str, args: &mut Args) -> Result<String, String> {
+    let pointer_size = args.dictionary.pointer_size;
+    let align_64 = args.dictionary.align_64;
+    let mut out = String::new();
+    let mut chars = fmt.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let mut spec = Spec::default();
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                '-' => spec.left = true,
+                '0' => spec.zero = true,
+                '+' => spec.plus = true,
+                ' ' => spec.space = true,
+                '#' => spec.alt = true,
+                _ => break,
+            }
+            chars.next();
+        }
+        if chars.next_if_eq(&'*').is_some() {
+            let width = args.next(INT_SIZE, INT_SIZE)? as u32 as i32;
+            spec.left |= width < 0;
+            spec.width = width.unsigned_abs() as usize;
+        }
+        while let Some(d) = chars.next_if(char::is_ascii_digit) {
+            spec.width = spec
+                .width
+                .saturating_mul(10)
+                .saturating_add(d.to_digit(10).unwrap() as usize);
+        }
+        if spec.width > MAX_FIELD {
+            return Err(format!("Field width {} is too large", spec.width));
+        }
+        if chars.next_if_eq(&'.').is_some() {
+            let mut precision = Some(0);
+            if chars.next_if_eq(&'*').is_some() {
+                // A negative precision is taken as if it were omitted
+                let value = args.next(INT_SIZE, INT_SIZE)? as u32 as i32;
+                precision = usize::try_from(value).ok();
+            }
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                precision = precision.map(|p| {
+                    p.saturating_mul(10)
+                        .saturating_add(d.to_digit(10).unwrap() as usize)
+                });
+            }
+            if let Some(p) = precision.filter(|&p| p > MAX_FIELD) {
+                return Err(format!("Precision {} is too large", p));
+            }
+            spec.precision = precision;
+        }
+        let mut length = String::new();
+        while let Some(l) = chars.next_if(|c| "hlzjtL".contains(*c)) {
+            length.push(l);
+        }
+        let Some(conversion) = chars.next() else {
+            break;
+        };
+        let int_size = match length.as_str() {
+            "ll" | "j" => 8,
+            "l" | "z" | "t" => pointer_size,
+            _ => INT_SIZE,
+        };
+        let int_align = if int_size == 8 { align_64 } else { int_size };
+
+        let text = match conversion {
+            '%' => "%".to_string(),
+            'd' | 'i' => {
+                let raw = args.next(int_size, int_align)?;
+                let shift = 64 - int_size * 8;
+                let v = ((raw << shift) as i64) >> shift;
+                format!(
+                    "{}{}",
+                    spec.sign(v < 0),
+                    spec.digits(v.unsigned_abs().to_string())
+                )
+            }
+            'u' | 'x' | 'X' | 'o' => {
+                let v = args.next(int_size, int_align)?;
+                let (digits, prefix) = match conversion {
+                    'u' => (v.to_string(), ""),
+                    'x' => (format!("{:x}", v), "0x"),
+                    'X' => (format!("{:X}", v), "0X"),
+                    _ => (format!("{:o}", v), "0"),
+                };
+                let prefix = if spec.alt && v != 0 { prefix } else { "" };
+                format!("{}{}", prefix, spec.digits(digits))
+            }
+            'c' => (args.next(INT_SIZE, INT_SIZE)? as u8 as char).to_string(),
+            'p' => format!("0x{:x}", args.next(pointer_size, pointer_size)?),
+            's' => {
+                let s = args.string()?;
+                match spec.precision {
+                    Some(p) => s.chars().take(p).collect(),
+                    None => s,
+                }
+            }
+            'f' | 'F' | 'e' | 'E' | 'g' | 'G' | 'a' | 'A' => {
+                let v = f64::from_bits(args.next(8, align_64)?);
+                let precision = spec.precision.unwrap_or(6);
+                let body = if !v.is_finite() {
+                    let s = if v.is_nan() { "nan" } else { "inf" };
+                    if conversion.is_ascii_uppercase() {
+                        s.to_uppercase()
+                    } else {
+                        s.to_string()
+                    }
+                } else {
+                    match conversion {
+                        'f' | 'F' => format!("{:.*}", precision, v.abs()),
+                        'e' | 'E' => c_exponent(v.abs(), precision, conversion == 'E'),
+                        // Hex floats are rare in logs; shown in decimal
+                        'a' | 'A' => v.abs().to_string(),
+                        _ => c_general(v.abs(), precision, spec.alt, conversion == 'G'),
+                    }
+                };
+                format!("{}{}", spec.sign(v.is_sign_negative() && !v.is_nan()), body)
+            }
+            'n' => continue,
+            other => format!("%{}{}", length, other),
+        };
+        out.push_str(&spec.pad(text));
+    }
+    Ok(out)
+}
+
+/// Turn a cbprintf package (header, format string pointer, arguments,
+/// string indexes, appended strings) into the message text.
+fn format_package(dictionary: &LogDictionary, package: &[u8]) -> Result<String, String> {
+    let [words, str_cnt, ro_str_cnt, rw_str_cnt] =
+        *package.first_chunk::<4>().ok_or("Truncated log package")?;
+    let header = INT_SIZE.max(dictionary.pointer_size);
+    let args_end = words as usize * INT_SIZE;
+
+    let fmt_pointer = dictionary
+        .uint(package, header, dictionary.pointer_size)
+        .ok_or("Truncated log package")?;
+    let fmt = dictionary
+        .strings
+        .get(&fmt_pointer)
+        .ok_or_else(|| format!("Format string 0x{:x} is not in the dictionary", fmt_pointer))?;
+
+    let mut strings = BTreeMap::new();
+    let mut offset = args_end + ro_str_cnt as usize + rw_str_cnt as usize;
+    for _ in 0..str_cnt {
+        let index = *package.get(offset).ok_or("Truncated log package")?;
+        let rest = &package[offset + 1..];
+        let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        strings.insert(
+            index as usize,
+            String::from_utf8_lossy(&rest[..len]).to_string(),
+        );
+        offset += 1 + len + 1;
+    }
+
+    let mut args = Args {
+        dictionary,
+        package: &package[..args_end.min(package.len())],
+        offset: header + dictionary.pointer_size,
+        strings,
+    };
+    format_message(fmt, &mut args)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamFormat {
+    /// `CONFIG_LOG_BACKEND_UART_OUTPUT_DICTIONARY_HEX`, the UART default
+    #[default]
+    Hex,
+    Binary,
+}
+
+/// Payload of the `log-record` event.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LogRecord {
+    pub source: String,
+    pub timestamp: u64, // Unix time in milliseconds
+    /// Raw log timestamp of the target, in its timestamp clock ticks
+    pub target_timestamp: u64,
+    pub level: LogLevel,
+    pub domain: u8,
+    pub module: Option<String>,
+    pub message: String,
+    /// Hexdump payload of `LOG_HEXDUMP_*`
+    pub data: Vec<u8>,
+    /// Set on "messages dropped" notices
+    pub dropped: Option<u32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Modules to keep; empty keeps all
+    pub modules: Vec<String>,
+    /// Most verbose level to keep
+    pub level: Option<LogLevel>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if record.dropped.is_some() || record.error.is_some() {
+            return true;
+        }
+        let module_ok = self.modules.is_empty()
+            || record
+                .module
+                .as_ref()
+                .is_some_and(|m| self.modules.contains(m));
+        module_ok && self.level.is_none_or(|level| record.level <= level)
+    }
+}
+
+/// Incremental decoder of a dictionary log stream.
+pub struct LogDecoder {
+    source: String,
+    dictionary: Arc<LogDictionary>,
+    format: StreamFormat,
+    synced: bool,
+    /// Tail of the input while looking for the separator
+    scan: Vec<u8>,
+    /// High nibble of a split hex byte
+    nibble: Option<u8>,
+    buf: Vec<u8>,
+}
+
+impl LogDecoder {
+    pub fn new(source: &str, dictionary: Arc<LogDictionary>, format: StreamFormat) -> Self {
+        Self {
+            source: source.to_string(),
+            dictionary,
+            format,
+            synced: false,
+            scan: Vec::new(),
+            nibble: None,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8], timestamp: u64) -> Vec<LogRecord> {
+        let mut records = Vec::new();
+        let mut input = data.to_vec();
+        loop {
+            if !self.synced {
+                self.scan.append(&mut input);
+                let Some(at) = self
+                    .scan
+                    .windows(LOG_SEPARATOR.len())
+                    .position(|w| w == LOG_SEPARATOR)
+                else {
+                    let keep = self.scan.len().saturating_sub(LOG_SEPARATOR.len() - 1);
+                    self.scan.drain(..keep);
+                    break;
+                };
+                self.synced = true;
+                self.nibble = None;
+                self.buf.clear();
+                input = self.scan.split_off(at + LOG_SEPARATOR.len());
+                self.scan.clear();
+            }
+
+            let rest = match self.format {
+                StreamFormat::Binary => {
+                    self.buf.append(&mut input);
+                    None
+                }
+                StreamFormat::Hex => self.push_hex(&input),
+            };
+            self.drain_messages(timestamp, &mut records);
+            match rest {
+                // Text in a hex stream: the target reset or printed outside
+                // of logging, so look for the next separator
+                Some(at) => {
+                    self.synced = false;
+                    input.drain(..at);
+                }
+                None => break,
+            }
+        }
+        records
+    }
+
+    /// Decode hex digits into `buf`; returns where non-hex input starts.
+    fn push_hex(&mut self, input: &[u8]) -> Option<usize> {
+        for (i, &c) in input.iter().enumerate() {
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            let Some(value) = (c as char).to_digit(16) else {
+                return Some(i);
+            };
+            match self.nibble.take() {
+                Some(high) => self.buf.push(high << 4 | value as u8),
+                None => self.nibble = Some(value as u8),
+            }
+        }
+        None
+    }
+
+    fn drain_messages(&mut self, timestamp: u64, records: &mut Vec<LogRecord>) {
+        while let Some(&kind) = self.buf.first() {
+            let parsed = match kind {
+                MSG_NORMAL => self.parse_normal(timestamp),
+                MSG_DROPPED => self.parse_dropped(timestamp),
+                other => Err(format!("Unknown log message type {}", other)),
+            };
+            match parsed {
+                Ok(Some((record, len))) => {
+                    self.buf.drain(..len);
+                    records.push(record);
+                }
+                Ok(None) => break,
+                Err(error) => {
+                    // Lengths can no longer be trusted; wait for the next
+                    // separator
+                    self.buf.clear();
+                    self.synced = false;
+                    records.push(self.record(
+                        timestamp,
+                        LogLevel::Error,
+                        error.clone(),
+                        Some(error),
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+
+    fn record(
+        &self,
+        timestamp: u64,
+        level: LogLevel,
+        message: String,
+        error: Option<String>,
+    ) -> LogRecord {
+        LogRecord {
+            source: self.source.clone(),
+            timestamp,
+            target_timestamp: 0,
+            level,
+            domain: 0,
+            module: None,
+            message,
+            data: Vec::new(),
+            dropped: None,
+            error,
+        }
+    }
+
+    fn parse_dropped(&self, timestamp: u64) -> Result<Option<(LogRecord, usize)>, String> {
+        let Some(count) = self.dictionary.uint(&self.buf, 1, 2) else {
+            return Ok(None);
+        };
+        let mut record = self.record(
+            timestamp,
+            LogLevel::Warning,
+            format!("--- {} messages dropped ---", count),
+            None,
+        );
+        record.dropped = Some(count as u32);
+        Ok(Some((record, 3)))
+    }
+
+    /// `type u8 | domain:4 level:4 | package_len u16 | data_len u16 |
+    /// source uintptr | timestamp | package | data`
+    fn parse_normal(&self, timestamp: u64) -> Result<Option<(LogRecord, usize)>, String> {
+        let dictionary = &self.dictionary;
+        let header = 6 + dictionary.pointer_size + dictionary.timestamp_size;
+        if self.buf.len() < header {
+            return Ok(None);
+        }
+        let bits = self.buf[1];
+        let (domain, level) = if dictionary.little_endian {
+            (bits & 0x0F, bits >> 4)
+        } else {
+            (bits >> 4, bits & 0x0F)
+        };
+        let uint = |offset, size| dictionary.uint(&self.buf, offset, size).unwrap();
+        let package_len = uint(2, 2) as usize;
+        let data_len = uint(4, 2) as usize;
+        let source_id = uint(6, dictionary.pointer_size);
+        let target_timestamp = uint(6 + dictionary.pointer_size, dictionary.timestamp_size);
+
+        let len = header + package_len + data_len;
+        if self.buf.len() < len {
+            return Ok(None);
+        }
+        let package = &self.buf[header..header + package_len];
+        let (message, error) = match format_package(dictionary, package) {
+            Ok(message) => (message, None),
+            Err(e) => (String::new(), Some(e)),
+        };
+
+        Ok(Some((
+            LogRecord {
+                source: self.source.clone(),
+                timestamp,
+                target_timestamp,
+                level: LogLevel::from_id(level),
+                domain,
+                module: dictionary.sources.get(&source_id).cloned(),
+                message,
+                data: self.buf[header + package_len..len].to_vec(),
+                dropped: None,
+                error,
+            },
+            len,
+        )))
+    }
+}
+
+struct AttachedLogDecoder {
+    app: AppHandle,
+    decoder: LogDecoder,
+    filter: LogFilter,
+}
+
+static DECODERS: Mutex<BTreeMap<String, AttachedLogDecoder>> = Mutex::new(BTreeMap::new());
+
+/// Decode received bytes if a log decoder is attached to the port.
+pub(crate) fn feed(port: &str, data: &[u8]) {
+    let mut decoders = DECODERS.lock().unwrap();
+    let Some(attached) = decoders.get_mut(port) else {
+        return;
+    };
+    for record in attached.decoder.push(data, serial_log::now_millis()) {
+        if attached.filter.matches(&record) {
+            let _ = attached.app.emit("log-record", record);
+        }
+    }
+}
+
+/// Path of the log dictionary for a project or build directory.
+#[tauri::command]
+pub fn find_log_dictionary(path: String) -> Result<String, String> {
+    find_dictionary(Path::new(&path)).map(|p| p.to_string_lossy().to_string())
+}
+
+/// Decode dictionary logging on a port into `log-record` events.
+/// `dictionary_path` may be the JSON file, a build directory or a project.
+#[tauri::command]
+pub fn attach_log_decoder(
+    app: AppHandle,
+    port: String,
+    dictionary_path: String,
+    format: Option<StreamFormat>,
+    filter: Option<LogFilter>,
+) -> Result<(), String> {
+    let dictionary = LogDictionary::load(&find_dictionary(Path::new(&dictionary_path))?)?;
+    let decoder = LogDecoder::new(&port, Arc::new(dictionary), format.unwrap_or_default());
+    DECODERS.lock().unwrap().insert(
+        port,
+        AttachedLogDecoder {
+            app,
+            decoder,
+            filter: filter.unwrap_or_default(),
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_log_filter(port: String, filter: LogFilter) -> Result<(), String> {
+    DECODERS
+        .lock()
+        .unwrap()
+        .get_mut(&port)
+        .map(|attached| attached.filter = filter)
+        .ok_or_else(|| format!("No log decoder on {}", port))
+}
+
+#[tauri::command]
+pub fn detach_log_decoder(port: String) -> Result<(), String> {
+    DECODERS
+        .lock()
+        .unwrap()
+        .remove(&port)
+        .map(|_| ())
+        .ok_or_else(|| format!("No log decoder on {}", port))
+}
+
+/// Decode a saved log: a serial capture or a plain hex/binary dump.
+#[tauri::command]
+pub fn decode_log_file(
+    path: String,
+    dictionary_path: String,
+    format: Option<StreamFormat>,
+    filter: Option<LogFilter>,
+) -> Result<Vec<LogRecord>, String> {
+    let dictionary = LogDictionary::load(&find_dictionary(Path::new(&dictionary_path))?)?;
+    let format = format.unwrap_or_default();
+    let filter = filter.unwrap_or_default();
+    let path = Path::new(&path);
+
+    let content =
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    // Text captures carry timestamps per line; binary dumps are fed as-is
+    let chunks = if format == StreamFormat::Hex || content.starts_with(serial_log::RAW_MAGIC) {
+        serial_log::read_capture(path)?
+            .into_iter()
+            .map(|record| (record.data, record.timestamp))
+            .collect()
+    } else {
+        vec![(content, 0)]
+    };
+
+    let mut decoder = LogDecoder::new(&path.to_string_lossy(), Arc::new(dictionary), format);
+    Ok(chunks
+        .iter()
+        .flat_map(|(data, timestamp)| decoder.push(data, *timestamp))
+        .filter(|record| filter.matches(record))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FMT_BOOT: u64 = 0x1000;
+    const FMT_SENSOR: u64 = 0x1040;
+    const FMT_NAME: u64 = 0x1080;
+    const STR_IMU: u64 = 0x10C0;
+
+    fn dictionary() -> Arc<LogDictionary> {
+        Arc::new(
+            LogDictionary::parse(&format!(
+                r#"{{
+                    "version": 3,
+                    "target": {{ "bits": 32, "little_endianness": true }},
+                    "arch": "arm",
+                    "kconfigs": {{ "CONFIG_LOG_DICTIONARY_SUPPORT": "y" }},
+                    "log_subsys": {{ "log_instances": {{
+                        "0": {{ "source_id": 0, "name": "main" }},
+                        "1": {{ "source_id": 1, "name": "imu" }}
+                    }} }},
+                    "string_mappings": {{
+                        "{}": "Booting v%d.%d",
+                        "{}": "%s: accel=%-6.2f temp=%04x %lld",
+                        "{}": "hello %s!",
+                        "{}": "bmi088"
+                    }}
+                }}"#,
+                FMT_BOOT, FMT_SENSOR, FMT_NAME, STR_IMU
+            ))
+            .unwrap(),
+        )
+    }
+
+    /// A normal message as `log_dict_output_msg_process` writes it on a
+    /// little-endian 32-bit target.
+    fn message(
+        level: u8,
+        source: u32,
+        ts: u32,
+        fmt: u64,
+        args: &[u8],
+        strings: &[(u8, &str)],
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut package = vec![0, strings.len() as u8, 0, 0];
+        package.extend_from_slice(&(fmt as u32).to_le_bytes());
+        package.extend_from_slice(args);
+        package[0] = (package.len() / INT_SIZE) as u8;
+        for (index, s) in strings {
+            package.push(*index);
+            package.extend_from_slice(s.as_bytes());
+            package.push(0);
+        }
+
+        let mut msg = vec![MSG_NORMAL, level << 4];
+        msg.extend_from_slice(&(package.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        msg.extend_from_slice(&source.to_le_bytes());
+        msg.extend_from_slice(&ts.to_le_bytes());
+        msg.extend_from_slice(&package);
+        msg.extend_from_slice(data);
+        msg
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn decodes_binary_messages_with_arguments() {
+        let mut args = Vec::new();
+        args.extend_from_slice(&(STR_IMU as u32).to_le_bytes());
+        args.extend_from_slice(&[0; 4]); // double is 8-aligned
+        args.extend_from_slice(&9.81f64.to_le_bytes());
+        args.extend_from_slice(&0x2Au32.to_le_bytes());
+        args.extend_from_slice(&[0; 4]);
+        args.extend_from_slice(&(-5i64).to_le_bytes());
+
+        let mut stream = b"*** Booting Zephyr OS ***\r\n".to_vec();
+        stream.extend_from_slice(LOG_SEPARATOR);
+        stream.extend(message(3, 1, 1234, FMT_SENSOR, &args, &[], &[]));
+        stream.extend([MSG_DROPPED, 7, 0]);
+
+        let mut decoder = LogDecoder::new("COM3", dictionary(), StreamFormat::Binary);
+        // Byte by byte to cover split messages
+        let records: Vec<LogRecord> = stream.iter().flat_map(|b| decoder.push(&[*b], 5)).collect();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].message, "bmi088: accel=9.81   temp=002a -5");
+        assert_eq!(records[0].level, LogLevel::Info);
+        assert_eq!(records[0].module.as_deref(), Some("imu"));
+        assert_eq!(records[0].target_timestamp, 1234);
+        assert_eq!(records[0].error, None);
+        assert_eq!(records[1].dropped, Some(7));
+    }
+
+    #[test]
+    fn decodes_hex_output_and_resyncs_after_reset() {
+        let boot = message(1, 0, 10, FMT_BOOT, &[3, 0, 0, 0, 7, 0, 0, 0], &[], &[]);
+        let greeting = message(4, 0, 20, FMT_NAME, &[0; 4], &[(2, "robot")], &[0xDE, 0xAD]);
+
+        let text = format!(
+            "##ZLOGV1##{}\r\n{}\r\n*** Booting Zephyr OS ***\r\n##ZLOGV1##{}\r\n",
+            hex(&boot),
+            &hex(&greeting)[..10], // cut short by a reset
+            hex(&greeting),
+        );
+        let mut decoder = LogDecoder::new("COM3", dictionary(), StreamFormat::Hex);
+        let records = decoder.push(text.as_bytes(), 0);
+
+        let messages: Vec<&str> = records.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, ["Booting v3.7", "hello robot!"]);
+        assert_eq!(records[0].level, LogLevel::Error);
+        assert_eq!(records[1].level, LogLevel::Debug);
+        assert_eq!(records[1].data, [0xDE, 0xAD]);
+    }
+
+    #[test]
+    fn reports_unknown_format_strings() {
+        let mut stream = LOG_SEPARATOR.to_vec();
+        stream.extend(message(3, 0, 0, 0xBAD, &[], &[], &[]));
+        let mut decoder = LogDecoder::new("COM3", dictionary(), StreamFormat::Binary);
+        let records = decoder.push(&stream, 0);
+        assert!(records[0].error.as_ref().unwrap().contains("0xbad"));
+    }
+
+    #[test]
+    fn filters_by_module_and_level() {
+        let record = |module: &str, level| LogRecord {
+            source: "COM3".to_string(),
+            timestamp: 0,
+            target_timestamp: 0,
+            level,
+            domain: 0,
+            module: Some(module.to_string()),
+            message: String::new(),
+            data: Vec::new(),
+            dropped: None,
+            error: None,
+        };
+        let filter = LogFilter {
+            modules: vec!["imu".to_string()],
+            level: Some(LogLevel::Warning),
+        };
+        assert!(filter.matches(&record("imu", LogLevel::Error)));
+        assert!(!filter.matches(&record("imu", LogLevel::Info)));
+        assert!(!filter.matches(&record("main", LogLevel::Error)));
+        assert!(LogFilter::default().matches(&record("main", LogLevel::Debug)));
+    }
+
+    fn render(fmt: &str, values: &[i64]) -> String {
+        try_render(fmt, values).unwrap()
+    }
+
+    fn try_render(fmt: &str, values: &[i64]) -> Result<String, String> {
+        let dictionary = LogDictionary::parse(r#"{"version": 3, "string_mappings": {}}"#).unwrap();
+        let package: Vec<u8> = values
+            .iter()
+            .flat_map(|v| (*v as u32).to_le_bytes())
+            .collect();
+        let mut args = Args {
+            dictionary: &dictionary,
+            package: &package,
+            offset: 0,
+            strings: BTreeMap::new(),
+        };
+        format_message(fmt, &mut args)
+    }
+
+    #[test]
+    fn formats_like_printf() {
+        assert_eq!(
+            render("[%5d|%-4u|%05d]", &[42, 7, -42]),
+            "[   42|7   |-0042]"
+        );
+        assert_eq!(
+            render("%#x %08X %o %c %%", &[255, 0xBEEF, 8, 'z' as i64]),
+            "0xff 0000BEEF 10 z %"
+        );
+        assert_eq!(render("%*d|%p", &[-4, 1, 0x2000_0000]), "1   |0x20000000");
+        assert_eq!(c_exponent(1500.0, 2, false), "1.50e+03");
+        assert_eq!(c_general(0.0001234, 6, false, false), "0.0001234");
+        assert_eq!(c_general(1234567.0, 6, false, false), "1.23457e+06");
+    }
+
+    #[test]
+    fn rejects_oversized_width_and_precision() {
+        assert!(try_render("%*d", &[i32::MAX as i64, 1]).is_err());
+        assert!(try_render("%*d", &[i32::MIN as i64, 1]).is_err());
+        assert!(try_render("%.*d", &[100_000, 1]).is_err());
+        assert!(try_render("%99999999999999999999d", &[1]).is_err());
+        assert_eq!(render("%.*d|%3.*d", &[-1, 7, 2, 5]), "7| 05");
+    }
+}
@@ -0,0 +1,406 @@
+use crate::frame_codec::DecodedFrame;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+/// Samples kept per channel; a little over three minutes at 1 kHz.
+const CHANNEL_CAPACITY: usize = 200_000;
+/// Samples kept across all channels (16 bytes each, so about 64 MB); each
+/// channel gets an equal share once there are more than 20.
+const TOTAL_CAPACITY: usize = 4_000_000;
+/// Channels beyond this are not buffered until others are cleared.
+const MAX_CHANNELS: usize = 256;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PlotPoint {
+    pub t: u64, // Unix time in milliseconds
+    pub v: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChannelInfo {
+    pub name: String,
+    pub samples: usize,
+    pub first: Option<u64>,
+    pub last: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PlotSeries {
+    pub channel: String,
+    pub points: Vec<PlotPoint>,
+    /// Samples in the window before downsampling
+    pub total: usize,
+}
+
+/// Fixed-capacity ring buffer of one channel's samples, oldest first.
+pub struct Channel {
+    samples: VecDeque<PlotPoint>,
+    capacity: usize,
+}
+
+impl Channel {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Append a sample. Timestamps never go backwards within a channel (the
+    /// clock may be stepped), so `range` can rely on them being sorted.
+    pub fn push(&mut self, mut point: PlotPoint) {
+        if let Some(last) = self.samples.back() {
+            point.t = point.t.max(last.t);
+        }
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(point);
+    }
+
+    /// Change the capacity, dropping the oldest samples that no longer fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        let excess = self.samples.len().saturating_sub(self.capacity);
+        self.samples.drain(..excess);
+    }
+
+    /// Samples with `from <= t <= to`; either bound may be open.
+    pub fn range(&self, from: Option<u64>, to: Option<u64>) -> impl Iterator<Item = &PlotPoint> {
+        let start = from.map_or(0, |from| self.samples.partition_point(|p| p.t < from));
+        let end = to.map_or(self.samples.len(), |to| {
+            self.samples.partition_point(|p| p.t <= to)
+        });
+        self.samples.range(start..end.max(start))
+    }
+
+    fn info(&self, name: &str) -> ChannelInfo {
+        ChannelInfo {
+            name: name.to_string(),
+            samples: self.samples.len(),
+            first: self.samples.front().map(|p| p.t),
+            last: self.samples.back().map(|p| p.t),
+        }
+    }
+}
+
+/// Reduce `points` (sorted by time) to at most two per pixel column: the
+/// minimum and maximum of each, in time order, so spikes stay visible.
+pub fn downsample(points: &[PlotPoint], width: usize) -> Vec<PlotPoint> {
+    let width = width.max(1);
+    if points.len() <= width * 2 {
+        return points.to_vec();
+    }
+    let from = points[0].t;
+    let span = points[points.len() - 1].t.saturating_sub(from).max(1) as f64;
+
+    let mut out = Vec::with_capacity(width * 2);
+    let mut bucket: Option<(usize, PlotPoint, PlotPoint)> = None;
+    let flush = |out: &mut Vec<PlotPoint>, (_, min, max): (usize, PlotPoint, PlotPoint)| {
+        if min == max {
+            out.push(min);
+        } else if min.t <= max.t {
+            out.extend([min, max]);
+        } else {
+            out.extend([max, min]);
+        }
+    };
+
+    for &point in points {
+        let index = ((point.t.saturating_sub(from) as f64 / span) * width as f64) as usize;
+        let index = index.min(width - 1);
+        match bucket.as_mut() {
+            Some((current, min, max)) if *current == index => {
+                if point.v < min.v {
+                    *min = point;
+                }
+                if point.v > max.v {
+                    *max = point;
+                }
+            }
+            _ => {
+                if let Some(done) = bucket.take() {
+                    flush(&mut out, done);
+                }
+                bucket = Some((index, point, point));
+            }
+        }
+    }
+    if let Some(done) = bucket {
+        flush(&mut out, done);
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per distinct timestamp, one column per channel; cells stay empty
+/// where a channel has no sample at that time.
+pub fn to_csv(series: &[(String, Vec<PlotPoint>)]) -> String {
+    let mut rows: BTreeMap<u64, Vec<Option<f64>>> = BTreeMap::new();
+    for (column, (_, points)) in series.iter().enumerate() {
+        for point in points {
+            rows.entry(point.t)
+                .or_insert_with(|| vec![None; series.len()])[column] = Some(point.v);
+        }
+    }
+
+    let mut csv = std::iter::once("timestamp_ms".to_string())
+        .chain(series.iter().map(|(name, _)| csv_field(name)))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for (t, values) in rows {
+        csv.push_str(&t.to_string());
+        for value in values {
+            csv.push(',');
+            if let Some(v) = value {
+                csv.push_str(&v.to_string());
+            }
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+static CHANNELS: Mutex<BTreeMap<String, Channel>> = Mutex::new(BTreeMap::new());
+
+/// Per-channel capacity that keeps `count` channels within the total budget.
+fn channel_capacity(count: usize) -> usize {
+    (TOTAL_CAPACITY / count.max(1)).min(CHANNEL_CAPACITY)
+}
+
+fn resize_channels(channels: &mut BTreeMap<String, Channel>) {
+    let capacity = channel_capacity(channels.len());
+    channels
+        .values_mut()
+        .for_each(|channel| channel.set_capacity(capacity));
+}
+
+fn record_into(
+    channels: &mut BTreeMap<String, Channel>,
+    points: impl IntoIterator<Item = (String, PlotPoint)>,
+) {
+    for (name, point) in points {
+        if !channels.contains_key(&name) {
+            if channels.len() >= MAX_CHANNELS {
+                continue;
+            }
+            channels.insert(name.clone(), Channel::new(CHANNEL_CAPACITY));
+            resize_channels(channels);
+        }
+        if let Some(channel) = channels.get_mut(&name) {
+            channel.push(point);
+        }
+    }
+}
+
+/// Append samples, creating channels on first use while under
+/// `MAX_CHANNELS`.
+pub(crate) fn record(points: impl IntoIterator<Item = (String, PlotPoint)>) {
+    record_into(&mut CHANNELS.lock().unwrap(), points);
+}
+
+/// Buffer every numeric (and boolean, as 0/1) field of a decoded frame as
+/// `<port>/<frame>.<field>`.
+pub(crate) fn record_frame(frame: &DecodedFrame) {
+    let Some(name) = &frame.name else {
+        return;
+    };
+    record(frame.fields.iter().filter_map(|(field, value)| {
+        let v = value
+            .as_f64()
+            .or_else(|| value.as_bool().map(|b| b as u8 as f64))?;
+        Some((
+            format!("{}/{}.{}", frame.port, name, field),
+            PlotPoint {
+                t: frame.timestamp,
+                v,
+            },
+        ))
+    }));
+}
+
+fn select(
+    channels: &[String],
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<Vec<(String, Vec<PlotPoint>)>, String> {
+    let buffers = CHANNELS.lock().unwrap();
+    channels
+        .iter()
+        .map(|name| {
+            let channel = buffers
+                .get(name)
+                .ok_or_else(|| format!("Unknown channel {}", name))?;
+            Ok((
+                name.clone(),
+                channel.range(from_ms, to_ms).copied().collect(),
+            ))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_plot_channels() -> Vec<ChannelInfo> {
+    CHANNELS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, channel)| channel.info(name))
+        .collect()
+}
+
+/// Samples of `channels` between `from_ms` and `to_ms`, downsampled to
+/// `width` pixel columns.
+#[tauri::command]
+pub fn query_plot_data(
+    channels: Vec<String>,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+    width: usize,
+) -> Result<Vec<PlotSeries>, String> {
+    Ok(select(&channels, from_ms, to_ms)?
+        .into_iter()
+        .map(|(channel, points)| PlotSeries {
+            channel,
+            total: points.len(),
+            points: downsample(&points, width),
+        })
+        .collect())
+}
+
+/// Write the full-resolution samples of `channels` to a CSV file.
+#[tauri::command]
+pub fn export_plot_csv(
+    channels: Vec<String>,
+    path: String,
+    from_ms: Option<u64>,
+    to_ms: Option<u64>,
+) -> Result<(), String> {
+    let series = select(&channels, from_ms, to_ms)?;
+    std::fs::write(&path, to_csv(&series)).map_err(|e| format!("Failed to write {}: {}", path, e))
+}
+
+/// Drop the given channels, or all of them.
+#[tauri::command]
+pub fn clear_plot_data(channels: Option<Vec<String>>) {
+    let mut buffers = CHANNELS.lock().unwrap();
+    match channels {
+        Some(names) => names.iter().for_each(|name| {
+            buffers.remove(name);
+        }),
+        None => buffers.clear(),
+    }
+    resize_channels(&mut buffers);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(t: u64, v: f64) -> PlotPoint {
+        PlotPoint { t, v }
+    }
+
+    #[test]
+    fn ring_buffer_drops_oldest() {
+        let mut channel = Channel::new(3);
+        for t in 0..5 {
+            channel.push(p(t, t as f64));
+        }
+        let ts: Vec<u64> = channel.range(None, None).map(|p| p.t).collect();
+        assert_eq!(ts, [2, 3, 4]);
+        let ts: Vec<u64> = channel.range(Some(3), Some(3)).map(|p| p.t).collect();
+        assert_eq!(ts, [3]);
+        assert_eq!(channel.range(Some(9), Some(1)).count(), 0);
+    }
+
+    #[test]
+    fn caps_channel_count_and_total_samples() {
+        let mut channels = BTreeMap::new();
+        record_into(
+            &mut channels,
+            (0..CHANNEL_CAPACITY as u64 + 10).map(|t| ("COM3/imu.ax".to_string(), p(t, 0.0))),
+        );
+        assert_eq!(channels["COM3/imu.ax"].samples.len(), CHANNEL_CAPACITY);
+
+        record_into(
+            &mut channels,
+            (0..MAX_CHANNELS + 10).map(|i| (format!("COM3/frame.f{}", i), p(1, 0.0))),
+        );
+        assert_eq!(channels.len(), MAX_CHANNELS);
+        assert!(!channels.contains_key(&format!("COM3/frame.f{}", MAX_CHANNELS)));
+
+        let total: usize = channels.values().map(|c| c.samples.len()).sum();
+        assert!(total <= TOTAL_CAPACITY);
+        assert_eq!(
+            channels["COM3/imu.ax"].samples.len(),
+            channel_capacity(MAX_CHANNELS)
+        );
+        assert_eq!(
+            channels["COM3/imu.ax"].samples.front().map(|p| p.t),
+            Some((CHANNEL_CAPACITY + 10 - channel_capacity(MAX_CHANNELS)) as u64)
+        );
+    }
+
+    #[test]
+    fn downsampling_keeps_spikes() {
+        // 1 kHz for 10 s with a single-sample spike each way
+        let mut points: Vec<PlotPoint> = (0..10_000).map(|t| p(t, 0.0)).collect();
+        points[4321].v = 100.0;
+        points[8765].v = -50.0;
+
+        let reduced = downsample(&points, 200);
+        assert!(reduced.len() <= 400);
+        assert!(reduced.contains(&p(4321, 100.0)));
+        assert!(reduced.contains(&p(8765, -50.0)));
+        assert!(reduced.windows(2).all(|w| w[0].t <= w[1].t));
+    }
+
+    #[test]
+    fn clock_steps_back_do_not_break_ordering() {
+        let mut channel = Channel::new(10);
+        for t in [100, 200, 150, 300] {
+            channel.push(p(t, t as f64));
+        }
+        let ts: Vec<u64> = channel.range(None, None).map(|p| p.t).collect();
+        assert_eq!(ts, [100, 200, 200, 300]);
+        let vs: Vec<f64> = channel.range(Some(200), Some(200)).map(|p| p.v).collect();
+        assert_eq!(vs, [200.0, 150.0]);
+
+        // Unsorted input is bucketed without underflowing
+        let points: Vec<PlotPoint> = (0..100).map(|t| p(1000 - t * 10, 0.0)).collect();
+        assert!(downsample(&points, 10).len() <= 20);
+    }
+
+    #[test]
+    fn short_windows_are_returned_as_is() {
+        let points = vec![p(0, 1.0), p(1, 2.0), p(2, 3.0)];
+        assert_eq!(downsample(&points, 100), points);
+    }
+
+    #[test]
+    fn exports_aligned_csv() {
+        let csv = to_csv(&[
+            (
+                "COM3/chassis.voltage".to_string(),
+                vec![p(10, 24.5), p(20, 24.4)],
+            ),
+            ("COM3/gimbal.yaw, deg".to_string(), vec![p(20, 1.5)]),
+        ]);
+        assert_eq!(
+            csv,
+            "timestamp_ms,COM3/chassis.voltage,\"COM3/gimbal.yaw, deg\"\n\
+             10,24.5,\n\
+             20,24.4,1.5\n"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use crate::serial_comm::{self, Connect, EventSink, Reader, SerialSink, Writer, READ_TIMEOUT};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Longest banner J-Link sends before the RTT data starts.
+const MAX_BANNER_BYTES: usize = 512;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RttServer {
+    /// `rtt server start <port> <channel>` in a running OpenOCD
+    #[default]
+    Openocd,
+    /// The RTT telnet server of J-Link GDB Server / J-Link Commander
+    Jlink,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RttConfig {
+    pub server: RttServer,
+    pub host: String,
+    /// OpenOCD: the port passed to `rtt server start`; J-Link: 19021 unless
+    /// changed with `-RTTTelnetPort`
+    pub port: u16,
+    /// Up/down buffer index. OpenOCD serves one channel per TCP port, so
+    /// here it only names the source; J-Link is asked to switch to it.
+    pub channel: u32,
+    pub auto_reconnect: bool,
+    pub reconnect_interval_ms: u64,
+}
+
+impl Default for RttConfig {
+    fn default() -> Self {
+        Self {
+            server: RttServer::Openocd,
+            host: "localhost".to_string(),
+            port: 9090,
+            channel: 0,
+            auto_reconnect: true,
+            reconnect_interval_ms: 1000,
+        }
+    }
+}
+
+impl RttConfig {
+    /// Name the source is registered under, alongside serial ports.
+    pub fn source_name(&self) -> String {
+        format!("rtt:{}:{}/{}", self.host, self.port, self.channel)
+    }
+}
+
+/// Drops the `SEGGER J-Link ... / Process: ...` lines J-Link prints on
+/// connect, so only target output reaches the viewer.
+struct JlinkBannerFilter {
+    inner: TcpStream,
+    /// Bytes held back while deciding whether they are the banner
+    held: Vec<u8>,
+    done: bool,
+    pending: Vec<u8>,
+}
+
+impl JlinkBannerFilter {
+    fn new(inner: TcpStream) -> Self {
+        Self {
+            inner,
+            held: Vec::new(),
+            done: false,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Strip the banner from `held` once it is complete, or give up when it
+    /// does not look like one.
+    fn resolve(&mut self) {
+        const PREFIX: &[u8] = b"SEGGER J-Link";
+        let n = self.held.len().min(PREFIX.len());
+        if self.held[..n] != PREFIX[..n] {
+            self.done = true;
+            self.pending = std::mem::take(&mut self.held);
+            return;
+        }
+        let mut start = 0;
+        while let Some(end) = self.held[start..].iter().position(|&b| b == b'\n') {
+            let line = &self.held[start..start + end];
+            start += end + 1;
+            if line.starts_with(b"Process:") {
+                self.done = true;
+                self.pending = self.held.split_off(start);
+                self.held.clear();
+                return;
+            }
+        }
+        if self.held.len() >= MAX_BANNER_BYTES {
+            self.done = true;
+            self.pending = std::mem::take(&mut self.held);
+        }
+    }
+}
+
+impl Read for JlinkBannerFilter {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while !self.done {
+            let mut chunk = [0u8; 256];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.held.extend_from_slice(&chunk[..n]);
+            self.resolve();
+        }
+        if !self.pending.is_empty() {
+            let n = self.pending.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            return Ok(n);
+        }
+        self.inner.read(buf)
+    }
+}
+
+fn connect(config: &RttConfig) -> Result<(Reader, Writer), String> {
+    let address = (config.host.as_str(), config.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", config.host, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", config.host))?;
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|e| {
+        format!(
+            "Failed to connect to RTT server at {}:{}: {}",
+            config.host, config.port, e
+        )
+    })?;
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let _ = stream.set_nodelay(true);
+
+    if config.server == RttServer::Jlink && config.channel != 0 {
+        stream
+            .write_all(format!("$$SEGGER_TELNET_ConfigStr=RTTCh;{}$$", config.channel).as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+
+    let writer = stream.try_clone().map_err(|e| e.to_string())?;
+    let reader: Reader = match config.server {
+        RttServer::Openocd => Box::new(stream),
+        RttServer::Jlink => Box::new(JlinkBannerFilter::new(stream)),
+    };
+    Ok((reader, Box::new(writer)))
+}
+
+pub(crate) fn open_rtt_source(
+    config: RttConfig,
+    sink: Arc<dyn SerialSink>,
+) -> Result<String, String> {
+    let name = config.source_name();
+    let reconnect = config
+        .auto_reconnect
+        .then(|| Duration::from_millis(config.reconnect_interval_ms));
+    let connect: Connect = Box::new(move || connect(&config));
+    serial_comm::open_source(&name, connect, reconnect, None, sink)?;
+    Ok(name)
+}
+
+/// Connect to an RTT server and stream it like a serial port: same
+/// `serial-data`/`serial-status` events, captures, decoders and writes
+/// (to the down channel). Returns the source name to use with those
+/// commands; close it with `close_serial_port`.
+#[tauri::command]
+pub fn open_rtt(app: AppHandle, config: Option<RttConfig>) -> Result<String, String> {
+    open_rtt_source(config.unwrap_or_default(), Arc::new(EventSink(app)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_comm::testing::{receive, sink, wait_for};
+    use crate::serial_comm::PortState;
+    use std::net::TcpListener;
+
+    fn stub() -> (TcpListener, RttConfig) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = RttConfig {
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+            reconnect_interval_ms: 50,
+            ..Default::default()
+        };
+        (listener, config)
+    }
+
+    #[test]
+    fn reads_up_and_writes_down_channel() {
+        let (listener, config) = stub();
+        let (sink, rx) = sink();
+        let name = open_rtt_source(config, sink).unwrap();
+        let (mut target, _) = listener.accept().unwrap();
+
+        target
+            .write_all(b"[00:00:01.000] <inf> main: boot\n")
+            .unwrap();
+        assert_eq!(
+            receive(&rx, &name, b"[00:00:01.000] <inf> main: boot\n"),
+            b"[00:00:01.000] <inf> main: boot\n"
+        );
+
+        serial_comm::write_port(&name, b"kernel threads\r").unwrap();
+        let mut buf = [0u8; 15];
+        target.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"kernel threads\r");
+
+        serial_comm::close_port(&name).unwrap();
+    }
+
+    #[test]
+    fn selects_jlink_channel_and_drops_banner() {
+        let (listener, mut config) = stub();
+        config.server = RttServer::Jlink;
+        config.channel = 1;
+        let (sink, rx) = sink();
+        let name = open_rtt_source(config, sink).unwrap();
+        assert!(name.ends_with("/1"));
+
+        let (mut target, _) = listener.accept().unwrap();
+        let expected = b"$$SEGGER_TELNET_ConfigStr=RTTCh;1$$";
+        let mut buf = [0u8; 35];
+        target.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], expected);
+
+        target
+            .write_all(
+                b"SEGGER J-Link V7.94e - Real time terminal output\r\n\
+                  J-Link OB-STM32F072-CortexM compiled Jan  2 2024, SN=779999999\r\n\
+                  Process: JLinkGDBServerCLExe\r\n",
+            )
+            .unwrap();
+        target.write_all(b"hello from channel 1\n").unwrap();
+        assert_eq!(
+            receive(&rx, &name, b"hello from channel 1\n"),
+            b"hello from channel 1\n"
+        );
+
+        serial_comm::close_port(&name).unwrap();
+    }
+
+    #[test]
+    fn reconnects_when_server_restarts() {
+        let (listener, config) = stub();
+        let (sink, rx) = sink();
+        let name = open_rtt_source(config, sink).unwrap();
+
+        let (first, _) = listener.accept().unwrap();
+        drop(first);
+        assert!(wait_for(&rx, &name, PortState::Disconnected));
+
+        let (mut second, _) = listener.accept().unwrap();
+        assert!(wait_for(&rx, &name, PortState::Reconnected));
+        second.write_all(b"back").unwrap();
+        assert_eq!(receive(&rx, &name, b"back"), b"back");
+
+        serial_comm::close_port(&name).unwrap();
+    }
+}
@@ -0,0 +1,758 @@
+use crate::cmd_zephyr;
+use crate::config_manager::{self, UserConfig};
+use crate::executor::{CommandExecutor, SystemExecutor};
+use crate::toolchain_manager::{self, exe_name};
+use crate::version;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Where an SDK candidate was found. Ordered from most to least trusted.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SdkSource {
+    EnvVar,
+    CmakeRegistry,
+    Configured,
+    Home,
+    System,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SdkToolchain {
+    pub name: String,
+    pub gcc_path: String,
+    pub working: bool,
+    pub gcc_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SdkCandidate {
+    pub path: String,
+    pub version: Option<String>,
+    pub sources: Vec<SdkSource>,
+    pub toolchains: Vec<SdkToolchain>,
+    pub valid: bool,
+    pub problems: Vec<String>,
+}
+
+impl SdkCandidate {
+    fn best_source(&self) -> SdkSource {
+        self.sources
+            .iter()
+            .min()
+            .copied()
+            .unwrap_or(SdkSource::System)
+    }
+}
+
+/// Directories registered for `package` in the CMake user package registry:
+/// files under `~/.cmake/packages/<package>`, or registry values under
+/// `HKCU\Software\Kitware\CMake\Packages\<package>` on Windows.
+pub fn cmake_package_registry(package: &str) -> Vec<PathBuf> {
+    #[cfg(windows)]
+    {
+        let key = format!("HKCU\\Software\\Kitware\\CMake\\Packages\\{}", package);
+        let Ok(output) = std::process::Command::new("reg")
+            .args(["query", &key])
+            .output()
+        else {
+            return Vec::new();
+        };
+        // `    <hash>    REG_SZ    C:\zephyr\share\zephyr-package\cmake`
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once("REG_SZ"))
+            .map(|(_, value)| PathBuf::from(value.trim()))
+            .collect()
+    }
+    #[cfg(not(windows))]
+    {
+        let Some(home) = dirs::home_dir() else {
+            return Vec::new();
+        };
+        let registry = home.join(".cmake").join("packages").join(package);
+        let Ok(entries) = std::fs::read_dir(registry) else {
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+            .map(|content| PathBuf::from(content.trim()))
+            .collect()
+    }
+}
+
+/// Paths registered by `setup.sh -c` in the CMake user package registry.
+/// Each entry is `<sdk>/cmake`.
+fn cmake_registry_sdks() -> Vec<PathBuf> {
+    cmake_package_registry("Zephyr-sdk")
+        .into_iter()
+        .filter_map(|cmake_dir| cmake_dir.parent().map(|p| p.to_path_buf()))
+        .collect()
+}
+
+/// `zephyr-sdk-*` directories directly inside `root`.
+fn sdk_dirs_in(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("zephyr-sdk-"))
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+/// Every location we look for SDKs in, tagged with where it came from.
+fn candidate_paths(config: &UserConfig) -> Vec<(PathBuf, SdkSource)> {
+    let mut paths = Vec::new();
+
+    if let Ok(dir) = std::env::var("ZEPHYR_SDK_INSTALL_DIR") {
+        paths.push((PathBuf::from(dir), SdkSource::EnvVar));
+    }
+
+    for dir in cmake_registry_sdks() {
+        paths.push((dir, SdkSource::CmakeRegistry));
+    }
+
+    for root in &config.sdk_search_roots {
+        let root = Path::new(root);
+        if root.join("sdk_version").exists() {
+            paths.push((root.to_path_buf(), SdkSource::Configured));
+        }
+        for dir in sdk_dirs_in(root) {
+            paths.push((dir, SdkSource::Configured));
+        }
+    }
+    for profile in &config.toolchains {
+        if let Some(sdk) = &profile.sdk_path {
+            paths.push((PathBuf::from(sdk), SdkSource::Configured));
+        }
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        for dir in sdk_dirs_in(&home) {
+            paths.push((dir, SdkSource::Home));
+        }
+        for dir in sdk_dirs_in(&home.join(".local")) {
+            paths.push((dir, SdkSource::Home));
+        }
+    }
+
+    for root in ["/opt", "/usr/local", "/usr"] {
+        for dir in sdk_dirs_in(Path::new(root)) {
+            paths.push((dir, SdkSource::System));
+        }
+    }
+
+    paths
+}
+
+/// Toolchain directories (`arm-zephyr-eabi`, `riscv64-zephyr-elf`, ...).
+/// SDK 0.17+ moved them under `gnu/`, older releases keep them at the root.
+fn find_toolchains(exec: &dyn CommandExecutor, sdk: &Path) -> Vec<SdkToolchain> {
+    let mut toolchains = Vec::new();
+
+    for dir in [sdk.to_path_buf(), sdk.join("gnu")] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.contains("-zephyr-") || !entry.path().is_dir() {
+                continue;
+            }
+            let gcc = entry
+                .path()
+                .join("bin")
+                .join(exe_name(&format!("{}-gcc", name)));
+            if !gcc.exists() {
+                continue;
+            }
+
+            let gcc_version = exec
+                .run(&gcc.to_string_lossy(), &["--version"])
+                .filter(|o| o.success)
+                .and_then(|o| o.stdout.lines().next().map(|l| l.trim().to_string()));
+
+            toolchains.push(SdkToolchain {
+                name,
+                gcc_path: gcc.to_string_lossy().to_string(),
+                working: gcc_version.is_some(),
+                gcc_version,
+            });
+        }
+    }
+
+    toolchains.sort_by(|a, b| a.name.cmp(&b.name));
+    toolchains
+}
+
+/// Inspect one directory and describe what kind of SDK it is.
+pub fn inspect_sdk(path: &Path, sources: Vec<SdkSource>) -> SdkCandidate {
+    let mut problems = Vec::new();
+
+    let version = toolchain_manager::read_sdk_version(path);
+    if version.is_none() {
+        problems.push("sdk_version file is missing".to_string());
+    }
+
+    let toolchains = find_toolchains(&SystemExecutor, path);
+    if toolchains.is_empty() {
+        problems.push("no toolchains installed".to_string());
+    }
+    for t in toolchains.iter().filter(|t| !t.working) {
+        problems.push(format!("{} gcc does not run", t.name));
+    }
+
+    if !path.join("cmake").join("Zephyr-sdkConfig.cmake").exists() {
+        problems.push("cmake/Zephyr-sdkConfig.cmake is missing".to_string());
+    }
+
+    let valid = version.is_some() && toolchains.iter().any(|t| t.working);
+
+    SdkCandidate {
+        path: path.to_string_lossy().to_string(),
+        version,
+        sources,
+        toolchains,
+        valid,
+        problems,
+    }
+}
+
+/// Valid SDKs first, then by how trustworthy the source is, then newest first.
+fn rank(candidates: &mut [SdkCandidate]) {
+    candidates.sort_by(|a, b| {
+        b.valid
+            .cmp(&a.valid)
+            .then_with(|| a.best_source().cmp(&b.best_source()))
+            .then_with(|| {
+                let va = a.version.as_deref().unwrap_or("0");
+                let vb = b.version.as_deref().unwrap_or("0");
+                version::compare(vb, va)
+            })
+    });
+}
+
+/// Merge duplicates found through several sources, keyed by canonical path.
+fn merge_candidates(paths: Vec<(PathBuf, SdkSource)>) -> Vec<(PathBuf, Vec<SdkSource>)> {
+    let mut merged: Vec<(PathBuf, Vec<SdkSource>)> = Vec::new();
+    for (path, source) in paths {
+        if !path.is_dir() {
+            continue;
+        }
+        let key = path.canonicalize().unwrap_or(path);
+        match merged.iter_mut().find(|(p, _)| *p == key) {
+            Some((_, sources)) => {
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+            None => merged.push((key, vec![source])),
+        }
+    }
+    merged
+}
+
+/// Find and validate every SDK on this machine, best candidate first.
+pub fn discover_sdks(config: &UserConfig) -> Vec<SdkCandidate> {
+    let mut candidates: Vec<SdkCandidate> = merge_candidates(candidate_paths(config))
+        .into_iter()
+        .map(|(path, sources)| inspect_sdk(&path, sources))
+        .collect();
+    rank(&mut candidates);
+    candidates
+}
+
+#[tauri::command]
+pub fn discover_zephyr_sdks(app: AppHandle) -> Result<Vec<SdkCandidate>, String> {
+    let config = config_manager::get_config(app)?;
+    Ok(discover_sdks(&config))
+}
+
+#[tauri::command]
+pub fn inspect_zephyr_sdk(path: String) -> Result<SdkCandidate, String> {
+    let path = Path::new(&path);
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    Ok(inspect_sdk(path, vec![SdkSource::Configured]))
+}
+
+const DEFAULT_SDK_MIRROR: &str = "https://github.com/zephyrproject-rtos/sdk-ng/releases/download";
+
+/// Where SDK archives come from. `LocalDir` lets offline labs point at a
+/// folder holding the minimal bundle and `toolchain_*` archives.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SdkArchiveSource {
+    Mirror { base_url: Option<String> },
+    LocalDir { path: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SdkInstallRequest {
+    pub version: String,
+    pub install_base: String,
+    pub toolchains: Vec<String>,
+    pub source: SdkArchiveSource,
+    #[serde(default)]
+    pub host_tools: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolchainUsage {
+    pub name: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InstalledSdk {
+    pub sdk: SdkCandidate,
+    pub size_bytes: u64,
+    pub toolchain_usage: Vec<ToolchainUsage>,
+}
+
+/// Host part of SDK archive names, e.g. `linux-x86_64`.
+fn host_id() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "macos",
+        "windows" => "windows",
+        _ => "linux",
+    };
+    format!("{}-{}", os, std::env::consts::ARCH)
+}
+
+fn archive_ext() -> &'static str {
+    if cfg!(windows) {
+        "7z"
+    } else {
+        "tar.xz"
+    }
+}
+
+fn minimal_archive_name(version: &str) -> String {
+    format!(
+        "zephyr-sdk-{}_{}_minimal.{}",
+        version,
+        host_id(),
+        archive_ext()
+    )
+}
+
+fn toolchain_archive_name(toolchain: &str) -> String {
+    format!("toolchain_{}_{}.{}", host_id(), toolchain, archive_ext())
+}
+
+/// Resolve an archive to a local file, downloading it into `download_dir`
+/// when it comes from a mirror.
+fn fetch_archive(
+    app: &AppHandle,
+    source: &SdkArchiveSource,
+    version: &str,
+    file_name: &str,
+    download_dir: &Path,
+) -> Result<PathBuf, String> {
+    match source {
+        SdkArchiveSource::LocalDir { path } => {
+            let file = Path::new(path).join(file_name);
+            if file.exists() {
+                Ok(file)
+            } else {
+                Err(format!("{} not found in {}", file_name, path))
+            }
+        }
+        SdkArchiveSource::Mirror { base_url } => {
+            let base = base_url.as_deref().unwrap_or(DEFAULT_SDK_MIRROR);
+            let url = format!("{}/v{}/{}", base.trim_end_matches('/'), version, file_name);
+            let dest = download_dir.join(file_name);
+            let dest_str = dest.to_string_lossy().to_string();
+
+            cmd_zephyr::emit_log(app, &format!("Downloading {}...", url));
+            if which::which("wget").is_ok() {
+                cmd_zephyr::run_command_stream(
+                    app,
+                    "wget",
+                    &["-q", "--show-progress", "-O", &dest_str, &url],
+                    None,
+                )?;
+            } else {
+                cmd_zephyr::run_command_stream(app, "curl", &["-fL", "-o", &dest_str, &url], None)?;
+            }
+            Ok(dest)
+        }
+    }
+}
+
+/// Fetch an archive and extract it into `dest`. Downloaded archives are
+/// deleted afterwards whether or not that worked; local ones are left alone.
+fn install_archive(
+    app: &AppHandle,
+    source: &SdkArchiveSource,
+    version: &str,
+    file_name: &str,
+    download_dir: &Path,
+    dest: &Path,
+) -> Result<(), String> {
+    let result = fetch_archive(app, source, version, file_name, download_dir)
+        .and_then(|archive| extract_archive(app, &archive, dest));
+    if matches!(source, SdkArchiveSource::Mirror { .. }) {
+        let _ = std::fs::remove_file(download_dir.join(file_name));
+    }
+    result
+}
+
+fn extract_archive(app: &AppHandle, archive: &Path, dest: &Path) -> Result<(), String> {
+    let name = archive.file_name().unwrap_or_default().to_string_lossy();
+    cmd_zephyr::emit_log(app, &format!("Extracting {}...", name));
+    std::fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+    let archive_str = archive.to_string_lossy().to_string();
+    let dest_str = dest.to_string_lossy().to_string();
+
+    if archive_str.ends_with(".7z") {
+        let out_arg = format!("-o{}", dest_str);
+        cmd_zephyr::run_command_stream(app, "7z", &["x", "-y", &out_arg, &archive_str], None)
+    } else {
+        cmd_zephyr::run_command_stream(app, "tar", &["xf", &archive_str, "-C", &dest_str], None)
+    }
+}
+
+/// Register the SDK in the CMake package registry (and optionally install
+/// host tools) through the SDK's own setup script.
+fn run_sdk_setup(app: &AppHandle, sdk_dir: &Path, host_tools: bool) -> Result<(), String> {
+    let sdk_dir_str = sdk_dir.to_string_lossy().to_string();
+
+    if cfg!(windows) {
+        let script = sdk_dir.join("setup.cmd").to_string_lossy().to_string();
+        cmd_zephyr::run_command_stream(app, "cmd", &["/C", &script, "/c"], Some(&sdk_dir_str))
+    } else {
+        let script = sdk_dir.join("setup.sh").to_string_lossy().to_string();
+        let mut args = vec![script.as_str(), "-c"];
+        if host_tools {
+            args.push("-h");
+        }
+        cmd_zephyr::run_command_stream(app, "bash", &args, Some(&sdk_dir_str))
+    }
+}
+
+#[tauri::command]
+pub async fn install_zephyr_sdk(
+    app: AppHandle,
+    request: SdkInstallRequest,
+) -> Result<SdkCandidate, String> {
+    let install_base = Path::new(&request.install_base);
+    let sdk_dir = install_base.join(format!("zephyr-sdk-{}", request.version));
+    let download_dir = std::env::temp_dir().join("one-studio-sdk");
+    std::fs::create_dir_all(&download_dir).map_err(|e| e.to_string())?;
+
+    // 1. Minimal bundle (setup script, cmake package, sdk_version)
+    if sdk_dir.join("sdk_version").exists() {
+        cmd_zephyr::emit_log(
+            &app,
+            &format!("{} already exists, reusing it", sdk_dir.display()),
+        );
+    } else {
+        install_archive(
+            &app,
+            &request.source,
+            &request.version,
+            &minimal_archive_name(&request.version),
+            &download_dir,
+            install_base,
+        )?;
+    }
+
+    // 2. Only the toolchains that were asked for
+    let existing = find_toolchains(&SystemExecutor, &sdk_dir);
+    for toolchain in &request.toolchains {
+        if existing.iter().any(|t| &t.name == toolchain) {
+            cmd_zephyr::emit_log(&app, &format!("{} is already installed", toolchain));
+            continue;
+        }
+        install_archive(
+            &app,
+            &request.source,
+            &request.version,
+            &toolchain_archive_name(toolchain),
+            &download_dir,
+            &sdk_dir,
+        )?;
+    }
+    // Only removed once empty, so a parallel install keeps its downloads
+    let _ = std::fs::remove_dir(&download_dir);
+
+    // 3. Register the CMake package so Zephyr builds can find the SDK
+    cmd_zephyr::emit_log(&app, "Registering SDK CMake package...");
+    run_sdk_setup(&app, &sdk_dir, request.host_tools)?;
+
+    let sdk = inspect_sdk(&sdk_dir, vec![SdkSource::Configured]);
+    if !sdk.valid {
+        return Err(format!(
+            "SDK installed but failed validation: {}",
+            sdk.problems.join(", ")
+        ));
+    }
+    cmd_zephyr::emit_log(&app, "Zephyr SDK installation complete!");
+    Ok(sdk)
+}
+
+/// Total size of a directory tree, not following symlinks.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| dir_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+#[tauri::command]
+pub async fn list_installed_sdks(app: AppHandle) -> Result<Vec<InstalledSdk>, String> {
+    let config = config_manager::get_config(app)?;
+
+    let installed = discover_sdks(&config)
+        .into_iter()
+        .filter(|sdk| sdk.version.is_some())
+        .map(|sdk| {
+            let toolchain_usage = sdk
+                .toolchains
+                .iter()
+                .map(|t| ToolchainUsage {
+                    name: t.name.clone(),
+                    // gcc lives in <toolchain>/bin/
+                    size_bytes: Path::new(&t.gcc_path)
+                        .parent()
+                        .and_then(|bin| bin.parent())
+                        .map(dir_size)
+                        .unwrap_or(0),
+                })
+                .collect();
+            InstalledSdk {
+                size_bytes: dir_size(Path::new(&sdk.path)),
+                sdk,
+                toolchain_usage,
+            }
+        })
+        .collect();
+    Ok(installed)
+}
+
+#[tauri::command]
+pub fn uninstall_zephyr_sdk(app: AppHandle, path: String) -> Result<(), String> {
+    let sdk_dir = Path::new(&path);
+    // Refuse to delete anything that does not look like an SDK
+    if toolchain_manager::read_sdk_version(sdk_dir).is_none() {
+        return Err(format!("{} is not a Zephyr SDK", path));
+    }
+
+    // Drop CMake registry entries pointing at this SDK
+    if let Some(home) = dirs::home_dir() {
+        let registry = home.join(".cmake").join("packages").join("Zephyr-sdk");
+        if let Ok(entries) = std::fs::read_dir(registry) {
+            for entry in entries.flatten() {
+                let points_here = std::fs::read_to_string(entry.path())
+                    .map(|c| Path::new(c.trim()).starts_with(sdk_dir))
+                    .unwrap_or(false);
+                if points_here {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+        }
+    }
+
+    std::fs::remove_dir_all(sdk_dir).map_err(|e| format!("Failed to remove SDK: {}", e))?;
+
+    // Profiles using this SDK fall back to discovery
+    let mut config = config_manager::get_config(app.clone())?;
+    for profile in &mut config.toolchains {
+        if profile.sdk_path.as_deref() == Some(path.as_str()) {
+            profile.sdk_path = None;
+            profile.sdk_version = None;
+        }
+    }
+    config_manager::save_config(app, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::FakeExecutor;
+    use crate::toolchain_manager::ToolchainProfile;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sdk-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// `<dir>/<toolchain>/bin/<toolchain>-gcc`, returning the gcc path.
+    fn add_toolchain(dir: &Path, toolchain: &str) -> String {
+        let bin = dir.join(toolchain).join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        let gcc = bin.join(exe_name(&format!("{}-gcc", toolchain)));
+        std::fs::write(&gcc, "").unwrap();
+        gcc.to_string_lossy().to_string()
+    }
+
+    fn candidate(
+        path: &str,
+        version: Option<&str>,
+        source: SdkSource,
+        valid: bool,
+    ) -> SdkCandidate {
+        SdkCandidate {
+            path: path.to_string(),
+            version: version.map(str::to_string),
+            sources: vec![source],
+            toolchains: Vec::new(),
+            valid,
+            problems: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn finds_toolchains_at_root_and_under_gnu() {
+        let sdk = temp_dir("toolchains");
+        let legacy = add_toolchain(&sdk, "arm-zephyr-eabi");
+        let gnu = add_toolchain(&sdk.join("gnu"), "riscv64-zephyr-elf");
+        let broken = add_toolchain(&sdk.join("gnu"), "arc-zephyr-elf");
+        add_toolchain(&sdk.join("gnu"), "xtensa-espressif_esp32_zephyr-elf");
+        std::fs::create_dir_all(sdk.join("gnu").join("x86_64-zephyr-elf")).unwrap();
+        std::fs::create_dir_all(sdk.join("cmake")).unwrap();
+
+        let exec = FakeExecutor::new()
+            .output(
+                &format!("{} --version", legacy),
+                "arm-zephyr-eabi-gcc (Zephyr SDK 0.16.8) 12.2.0\nCopyright\n",
+            )
+            .output(
+                &format!("{} --version", gnu),
+                "riscv64-zephyr-elf-gcc (Zephyr SDK 0.17.0) 12.2.0\n",
+            )
+            .failure(&format!("{} --version", broken), 126, "cannot execute");
+        let toolchains = find_toolchains(&exec, &sdk);
+        std::fs::remove_dir_all(&sdk).unwrap();
+
+        let found: Vec<(&str, bool)> = toolchains
+            .iter()
+            .map(|t| (t.name.as_str(), t.working))
+            .collect();
+        // xtensa names lack `-zephyr-`; x86_64 has no gcc
+        assert_eq!(
+            found,
+            [
+                ("arc-zephyr-elf", false),
+                ("arm-zephyr-eabi", true),
+                ("riscv64-zephyr-elf", true)
+            ]
+        );
+        assert_eq!(toolchains[0].gcc_version, None);
+        assert_eq!(toolchains[2].gcc_path, gnu);
+        assert_eq!(
+            toolchains[1].gcc_version.as_deref(),
+            Some("arm-zephyr-eabi-gcc (Zephyr SDK 0.16.8) 12.2.0")
+        );
+    }
+
+    #[test]
+    fn ranks_valid_trusted_and_newest_first() {
+        let mut candidates = vec![
+            candidate("/opt/broken", Some("0.17.1"), SdkSource::EnvVar, false),
+            candidate(
+                "/opt/zephyr-sdk-0.16.8",
+                Some("0.16.8"),
+                SdkSource::System,
+                true,
+            ),
+            candidate(
+                "/opt/zephyr-sdk-0.17.0",
+                Some("0.17.0"),
+                SdkSource::System,
+                true,
+            ),
+            candidate(
+                "/opt/zephyr-sdk-0.17.0-rc1",
+                Some("0.17.0-rc1"),
+                SdkSource::System,
+                true,
+            ),
+            candidate(
+                "/home/u/zephyr-sdk-0.16.5",
+                Some("0.16.5"),
+                SdkSource::Home,
+                true,
+            ),
+            candidate("/sdk/unknown", None, SdkSource::System, true),
+        ];
+        rank(&mut candidates);
+        let order: Vec<&str> = candidates.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            order,
+            [
+                "/home/u/zephyr-sdk-0.16.5",
+                "/opt/zephyr-sdk-0.17.0",
+                "/opt/zephyr-sdk-0.17.0-rc1",
+                "/opt/zephyr-sdk-0.16.8",
+                "/sdk/unknown",
+                "/opt/broken",
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_configured_paths_and_merges_duplicates() {
+        let root = temp_dir("roots");
+        let bundled = root.join("zephyr-sdk-0.16.8");
+        std::fs::create_dir_all(&bundled).unwrap();
+        std::fs::create_dir_all(root.join("not-an-sdk")).unwrap();
+        std::fs::write(root.join("sdk_version"), "0.17.0").unwrap();
+
+        let config = UserConfig {
+            sdk_search_roots: vec![root.to_string_lossy().to_string()],
+            toolchains: vec![ToolchainProfile {
+                id: "main".to_string(),
+                name: "main".to_string(),
+                zephyr_base: String::new(),
+                venv_path: None,
+                // Same SDK as the search root finds, spelled differently
+                sdk_path: Some(
+                    root.join("not-an-sdk")
+                        .join("..")
+                        .join("zephyr-sdk-0.16.8")
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+                zephyr_version: None,
+                sdk_version: None,
+            }],
+            ..Default::default()
+        };
+
+        let paths = candidate_paths(&config);
+        assert!(paths.contains(&(root.clone(), SdkSource::Configured)));
+        assert!(paths.contains(&(bundled.clone(), SdkSource::Configured)));
+        assert!(!paths.iter().any(|(p, _)| p.ends_with("not-an-sdk")));
+
+        let merged = merge_candidates(vec![
+            (bundled.clone(), SdkSource::System),
+            (root.join("missing"), SdkSource::Configured),
+            (
+                bundled.join("..").join("zephyr-sdk-0.16.8"),
+                SdkSource::Home,
+            ),
+            (bundled.clone(), SdkSource::System),
+        ]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].0, bundled.canonicalize().unwrap());
+        assert_eq!(merged[0].1, [SdkSource::System, SdkSource::Home]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
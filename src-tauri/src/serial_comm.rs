@@ -0,0 +1,695 @@
+//! Serial ports and other byte sources, streamed to the frontend.
+//!
+//! Port I/O is blocking, not async: each open source gets one reader thread
+//! that blocks in `read` for at most `READ_TIMEOUT`, then checks whether the
+//! port was closed, so closing a port (stop flag, then join) returns within
+//! one timeout. Writes go straight to the device from the calling command,
+//! behind a lock shared with the reader's reconnect logic.
+
+use crate::coredump;
+use crate::frame_codec;
+use crate::log_dictionary;
+use crate::serial_log;
+use serde::{Deserialize, Serialize};
+use serialport::{SerialPort, SerialPortType};
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// How long a blocking read waits before checking whether the port was closed.
+pub(crate) const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StopBits {
+    #[default]
+    One,
+    Two,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowControl {
+    #[default]
+    None,
+    Software,
+    Hardware,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SerialConfig {
+    pub baud_rate: u32,
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+    /// Keep retrying the same device path after it disappears (USB unplug)
+    pub auto_reconnect: bool,
+    pub reconnect_interval_ms: u64,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: 115200,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            auto_reconnect: true,
+            reconnect_interval_ms: 1000,
+        }
+    }
+}
+
+/// A port found on the system.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SerialPortEntry {
+    pub name: String,
+    /// `usb`, `pci`, `bluetooth` or `unknown`
+    pub port_type: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PortState {
+    Open,
+    Disconnected,
+    Reconnected,
+    Closed,
+}
+
+/// Payload of the `serial-data` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct SerialData {
+    pub port: String,
+    pub data: Vec<u8>,
+    pub timestamp: u64, // Unix time in milliseconds
+}
+
+/// Payload of the `serial-status` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct SerialStatus {
+    pub port: String,
+    pub state: PortState,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OpenPortInfo {
+    pub port: String,
+    /// `None` for non-serial sources such as RTT
+    pub config: Option<SerialConfig>,
+    pub connected: bool,
+}
+
+/// Where a port's reader thread delivers bytes and state changes.
+pub trait SerialSink: Send + Sync {
+    fn data(&self, port: &str, data: &[u8]);
+    fn status(&self, port: &str, state: PortState, message: Option<String>);
+}
+
+/// Forwards to the frontend as `serial-data` / `serial-status` events.
+pub(crate) struct EventSink(pub AppHandle);
+
+impl SerialSink for EventSink {
+    fn data(&self, port: &str, data: &[u8]) {
+        let _ = self.0.emit(
+            "serial-data",
+            SerialData {
+                port: port.to_string(),
+                data: data.to_vec(),
+                timestamp: serial_log::now_millis(),
+            },
+        );
+    }
+
+    fn status(&self, port: &str, state: PortState, message: Option<String>) {
+        let _ = self.0.emit(
+            "serial-status",
+            SerialStatus {
+                port: port.to_string(),
+                state,
+                message,
+            },
+        );
+    }
+}
+
+pub(crate) type Reader = Box<dyn Read + Send>;
+pub(crate) type Writer = Box<dyn Write + Send>;
+/// Opens a source's read and write halves; called again to reconnect.
+/// Reads must time out regularly so the source can be closed.
+pub(crate) type Connect = Box<dyn Fn() -> Result<(Reader, Writer), String> + Send>;
+
+type SharedWriter = Arc<Mutex<Option<Writer>>>;
+
+struct OpenPort {
+    config: Option<SerialConfig>,
+    /// Clone of the reader's handle; `None` while disconnected
+    writer: SharedWriter,
+    stop: Arc<AtomicBool>,
+    reader: thread::JoinHandle<()>,
+    sink: Arc<dyn SerialSink>,
+}
+
+static OPEN_PORTS: Mutex<BTreeMap<String, OpenPort>> = Mutex::new(BTreeMap::new());
+
+struct Subscriber {
+    id: u64,
+    port: String,
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static NEXT_SUBSCRIBER: AtomicU64 = AtomicU64::new(1);
+
+/// Bytes received on a port while this is alive, for backend consumers
+/// such as the shell client.
+pub(crate) struct Subscription {
+    id: u64,
+    pub rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        SUBSCRIBERS.lock().unwrap().retain(|s| s.id != self.id);
+    }
+}
+
+pub(crate) fn subscribe(port: &str) -> Result<Subscription, String> {
+    if !OPEN_PORTS.lock().unwrap().contains_key(port) {
+        return Err(format!("{} is not open", port));
+    }
+    let (tx, rx) = mpsc::channel();
+    let id = NEXT_SUBSCRIBER.fetch_add(1, Ordering::Relaxed);
+    SUBSCRIBERS.lock().unwrap().push(Subscriber {
+        id,
+        port: port.to_string(),
+        tx,
+    });
+    Ok(Subscription { id, rx })
+}
+
+fn notify_subscribers(port: &str, data: &[u8]) {
+    for subscriber in SUBSCRIBERS.lock().unwrap().iter() {
+        if subscriber.port == port {
+            let _ = subscriber.tx.send(data.to_vec());
+        }
+    }
+}
+
+fn port_entry(info: serialport::SerialPortInfo) -> SerialPortEntry {
+    let mut entry = SerialPortEntry {
+        name: info.port_name,
+        port_type: "unknown".to_string(),
+        vid: None,
+        pid: None,
+        serial_number: None,
+        manufacturer: None,
+        product: None,
+        description: "Serial port".to_string(),
+    };
+    match info.port_type {
+        SerialPortType::UsbPort(usb) => {
+            entry.port_type = "usb".to_string();
+            entry.description = usb
+                .product
+                .clone()
+                .or_else(|| usb.manufacturer.clone())
+                .unwrap_or_else(|| format!("USB {:04x}:{:04x}", usb.vid, usb.pid));
+            entry.vid = Some(usb.vid);
+            entry.pid = Some(usb.pid);
+            entry.serial_number = usb.serial_number;
+            entry.manufacturer = usb.manufacturer;
+            entry.product = usb.product;
+        }
+        SerialPortType::PciPort => {
+            entry.port_type = "pci".to_string();
+            entry.description = "PCI serial port".to_string();
+        }
+        SerialPortType::BluetoothPort => {
+            entry.port_type = "bluetooth".to_string();
+            entry.description = "Bluetooth serial port".to_string();
+        }
+        SerialPortType::Unknown => {}
+    }
+    entry
+}
+
+fn open_device(name: &str, config: &SerialConfig) -> Result<Box<dyn SerialPort>, String> {
+    let data_bits = match config.data_bits {
+        5 => serialport::DataBits::Five,
+        6 => serialport::DataBits::Six,
+        7 => serialport::DataBits::Seven,
+        8 => serialport::DataBits::Eight,
+        n => return Err(format!("Unsupported data bits: {}", n)),
+    };
+    let parity = match config.parity {
+        Parity::None => serialport::Parity::None,
+        Parity::Odd => serialport::Parity::Odd,
+        Parity::Even => serialport::Parity::Even,
+    };
+    let stop_bits = match config.stop_bits {
+        StopBits::One => serialport::StopBits::One,
+        StopBits::Two => serialport::StopBits::Two,
+    };
+    let flow_control = match config.flow_control {
+        FlowControl::None => serialport::FlowControl::None,
+        FlowControl::Software => serialport::FlowControl::Software,
+        FlowControl::Hardware => serialport::FlowControl::Hardware,
+    };
+
+    serialport::new(name, config.baud_rate)
+        .data_bits(data_bits)
+        .parity(parity)
+        .stop_bits(stop_bits)
+        .flow_control(flow_control)
+        .timeout(READ_TIMEOUT)
+        .open()
+        .map_err(|e| format!("Failed to open {}: {}", name, e))
+}
+
+/// Sleep for `duration`, waking early when `stop` is set.
+pub(crate) fn wait_unless_stopped(stop: &AtomicBool, duration: Duration) {
+    let deadline = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+struct ReadLoop {
+    name: String,
+    connect: Connect,
+    /// Retry interval after the source drops; `None` to give up
+    reconnect: Option<Duration>,
+    writer: SharedWriter,
+    stop: Arc<AtomicBool>,
+    sink: Arc<dyn SerialSink>,
+}
+
+fn read_loop(ctx: ReadLoop, reader: Reader) {
+    let ReadLoop {
+        name,
+        connect,
+        reconnect,
+        writer,
+        stop,
+        sink,
+    } = ctx;
+    let mut port = Some(reader);
+    let mut buf = [0u8; 4096];
+
+    while !stop.load(Ordering::Relaxed) {
+        let Some(device) = port.as_mut() else {
+            let Some(interval) = reconnect else {
+                break;
+            };
+            wait_unless_stopped(&stop, interval);
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            if let Ok((reader, new_writer)) = connect() {
+                *writer.lock().unwrap() = Some(new_writer);
+                port = Some(reader);
+                sink.status(&name, PortState::Reconnected, None);
+            }
+            continue;
+        };
+
+        let error = match device.read(&mut buf) {
+            Ok(0) => "Connection closed".to_string(),
+            Ok(n) => {
+                serial_log::record(&name, &buf[..n]);
+                frame_codec::feed(&name, &buf[..n]);
+                log_dictionary::feed(&name, &buf[..n]);
+                coredump::feed(&name, &buf[..n]);
+                notify_subscribers(&name, &buf[..n]);
+                sink.data(&name, &buf[..n]);
+                continue;
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted
+                ) =>
+            {
+                continue
+            }
+            Err(e) => e.to_string(),
+        };
+
+        // Unplugged or otherwise gone; drop both handles so the device node
+        // can be reused when it comes back
+        *writer.lock().unwrap() = None;
+        port = None;
+        sink.status(&name, PortState::Disconnected, Some(error));
+    }
+}
+
+/// Register a byte source under `name` and start streaming it to `sink`,
+/// captures, frame decoders and subscribers.
+pub(crate) fn open_source(
+    name: &str,
+    connect: Connect,
+    reconnect: Option<Duration>,
+    config: Option<SerialConfig>,
+    sink: Arc<dyn SerialSink>,
+) -> Result<(), String> {
+    let already_open = || format!("{} is already open", name);
+    if OPEN_PORTS.lock().unwrap().contains_key(name) {
+        return Err(already_open());
+    }
+
+    // Connecting can block (device open, TCP timeout); keep the registry
+    // unlocked meanwhile and re-check once it is done.
+    let (reader, writer) = connect()?;
+    let mut ports = OPEN_PORTS.lock().unwrap();
+    if ports.contains_key(name) {
+        return Err(already_open());
+    }
+    let writer = Arc::new(Mutex::new(Some(writer)));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let reader = {
+        let ctx = ReadLoop {
+            name: name.to_string(),
+            connect,
+            reconnect,
+            writer: writer.clone(),
+            stop: stop.clone(),
+            sink: sink.clone(),
+        };
+        thread::spawn(move || read_loop(ctx, reader))
+    };
+
+    sink.status(name, PortState::Open, None);
+    ports.insert(
+        name.to_string(),
+        OpenPort {
+            config,
+            writer,
+            stop,
+            reader,
+            sink,
+        },
+    );
+    Ok(())
+}
+
+pub(crate) fn open_port(
+    name: &str,
+    config: SerialConfig,
+    sink: Arc<dyn SerialSink>,
+) -> Result<(), String> {
+    let connect: Connect = {
+        let name = name.to_string();
+        let config = config.clone();
+        Box::new(move || {
+            let device = open_device(&name, &config)?;
+            let writer = device.try_clone().map_err(|e| e.to_string())?;
+            Ok((Box::new(device) as Reader, Box::new(writer) as Writer))
+        })
+    };
+    let reconnect = config
+        .auto_reconnect
+        .then(|| Duration::from_millis(config.reconnect_interval_ms));
+    open_source(name, connect, reconnect, Some(config), sink)
+}
+
+pub(crate) fn close_port(name: &str) -> Result<(), String> {
+    let port = OPEN_PORTS
+        .lock()
+        .unwrap()
+        .remove(name)
+        .ok_or_else(|| format!("{} is not open", name))?;
+    port.stop.store(true, Ordering::Relaxed);
+    let _ = port.reader.join();
+    let _ = serial_log::stop_capture(name);
+    port.sink.status(name, PortState::Closed, None);
+    Ok(())
+}
+
+pub(crate) fn write_port(name: &str, data: &[u8]) -> Result<usize, String> {
+    let writer = OPEN_PORTS
+        .lock()
+        .unwrap()
+        .get(name)
+        .map(|p| p.writer.clone())
+        .ok_or_else(|| format!("{} is not open", name))?;
+
+    let mut writer = writer.lock().unwrap();
+    let device = writer
+        .as_mut()
+        .ok_or_else(|| format!("{} is disconnected", name))?;
+    device
+        .write_all(data)
+        .and_then(|_| device.flush())
+        .map_err(|e| format!("Failed to write to {}: {}", name, e))?;
+    Ok(data.len())
+}
+
+pub(crate) fn open_ports() -> Vec<OpenPortInfo> {
+    OPEN_PORTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, p)| OpenPortInfo {
+            port: name.clone(),
+            config: p.config.clone(),
+            connected: p.writer.lock().unwrap().is_some(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn list_serial_ports() -> Result<Vec<SerialPortEntry>, String> {
+    let mut ports: Vec<SerialPortEntry> = serialport::available_ports()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(port_entry)
+        .collect();
+    // USB adapters are what people plug boards into; list them first
+    ports.sort_by(|a, b| (a.port_type != "usb", &a.name).cmp(&(b.port_type != "usb", &b.name)));
+    Ok(ports)
+}
+
+#[tauri::command]
+pub fn open_serial_port(
+    app: AppHandle,
+    port: String,
+    config: Option<SerialConfig>,
+) -> Result<(), String> {
+    open_port(&port, config.unwrap_or_default(), Arc::new(EventSink(app)))
+}
+
+#[tauri::command]
+pub fn close_serial_port(port: String) -> Result<(), String> {
+    close_port(&port)
+}
+
+#[tauri::command]
+pub fn write_serial(port: String, data: Vec<u8>) -> Result<usize, String> {
+    write_port(&port, &data)
+}
+
+#[tauri::command]
+pub fn list_open_serial_ports() -> Vec<OpenPortInfo> {
+    open_ports()
+}
+
+/// A sink recording events on a channel, for tests of byte sources.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    #[derive(Debug)]
+    pub(crate) enum Event {
+        Data(String, Vec<u8>),
+        Status(String, PortState),
+    }
+
+    pub(crate) struct ChannelSink(Mutex<mpsc::Sender<Event>>);
+
+    impl SerialSink for ChannelSink {
+        fn data(&self, port: &str, data: &[u8]) {
+            let _ = self
+                .0
+                .lock()
+                .unwrap()
+                .send(Event::Data(port.to_string(), data.to_vec()));
+        }
+
+        fn status(&self, port: &str, state: PortState, _message: Option<String>) {
+            let _ = self
+                .0
+                .lock()
+                .unwrap()
+                .send(Event::Status(port.to_string(), state));
+        }
+    }
+
+    pub(crate) fn sink() -> (Arc<dyn SerialSink>, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel();
+        (Arc::new(ChannelSink(Mutex::new(tx))), rx)
+    }
+
+    /// Collect bytes received on `port` until `expected` has arrived.
+    pub(crate) fn receive(rx: &mpsc::Receiver<Event>, port: &str, expected: &[u8]) -> Vec<u8> {
+        let mut received = Vec::new();
+        while received.len() < expected.len() {
+            match rx.recv_timeout(Duration::from_secs(2)) {
+                Ok(Event::Data(p, data)) if p == port => received.extend(data),
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        received
+    }
+
+    pub(crate) fn wait_for(rx: &mpsc::Receiver<Event>, port: &str, state: PortState) -> bool {
+        while let Ok(event) = rx.recv_timeout(Duration::from_secs(3)) {
+            if let Event::Status(p, s) = event {
+                if p == port && s == state {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::testing::{receive, sink, wait_for};
+    use super::*;
+    use serialport::TTYPort;
+
+    /// A pty pair; the returned path is the device side the monitor opens.
+    fn pty() -> (TTYPort, String) {
+        let (mut master, slave) = TTYPort::pair().expect("pty pair");
+        master.set_timeout(Duration::from_secs(2)).unwrap();
+        let name = slave.name().unwrap();
+        (master, name)
+    }
+
+    #[test]
+    fn streams_received_bytes_and_accepts_writes() {
+        let (mut master, name) = pty();
+        let (sink, rx) = sink();
+        open_port(&name, SerialConfig::default(), sink).unwrap();
+        assert!(wait_for(&rx, &name, PortState::Open));
+
+        master.write_all(b"uart:~$ hello\r\n").unwrap();
+        assert_eq!(
+            receive(&rx, &name, b"uart:~$ hello\r\n"),
+            b"uart:~$ hello\r\n"
+        );
+
+        assert_eq!(write_port(&name, b"ping\n").unwrap(), 5);
+        let mut buf = [0u8; 5];
+        master.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping\n");
+
+        assert!(open_port(&name, SerialConfig::default(), self::sink().0).is_err());
+        close_port(&name).unwrap();
+        assert!(wait_for(&rx, &name, PortState::Closed));
+        assert!(write_port(&name, b"x").is_err());
+    }
+
+    #[test]
+    fn subscribers_see_received_bytes() {
+        let (mut master, name) = pty();
+        assert!(subscribe(&name).is_err());
+        open_port(&name, SerialConfig::default(), sink().0).unwrap();
+
+        let subscription = subscribe(&name).unwrap();
+        master.write_all(b"uart:~$ ").unwrap();
+        let data = subscription
+            .rx
+            .recv_timeout(Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(data, b"uart:~$ ");
+
+        drop(subscription);
+        assert!(SUBSCRIBERS.lock().unwrap().iter().all(|s| s.port != name));
+        close_port(&name).unwrap();
+    }
+
+    #[test]
+    fn keeps_several_ports_apart() {
+        let (mut master_a, a) = pty();
+        let (mut master_b, b) = pty();
+        let (sink, rx) = sink();
+        open_port(&a, SerialConfig::default(), sink.clone()).unwrap();
+        open_port(&b, SerialConfig::default(), sink).unwrap();
+
+        let names: Vec<String> = open_ports().into_iter().map(|p| p.port).collect();
+        assert!(names.contains(&a) && names.contains(&b));
+
+        master_b.write_all(b"from b").unwrap();
+        assert_eq!(receive(&rx, &b, b"from b"), b"from b");
+        master_a.write_all(b"from a").unwrap();
+        assert_eq!(receive(&rx, &a, b"from a"), b"from a");
+
+        close_port(&a).unwrap();
+        close_port(&b).unwrap();
+    }
+
+    #[test]
+    fn reconnects_after_unplug() {
+        // A stable symlink stands in for /dev/serial/by-id, re-pointed at a
+        // new pty once the first one goes away
+        let dir = std::env::temp_dir().join(format!("serial-reconnect-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let link = dir.join("ttyBOARD");
+        let _ = std::fs::remove_file(&link);
+
+        let (master, first) = pty();
+        std::os::unix::fs::symlink(&first, &link).unwrap();
+        let name = link.to_string_lossy().to_string();
+
+        let config = SerialConfig {
+            reconnect_interval_ms: 50,
+            ..Default::default()
+        };
+        let (sink, rx) = sink();
+        open_port(&name, config, sink).unwrap();
+
+        drop(master);
+        std::fs::remove_file(&link).unwrap();
+        assert!(wait_for(&rx, &name, PortState::Disconnected));
+        assert!(write_port(&name, b"x").is_err());
+
+        let (mut master, second) = pty();
+        std::os::unix::fs::symlink(&second, &link).unwrap();
+        assert!(wait_for(&rx, &name, PortState::Reconnected));
+
+        master.write_all(b"back").unwrap();
+        assert_eq!(receive(&rx, &name, b"back"), b"back");
+        assert_eq!(write_port(&name, b"ok").unwrap(), 2);
+
+        close_port(&name).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
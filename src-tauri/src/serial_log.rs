@@ -0,0 +1,798 @@
+use crate::serial_comm::SerialData;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// Header of raw captures, followed by records of
+/// `[u64 LE timestamp ms][u32 LE length][bytes]`.
+pub(crate) const RAW_MAGIC: &[u8] = b"OSCAP1\n";
+/// A line longer than this without a newline is written out anyway.
+const MAX_LINE_BYTES: usize = 4096;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureMode {
+    /// Text, one `[timestamp] line` per received line
+    #[default]
+    Line,
+    /// Every received chunk verbatim with its arrival time
+    Raw,
+}
+
+impl CaptureMode {
+    fn extension(self) -> &'static str {
+        match self {
+            CaptureMode::Line => "log",
+            CaptureMode::Raw => "bin",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CaptureOptions {
+    pub mode: CaptureMode,
+    /// Defaults to `captures/` in the app data directory
+    pub directory: Option<String>,
+    /// Start a new file once the current one reaches this size
+    pub max_file_bytes: Option<u64>,
+    /// Start a new file after this many seconds
+    pub rotate_after_secs: Option<u64>,
+    /// Delete the oldest files of this port beyond this count
+    pub max_files: Option<usize>,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            mode: CaptureMode::Line,
+            directory: None,
+            max_file_bytes: Some(16 * 1024 * 1024),
+            rotate_after_secs: None,
+            max_files: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureFile {
+    pub path: String,
+    pub port: String,
+    pub mode: CaptureMode,
+    pub size: u64,
+    pub started_at: Option<u64>, // Unix time in milliseconds, from the file name
+}
+
+/// An in-progress capture of one port.
+pub struct Capture {
+    dir: PathBuf,
+    stem: String,
+    options: CaptureOptions,
+    file: File,
+    path: PathBuf,
+    opened_at: u64,
+    written: u64,
+    /// Partial line and the time its first byte arrived
+    pending: Vec<u8>,
+    pending_since: u64,
+    files: Vec<PathBuf>,
+}
+
+impl Capture {
+    pub fn start(dir: &Path, port: &str, options: CaptureOptions, now: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let stem = file_stem(port);
+        let (file, path) = create_file(dir, &stem, options.mode.extension(), now)?;
+        let mut capture = Self {
+            dir: dir.to_path_buf(),
+            stem,
+            options,
+            file,
+            path: path.clone(),
+            opened_at: now,
+            written: 0,
+            pending: Vec::new(),
+            pending_since: now,
+            files: vec![path],
+        };
+        capture.write_header()?;
+        Ok(capture)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, data: &[u8], now: u64) -> io::Result<()> {
+        match self.options.mode {
+            CaptureMode::Raw => {
+                let mut record = Vec::with_capacity(12 + data.len());
+                record.extend_from_slice(&now.to_le_bytes());
+                record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                record.extend_from_slice(data);
+                self.write_record(&record, now)
+            }
+            CaptureMode::Line => {
+                for &byte in data {
+                    if self.pending.is_empty() {
+                        self.pending_since = now;
+                    }
+                    if byte == b'\n' {
+                        self.flush_line(now)?;
+                    } else {
+                        self.pending.push(byte);
+                        if self.pending.len() >= MAX_LINE_BYTES {
+                            self.flush_line(now)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Write out any partial line and return every file this capture wrote.
+    pub fn finish(mut self, now: u64) -> io::Result<Vec<PathBuf>> {
+        if !self.pending.is_empty() {
+            self.flush_line(now)?;
+        }
+        self.file.flush()?;
+        Ok(self.files)
+    }
+
+    fn flush_line(&mut self, now: u64) -> io::Result<()> {
+        let line = std::mem::take(&mut self.pending);
+        let text = String::from_utf8_lossy(&line);
+        let text = text.strip_suffix('\r').unwrap_or(&text);
+        let record = format!("[{}] {}\n", format_timestamp(self.pending_since), text);
+        self.write_record(record.as_bytes(), now)
+    }
+
+    fn write_record(&mut self, record: &[u8], now: u64) -> io::Result<()> {
+        if self.should_rotate(now) {
+            self.rotate(now)?;
+        }
+        self.file.write_all(record)?;
+        self.written += record.len() as u64;
+        Ok(())
+    }
+
+    fn should_rotate(&self, now: u64) -> bool {
+        if self.written == 0 {
+            return false;
+        }
+        let too_big = self
+            .options
+            .max_file_bytes
+            .is_some_and(|max| self.written >= max);
+        let too_old = self
+            .options
+            .rotate_after_secs
+            .is_some_and(|secs| now.saturating_sub(self.opened_at) >= secs * 1000);
+        too_big || too_old
+    }
+
+    fn rotate(&mut self, now: u64) -> io::Result<()> {
+        self.file.flush()?;
+        let (file, path) = create_file(&self.dir, &self.stem, self.options.mode.extension(), now)?;
+        self.file = file;
+        self.path = path.clone();
+        self.opened_at = now;
+        self.written = 0;
+        self.files.push(path);
+        self.write_header()?;
+        self.prune();
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if self.options.mode == CaptureMode::Raw {
+            self.file.write_all(RAW_MAGIC)?;
+        }
+        Ok(())
+    }
+
+    fn prune(&mut self) {
+        let Some(max_files) = self.options.max_files else {
+            return;
+        };
+        let mut files: Vec<PathBuf> = list_capture_files(&self.dir)
+            .into_iter()
+            .filter(|f| f.port == self.stem && f.mode == self.options.mode)
+            .map(|f| PathBuf::from(f.path))
+            .collect();
+        files.sort();
+        let excess = files.len().saturating_sub(max_files.max(1));
+        for path in &files[..excess] {
+            let _ = fs::remove_file(path);
+        }
+        self.files.retain(|p| p.exists());
+    }
+}
+
+static CAPTURES: Mutex<BTreeMap<String, Capture>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Tee received bytes into the port's capture, if one is running.
+pub(crate) fn record(port: &str, data: &[u8]) {
+    if let Some(capture) = CAPTURES.lock().unwrap().get_mut(port) {
+        // A failed write (e.g. disk full) only loses this chunk
+        let _ = capture.write(data, now_millis());
+    }
+}
+
+pub(crate) fn start_capture(
+    dir: &Path,
+    port: &str,
+    options: CaptureOptions,
+) -> Result<PathBuf, String> {
+    let mut captures = CAPTURES.lock().unwrap();
+    if captures.contains_key(port) {
+        return Err(format!("{} is already being captured", port));
+    }
+    let capture = Capture::start(dir, port, options, now_millis())
+        .map_err(|e| format!("Failed to start capture: {}", e))?;
+    let path = capture.path().to_path_buf();
+    captures.insert(port.to_string(), capture);
+    Ok(path)
+}
+
+pub(crate) fn stop_capture(port: &str) -> Result<Vec<PathBuf>, String> {
+    let capture = CAPTURES
+        .lock()
+        .unwrap()
+        .remove(port)
+        .ok_or_else(|| format!("{} is not being captured", port))?;
+    capture.finish(now_millis()).map_err(|e| e.to_string())
+}
+
+/// Captures in `dir`, newest first; files started in the same second are
+/// ordered by path, which puts later rotations (`_1`, `_2`) first.
+pub fn list_capture_files(dir: &Path) -> Vec<CaptureFile> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<CaptureFile> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let mode = match path.extension()?.to_str()? {
+                "log" => CaptureMode::Line,
+                "bin" => CaptureMode::Raw,
+                _ => return None,
+            };
+            let (port, started_at) = parse_file_name(path.file_stem()?.to_str()?)?;
+            Some(CaptureFile {
+                path: path.to_string_lossy().to_string(),
+                port,
+                mode,
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+                started_at: Some(started_at),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| {
+        b.started_at
+            .cmp(&a.started_at)
+            .then_with(|| b.path.cmp(&a.path))
+    });
+    files
+}
+
+/// Records of a capture file, read incrementally.
+enum CaptureRecords {
+    /// Raw records; a truncated final one (crash mid-write) ends the file
+    Raw(BufReader<File>),
+    /// Lines, with the timestamp of the last stamped one for those without
+    Line { reader: BufReader<File>, last: u64 },
+}
+
+struct CaptureReader {
+    port: String,
+    records: CaptureRecords,
+    len: u64,
+}
+
+impl CaptureReader {
+    fn open(path: &Path) -> Result<Self, String> {
+        let port = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(parse_file_name)
+            .map(|(port, _)| port)
+            .unwrap_or_else(|| path.to_string_lossy().to_string());
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let len = file.metadata().map_err(|e| e.to_string())?.len();
+        let mut reader = BufReader::new(file);
+
+        let is_raw = reader
+            .fill_buf()
+            .map_err(|e| e.to_string())?
+            .starts_with(RAW_MAGIC);
+        let records = if is_raw {
+            reader.consume(RAW_MAGIC.len());
+            CaptureRecords::Raw(reader)
+        } else {
+            CaptureRecords::Line { reader, last: 0 }
+        };
+        Ok(Self { port, records, len })
+    }
+
+    fn reader(&mut self) -> &mut BufReader<File> {
+        match &mut self.records {
+            CaptureRecords::Raw(reader) => reader,
+            CaptureRecords::Line { reader, .. } => reader,
+        }
+    }
+
+    /// Byte position of the next record.
+    fn position(&mut self) -> Result<u64, String> {
+        self.reader().stream_position().map_err(|e| e.to_string())
+    }
+
+    /// Continue from a position returned by `position`. Positions before the
+    /// first record (inside the raw header) start at the first record.
+    fn seek(&mut self, position: u64) -> Result<(), String> {
+        if position > self.position()? {
+            self.reader()
+                .seek(SeekFrom::Start(position))
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for CaptureReader {
+    type Item = SerialData;
+
+    fn next(&mut self) -> Option<SerialData> {
+        let (data, timestamp) = match &mut self.records {
+            CaptureRecords::Raw(reader) => {
+                let mut header = [0u8; 12];
+                reader.read_exact(&mut header).ok()?;
+                let timestamp = u64::from_le_bytes(header[..8].try_into().unwrap());
+                let len = u32::from_le_bytes(header[8..].try_into().unwrap()) as u64;
+                let mut data = Vec::new();
+                reader.by_ref().take(len).read_to_end(&mut data).ok()?;
+                if data.len() as u64 != len {
+                    return None;
+                }
+                (data, timestamp)
+            }
+            CaptureRecords::Line { reader, last } => {
+                let mut line = Vec::new();
+                if reader.read_until(b'\n', &mut line).ok()? == 0 {
+                    return None;
+                }
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end_matches('\n').trim_end_matches('\r');
+                let (timestamp, text) = line
+                    .strip_prefix('[')
+                    .and_then(|l| l.split_once("] "))
+                    .and_then(|(ts, text)| Some((parse_timestamp(ts)?, text)))
+                    .unwrap_or((*last, line));
+                *last = timestamp;
+                (format!("{}\n", text).into_bytes(), timestamp)
+            }
+        };
+        Some(SerialData {
+            port: self.port.clone(),
+            data,
+            timestamp,
+        })
+    }
+}
+
+/// Read a capture back as the chunks the viewer shows for a live port.
+pub fn read_capture(path: &Path) -> Result<Vec<SerialData>, String> {
+    Ok(CaptureReader::open(path)?.collect())
+}
+
+/// Records per `open_serial_capture` page unless asked otherwise, and the
+/// most it returns at once.
+const DEFAULT_PAGE: usize = 5_000;
+const MAX_PAGE: usize = 50_000;
+
+/// A window of a capture file's records.
+#[derive(Debug, Serialize, Clone)]
+pub struct CapturePage {
+    pub records: Vec<SerialData>,
+    /// Byte position of the first record in `records`
+    pub offset: u64,
+    /// Byte position of the next page, `None` at the end of the file
+    pub next_offset: Option<u64>,
+}
+
+/// Up to `limit` records starting at byte `offset`, which is 0 or a
+/// `next_offset` from an earlier page, so each page is a seek away.
+pub fn read_capture_page(path: &Path, offset: u64, limit: usize) -> Result<CapturePage, String> {
+    let limit = limit.clamp(1, MAX_PAGE);
+    let mut reader = CaptureReader::open(path)?;
+    reader.seek(offset)?;
+    let offset = reader.position()?;
+    let records: Vec<SerialData> = reader.by_ref().take(limit).collect();
+    let end = reader.position()?;
+    let next_offset = (records.len() == limit && end < reader.len).then_some(end);
+    Ok(CapturePage {
+        records,
+        offset,
+        next_offset,
+    })
+}
+
+/// `/dev/serial/by-id/usb-SEGGER_J-Link-if00` -> `usb-SEGGER_J-Link-if00`,
+/// `COM3` -> `COM3`.
+pub(crate) fn file_stem(port: &str) -> String {
+    let name = Path::new(port)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(port);
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// `<port>_<YYYYMMDD-HHMMSS>[_n].<ext>`; `_n` only when a file for the same
+/// second already exists.
+pub(crate) fn create_file(
+    dir: &Path,
+    stem: &str,
+    extension: &str,
+    now: u64,
+) -> io::Result<(File, PathBuf)> {
+    let base = format!("{}_{}", stem, file_stamp(now));
+    for n in 0.. {
+        let name = match n {
+            0 => format!("{}.{}", base, extension),
+            n => format!("{}_{}.{}", base, n, extension),
+        };
+        let path = dir.join(name);
+        match File::options().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((file, path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/// Split a capture file stem into the port and its start time.
+fn parse_file_name(stem: &str) -> Option<(String, u64)> {
+    let parts: Vec<&str> = stem.split('_').collect();
+    let index = parts.iter().rposition(|p| parse_file_stamp(p).is_some())?;
+    if index == 0 {
+        return None;
+    }
+    Some((parts[..index].join("_"), parse_file_stamp(parts[index])?))
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian date, and back
+// (Howard Hinnant's algorithms).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
+
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if m > 2 { m - 3 } else { m + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn split_millis(ms: u64) -> (i64, u32, u32, u64, u64, u64, u64) {
+    let secs = ms / 1000;
+    let (y, m, d) = civil_from_days((secs / 86400) as i64);
+    let day_secs = secs % 86400;
+    (
+        y,
+        m,
+        d,
+        day_secs / 3600,
+        day_secs % 3600 / 60,
+        day_secs % 60,
+        ms % 1000,
+    )
+}
+
+/// UTC, e.g. `2026-10-18T12:34:56.789Z`.
+pub fn format_timestamp(ms: u64) -> String {
+    let (y, m, d, hh, mm, ss, millis) = split_millis(ms);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        y, m, d, hh, mm, ss, millis
+    )
+}
+
+fn parse_timestamp(s: &str) -> Option<u64> {
+    let s = s.strip_suffix('Z')?;
+    let (date, time) = s.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|p| p.parse::<u32>().ok());
+    let (y, m, d) = (date.next()??, date.next()??, date.next()??);
+    let (hms, millis) = time.split_once('.')?;
+    let mut hms = hms.splitn(3, ':').map(|p| p.parse::<u64>().ok());
+    let (hh, mm, ss) = (hms.next()??, hms.next()??, hms.next()??);
+    let days = u64::try_from(days_from_civil(y as i64, m, d)).ok()?;
+    Some(((days * 86400 + hh * 3600 + mm * 60 + ss) * 1000) + millis.parse::<u64>().ok()?)
+}
+
+fn file_stamp(ms: u64) -> String {
+    let (y, m, d, hh, mm, ss, _) = split_millis(ms);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", y, m, d, hh, mm, ss)
+}
+
+fn parse_file_stamp(s: &str) -> Option<u64> {
+    let (date, time) = s.split_once('-')?;
+    if date.len() != 8 || time.len() != 6 || !s.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return None;
+    }
+    parse_timestamp(&format!(
+        "{}-{}-{}T{}:{}:{}.000Z",
+        &date[..4],
+        &date[4..6],
+        &date[6..],
+        &time[..2],
+        &time[2..4],
+        &time[4..]
+    ))
+}
+
+fn captures_dir(app: &AppHandle, directory: Option<String>) -> Result<PathBuf, String> {
+    match directory {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => app
+            .path()
+            .app_data_dir()
+            .map(|p| p.join("captures"))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn start_serial_capture(
+    app: AppHandle,
+    port: String,
+    options: Option<CaptureOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let dir = captures_dir(&app, options.directory.clone())?;
+    start_capture(&dir, &port, options).map(|p| p.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn stop_serial_capture(port: String) -> Result<Vec<String>, String> {
+    stop_capture(&port).map(|files| {
+        files
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    })
+}
+
+#[tauri::command]
+pub fn list_serial_captures(
+    app: AppHandle,
+    directory: Option<String>,
+) -> Result<Vec<CaptureFile>, String> {
+    Ok(list_capture_files(&captures_dir(&app, directory)?))
+}
+
+/// `limit` records of a capture from byte `offset`; keep requesting
+/// `next_offset` until it is `None` to read the whole file.
+#[tauri::command]
+pub fn open_serial_capture(
+    path: String,
+    offset: Option<u64>,
+    limit: Option<usize>,
+) -> Result<CapturePage, String> {
+    read_capture_page(
+        Path::new(&path),
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_PAGE),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: u64 = 1792326896789; // 2026-10-18T12:34:56.789Z
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("serial-log-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn formats_and_parses_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(format_timestamp(T0), "2026-10-18T12:34:56.789Z");
+        assert_eq!(parse_timestamp("2026-10-18T12:34:56.789Z"), Some(T0));
+        assert_eq!(file_stamp(T0), "20261018-123456");
+        assert_eq!(
+            parse_file_name("usb-SEGGER_J-Link-if00_20261018-123456_2"),
+            Some(("usb-SEGGER_J-Link-if00".to_string(), T0 - 789))
+        );
+    }
+
+    #[test]
+    fn stamps_each_line_when_it_starts() {
+        let dir = temp_dir("line");
+        let mut capture =
+            Capture::start(&dir, "/dev/ttyACM0", CaptureOptions::default(), T0).unwrap();
+        capture.write(b"booting\r\nuart:~$ ", T0).unwrap();
+        capture.write(b"kernel threads\r\n", T0 + 250).unwrap();
+        capture.write(b"partial", T0 + 1000).unwrap();
+        let files = capture.finish(T0 + 2000).unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("ttyACM0_20261018-123456.log"));
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            "[2026-10-18T12:34:56.789Z] booting\n\
+             [2026-10-18T12:34:56.789Z] uart:~$ kernel threads\n\
+             [2026-10-18T12:34:57.789Z] partial\n"
+        );
+
+        let records = read_capture(&files[0]).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].port, "ttyACM0");
+        assert_eq!(records[2].timestamp, T0 + 1000);
+        assert_eq!(records[2].data, b"partial\n");
+
+        let mut offset = 0;
+        let mut paged = Vec::new();
+        loop {
+            let page = read_capture_page(&files[0], offset, 2).unwrap();
+            paged.extend(page.records);
+            match page.next_offset {
+                Some(next) => offset = next,
+                None => break,
+            }
+        }
+        assert_eq!(paged.len(), 3);
+        assert_eq!(paged[2].data, records[2].data);
+        assert_eq!(paged[2].timestamp, records[2].timestamp);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn raw_captures_keep_bytes_and_timing() {
+        let dir = temp_dir("raw");
+        let options = CaptureOptions {
+            mode: CaptureMode::Raw,
+            ..Default::default()
+        };
+        let mut capture = Capture::start(&dir, "COM3", options, T0).unwrap();
+        capture.write(&[0xA5, 0x00, 0x0A, 0xFF], T0).unwrap();
+        capture.write(b"\r\n", T0 + 5).unwrap();
+        let files = capture.finish(T0 + 10).unwrap();
+
+        let records = read_capture(&files[0]).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].data, [0xA5, 0x00, 0x0A, 0xFF]);
+        assert_eq!(records[1].timestamp, T0 + 5);
+
+        let first = read_capture_page(&files[0], 0, 1).unwrap();
+        assert_eq!(first.records.len(), 1);
+        assert_eq!(first.records[0].data, records[0].data);
+        // Header, then a 12-byte record header and 4 bytes of data
+        assert_eq!(first.offset, RAW_MAGIC.len() as u64);
+        let next = RAW_MAGIC.len() as u64 + 16;
+        assert_eq!(first.next_offset, Some(next));
+        let last = read_capture_page(&files[0], next, 10).unwrap();
+        assert_eq!((last.offset, last.next_offset), (next, None));
+        assert_eq!(last.records.len(), 1);
+        assert_eq!(last.records[0].timestamp, T0 + 5);
+        // A full last page still ends the file
+        assert_eq!(
+            read_capture_page(&files[0], next, 1).unwrap().next_offset,
+            None
+        );
+        assert!(read_capture_page(&files[0], 1000, 10)
+            .unwrap()
+            .records
+            .is_empty());
+
+        let listed = list_capture_files(&dir);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].port, "COM3");
+        assert_eq!(listed[0].mode, CaptureMode::Raw);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lists_newest_capture_first_across_ports() {
+        let dir = temp_dir("list");
+        fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "COM3_20261018-123456.log",
+            "ACM0_20261018-123556.bin",
+            "ttyUSB0_20261018-123456.log",
+            "ttyUSB0_20261018-123456_1.log",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = list_capture_files(&dir)
+            .iter()
+            .map(|f| {
+                Path::new(&f.path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(
+            names,
+            [
+                "ACM0_20261018-123556.bin",
+                "ttyUSB0_20261018-123456_1.log",
+                "ttyUSB0_20261018-123456.log",
+                "COM3_20261018-123456.log",
+            ]
+        );
+    }
+
+    #[test]
+    fn rotates_by_size_and_time_and_prunes() {
+        let dir = temp_dir("rotate");
+        let options = CaptureOptions {
+            max_file_bytes: Some(38),
+            rotate_after_secs: Some(60),
+            max_files: Some(2),
+            ..Default::default()
+        };
+        let mut capture = Capture::start(&dir, "ttyUSB0", options, T0).unwrap();
+        // Each line is 38 bytes, so the second one starts a new file
+        capture.write(b"aaaaaaaaaa\n", T0).unwrap();
+        capture.write(b"bbbbbbbbbb\n", T0).unwrap();
+        // Small, but a minute later
+        capture.write(b"c\n", T0 + 60_000).unwrap();
+        let files = capture.finish(T0 + 60_000).unwrap();
+
+        // The first of the three files was pruned
+        let listed = list_capture_files(&dir);
+        assert_eq!(listed.len(), 2);
+        assert!(listed[0].path.ends_with("ttyUSB0_20261018-123556.log"));
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("ttyUSB0_20261018-123456_1.log"));
+        assert!(files[1].ends_with("ttyUSB0_20261018-123556.log"));
+        assert_eq!(
+            fs::read_to_string(&files[1]).unwrap(),
+            "[2026-10-18T12:35:56.789Z] c\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
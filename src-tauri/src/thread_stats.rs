@@ -0,0 +1,498 @@
+use crate::frame_codec::DecodedFrame;
+use crate::plot_buffer::{self, PlotPoint};
+use crate::serial_comm;
+use crate::serial_log;
+use crate::zephyr_shell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const SHELL_TIMEOUT: Duration = Duration::from_secs(3);
+/// Schema name of frames carrying one thread's stats
+pub const STATS_FRAME: &str = "thread_stats";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ThreadSample {
+    pub name: String,
+    /// Thread (or IRQ stack) address as printed by the shell
+    pub address: Option<String>,
+    pub state: Option<String>,
+    pub priority: Option<i32>,
+    /// Share of CPU time since the previous sample, or since boot for the
+    /// first one
+    pub cpu_percent: Option<f64>,
+    pub execution_cycles: Option<u64>,
+    pub stack_size: Option<u64>,
+    /// High-water mark
+    pub stack_used: Option<u64>,
+    pub stack_percent: Option<f64>,
+}
+
+impl ThreadSample {
+    fn key(&self) -> String {
+        self.address.clone().unwrap_or_else(|| self.name.clone())
+    }
+
+    fn set_stack(&mut self, size: u64, used: u64) {
+        self.stack_size = Some(size);
+        self.stack_used = Some(used);
+        self.stack_percent = (size > 0).then(|| used as f64 * 100.0 / size as f64);
+    }
+}
+
+/// The number right after `key`, e.g. `unused 1224` for `"unused"`.
+fn number_after(text: &str, key: &str) -> Option<u64> {
+    let rest = text[text.find(key)? + key.len()..].trim_start();
+    let end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+/// `usage 824 / 2048` -> (2048, 824)
+fn stack_usage(line: &str) -> Option<(u64, u64)> {
+    let (used, size) = line[line.find("usage")? + "usage".len()..].split_once('/')?;
+    Some((number_after(size, "")?, used.trim().parse().ok()?))
+}
+
+/// Parse `kernel threads`:
+///
+/// ```text
+/// *0x20000b08 shell_uart
+///         options: 0x0, priority: 14 timeout: 0
+///         state: queued, entry: 0x1d25
+///         Total execution cycles: 1850032 (5 %)
+///         stack size 2048, unused 1224, usage 824 / 2048 (40 %)
+/// ```
+pub fn parse_kernel_threads(text: &str) -> Vec<ThreadSample> {
+    let mut threads: Vec<ThreadSample> = Vec::new();
+    for line in text.lines() {
+        let header = line.trim_start_matches(['*', ' ']);
+        if line.starts_with(['*', ' ']) && header.starts_with("0x") {
+            let (address, name) = header
+                .split_once(char::is_whitespace)
+                .unwrap_or((header, ""));
+            threads.push(ThreadSample {
+                name: name.trim().to_string(),
+                address: Some(address.to_string()),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(thread) = threads.last_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("options:") {
+            thread.priority = rest
+                .split_once("priority:")
+                .and_then(|(_, p)| p.split_whitespace().next())
+                .and_then(|p| p.parse().ok());
+        } else if let Some(rest) = line.strip_prefix("state:") {
+            let state = rest.split(", entry").next().unwrap_or(rest).trim();
+            thread.state = (!state.is_empty()).then(|| state.to_string());
+        } else if line.starts_with("Total execution cycles:") {
+            thread.execution_cycles = number_after(line, "cycles:");
+            thread.cpu_percent = number_after(line, "(").map(|p| p as f64);
+        } else if line.starts_with("stack size") {
+            if let Some((size, used)) = stack_usage(line) {
+                thread.set_stack(size, used);
+            }
+        }
+    }
+    threads
+}
+
+/// Parse `kernel stacks`, which also lists the interrupt stacks:
+///
+/// ```text
+/// 0x20000b08 shell_uart  (real size 2048):  unused 1224  usage 824 / 2048 (40 %)
+/// 0x20001c40 IRQ 00      (real size 2048):  unused 1800  usage 248 / 2048 (12 %)
+/// ```
+pub fn parse_kernel_stacks(text: &str) -> Vec<ThreadSample> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (address, rest) = line.split_once(char::is_whitespace)?;
+            let (name, _) = rest.split_once("(real size")?;
+            let (size, used) = stack_usage(line)?;
+            let mut sample = ThreadSample {
+                name: name.trim().to_string(),
+                address: address.starts_with("0x").then(|| address.to_string()),
+                ..Default::default()
+            };
+            sample.set_stack(size, used);
+            Some(sample)
+        })
+        .collect()
+}
+
+/// Fill in stack usage from `kernel stacks` and add the stacks (IRQ) that
+/// `kernel threads` does not list.
+pub fn merge_stacks(threads: &mut Vec<ThreadSample>, stacks: Vec<ThreadSample>) {
+    for stack in stacks {
+        match threads.iter_mut().find(|t| t.key() == stack.key()) {
+            Some(thread) if thread.stack_size.is_none() => {
+                if let (Some(size), Some(used)) = (stack.stack_size, stack.stack_used) {
+                    thread.set_stack(size, used);
+                }
+            }
+            Some(_) => {}
+            None => threads.push(stack),
+        }
+    }
+}
+
+/// One thread's stats from a `thread_stats` frame: `thread` (name bytes or
+/// numeric id), `cpu_percent`, `stack_used` and `stack_size`.
+pub fn sample_from_frame(frame: &DecodedFrame) -> Option<ThreadSample> {
+    if frame.name.as_deref() != Some(STATS_FRAME) {
+        return None;
+    }
+    let field = |name: &str| frame.fields.get(name).and_then(Value::as_f64);
+    let name = match frame.fields.get("thread")? {
+        // Bytes fields are decoded as hex
+        Value::String(hex) => {
+            let bytes = (0..hex.len() / 2)
+                .filter_map(|i| u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok())
+                .take_while(|&b| b != 0)
+                .collect::<Vec<_>>();
+            String::from_utf8_lossy(&bytes).to_string()
+        }
+        id => format!("thread {}", id),
+    };
+    let mut sample = ThreadSample {
+        name,
+        cpu_percent: field("cpu_percent"),
+        ..Default::default()
+    };
+    if let (Some(size), Some(used)) = (field("stack_size"), field("stack_used")) {
+        sample.set_stack(size as u64, used as u64);
+    }
+    Some(sample)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StackWarning {
+    pub thread: String,
+    pub stack_percent: f64,
+    pub threshold: f64,
+}
+
+/// Payload of the `thread-stats` event.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThreadStats {
+    pub port: String,
+    pub timestamp: u64, // Unix time in milliseconds
+    pub threads: Vec<ThreadSample>,
+    /// Stacks that crossed the threshold with this sample
+    pub warnings: Vec<StackWarning>,
+    pub error: Option<String>,
+}
+
+/// Turns successive samples of a port into CPU load and threshold warnings.
+pub struct StatsTracker {
+    threshold: f64,
+    cycles: BTreeMap<String, u64>,
+    warned: BTreeSet<String>,
+}
+
+impl StatsTracker {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            cycles: BTreeMap::new(),
+            warned: BTreeSet::new(),
+        }
+    }
+
+    pub fn update(&mut self, threads: &mut [ThreadSample]) -> Vec<StackWarning> {
+        // Cycles are cumulative; the load is each thread's share of the
+        // cycles spent by all of them (idle included) since the last poll
+        let deltas: Vec<Option<u64>> = threads
+            .iter()
+            .map(|t| {
+                Some(
+                    t.execution_cycles?
+                        .saturating_sub(*self.cycles.get(&t.key())?),
+                )
+            })
+            .collect();
+        let total: u64 = deltas.iter().flatten().sum();
+        for (thread, delta) in threads.iter_mut().zip(&deltas) {
+            if let (Some(delta), true) = (delta, total > 0) {
+                thread.cpu_percent = Some(*delta as f64 * 100.0 / total as f64);
+            }
+            if let Some(cycles) = thread.execution_cycles {
+                self.cycles.insert(thread.key(), cycles);
+            }
+        }
+
+        let mut warnings = Vec::new();
+        for thread in threads.iter() {
+            let Some(percent) = thread.stack_percent else {
+                continue;
+            };
+            if percent < self.threshold {
+                self.warned.remove(&thread.key());
+            } else if self.warned.insert(thread.key()) {
+                warnings.push(StackWarning {
+                    thread: thread.name.clone(),
+                    stack_percent: percent,
+                    threshold: self.threshold,
+                });
+            }
+        }
+        warnings
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsSource {
+    /// Poll `kernel threads` / `kernel stacks` on the port's shell
+    #[default]
+    Shell,
+    /// `thread_stats` frames from the port's frame decoder
+    Frame,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct StatsOptions {
+    pub source: StatsSource,
+    pub interval_ms: u64,
+    pub stack_warn_percent: f64,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            source: StatsSource::Shell,
+            interval_ms: 2000,
+            stack_warn_percent: 80.0,
+        }
+    }
+}
+
+struct Monitor {
+    app: AppHandle,
+    tracker: StatsTracker,
+    stop: Arc<AtomicBool>,
+    /// Tells a stopped monitor's poller apart from the one of a monitor
+    /// restarted on the same port
+    generation: u64,
+}
+
+static MONITORS: Mutex<BTreeMap<String, Monitor>> = Mutex::new(BTreeMap::new());
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Track a sample, buffer it as `<port>/threads.<name>.cpu|stack` and
+/// emit it. A poller passes the `generation` it was started for, so a
+/// sample it took before being stopped is dropped; frames have none.
+fn publish(
+    port: &str,
+    generation: Option<u64>,
+    mut threads: Vec<ThreadSample>,
+    error: Option<String>,
+) {
+    let mut monitors = MONITORS.lock().unwrap();
+    let Some(monitor) = monitors.get_mut(port) else {
+        return;
+    };
+    if generation.is_some_and(|g| g != monitor.generation) {
+        return;
+    }
+    let timestamp = serial_log::now_millis();
+    let warnings = monitor.tracker.update(&mut threads);
+
+    plot_buffer::record(threads.iter().flat_map(|thread| {
+        let channel = |series| format!("{}/threads.{}.{}", port, thread.name, series);
+        let point = |v| PlotPoint { t: timestamp, v };
+        [
+            thread.cpu_percent.map(|v| (channel("cpu"), point(v))),
+            thread.stack_percent.map(|v| (channel("stack"), point(v))),
+        ]
+        .into_iter()
+        .flatten()
+    }));
+
+    let _ = monitor.app.emit(
+        "thread-stats",
+        ThreadStats {
+            port: port.to_string(),
+            timestamp,
+            threads,
+            warnings,
+            error,
+        },
+    );
+}
+
+/// Handle a `thread_stats` frame if stats are monitored on its port.
+pub(crate) fn record_frame(frame: &DecodedFrame) {
+    if let Some(sample) = sample_from_frame(frame) {
+        publish(&frame.port, None, vec![sample], None);
+    }
+}
+
+fn poll_shell(port: &str) -> Result<Vec<ThreadSample>, String> {
+    let mut threads =
+        parse_kernel_threads(&zephyr_shell::run(port, "kernel threads", SHELL_TIMEOUT)?);
+    // Needs CONFIG_KERNEL_SHELL's stack support; optional
+    if let Ok(stacks) = zephyr_shell::run(port, "kernel stacks", SHELL_TIMEOUT) {
+        merge_stacks(&mut threads, parse_kernel_stacks(&stacks));
+    }
+    Ok(threads)
+}
+
+/// Start sampling thread stats of a port into `thread-stats` events and
+/// the plot buffers.
+#[tauri::command]
+pub fn start_thread_stats(
+    app: AppHandle,
+    port: String,
+    options: Option<StatsOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let stop = Arc::new(AtomicBool::new(false));
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut monitors = MONITORS.lock().unwrap();
+        if monitors.contains_key(&port) {
+            return Err(format!("Thread stats are already running on {}", port));
+        }
+        monitors.insert(
+            port.clone(),
+            Monitor {
+                app,
+                tracker: StatsTracker::new(options.stack_warn_percent),
+                stop: stop.clone(),
+                generation,
+            },
+        );
+    }
+
+    if options.source == StatsSource::Shell {
+        let interval = Duration::from_millis(options.interval_ms);
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match poll_shell(&port) {
+                    Ok(threads) => publish(&port, Some(generation), threads, None),
+                    Err(e) => publish(&port, Some(generation), Vec::new(), Some(e)),
+                }
+                serial_comm::wait_unless_stopped(&stop, interval);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_thread_stats(port: String) -> Result<(), String> {
+    MONITORS
+        .lock()
+        .unwrap()
+        .remove(&port)
+        .map(|monitor| monitor.stop.store(true, Ordering::Relaxed))
+        .ok_or_else(|| format!("No thread stats running on {}", port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Map;
+
+    const THREADS: &str = "Scheduler: 277 since last call\r\n\
+        Threads:\r\n\
+        *0x20000b08 shell_uart\r\n\
+        \toptions: 0x0, priority: 14 timeout: 0\r\n\
+        \tstate: queued, entry: 0x1d25\r\n\
+        \tTotal execution cycles: 1000 (10 %)\r\n\
+        \tstack size 2048, unused 1224, usage 824 / 2048 (40 %)\r\n\
+        \r\n \
+        0x20000580 idle\r\n\
+        \toptions: 0x1, priority: 15 timeout: 0\r\n\
+        \tstate: , entry: 0x8c41\r\n\
+        \tTotal execution cycles: 9000 (90 %)\r\n\
+        \tstack size 320, unused 280, usage 40 / 320 (12 %)\r\n\
+        \r\n";
+
+    const STACKS: &str =
+        "0x20000b08 shell_uart       (real size 2048):\tunused 1224\tusage  824 / 2048 (40 %)\r\n\
+        0x20000580 idle             (real size  320):\tunused  280\tusage   40 /  320 (12 %)\r\n\
+        0x20001c40 IRQ 00           (real size 2048):\tunused 1800\tusage  248 / 2048 (12 %)\r\n";
+
+    #[test]
+    fn parses_kernel_threads_and_stacks() {
+        let mut threads = parse_kernel_threads(THREADS);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].name, "shell_uart");
+        assert_eq!(threads[0].address.as_deref(), Some("0x20000b08"));
+        assert_eq!(threads[0].priority, Some(14));
+        assert_eq!(threads[0].state.as_deref(), Some("queued"));
+        assert_eq!(threads[0].execution_cycles, Some(1000));
+        assert_eq!(threads[0].cpu_percent, Some(10.0));
+        assert_eq!(threads[0].stack_used, Some(824));
+        assert_eq!(threads[1].name, "idle");
+        assert_eq!(threads[1].priority, Some(15));
+
+        merge_stacks(&mut threads, parse_kernel_stacks(STACKS));
+        assert_eq!(threads.len(), 3);
+        assert_eq!(threads[2].name, "IRQ 00");
+        assert_eq!(threads[2].stack_size, Some(2048));
+        assert_eq!(threads[2].stack_used, Some(248));
+    }
+
+    #[test]
+    fn computes_load_between_polls_and_warns_once() {
+        let mut tracker = StatsTracker::new(50.0);
+        let mut first = parse_kernel_threads(THREADS);
+        assert!(tracker.update(&mut first).is_empty());
+        assert_eq!(first[0].cpu_percent, Some(10.0)); // since boot
+
+        let mut second = parse_kernel_threads(
+            &THREADS
+                .replace("cycles: 1000", "cycles: 1600")
+                .replace("cycles: 9000", "cycles: 9400")
+                .replace("unused 1224, usage 824", "unused 824, usage 1224"),
+        );
+        let warnings = tracker.update(&mut second);
+        assert_eq!(second[0].cpu_percent, Some(60.0));
+        assert_eq!(second[1].cpu_percent, Some(40.0));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].thread, "shell_uart");
+
+        // Still above the threshold: no repeated warning
+        let mut third = second.clone();
+        assert!(tracker.update(&mut third).is_empty());
+    }
+
+    #[test]
+    fn reads_stats_frames() {
+        let mut fields = Map::new();
+        fields.insert("thread".into(), "6d61696e0000".into()); // "main\0\0"
+        fields.insert("cpu_percent".into(), 12.5.into());
+        fields.insert("stack_used".into(), 300.into());
+        fields.insert("stack_size".into(), 1024.into());
+        let frame = DecodedFrame {
+            port: "COM3".to_string(),
+            cmd_id: 0x0301,
+            seq: 0,
+            name: Some(STATS_FRAME.to_string()),
+            fields,
+            data: Vec::new(),
+            timestamp: 0,
+            error: None,
+        };
+        let sample = sample_from_frame(&frame).unwrap();
+        assert_eq!(sample.name, "main");
+        assert_eq!(sample.cpu_percent, Some(12.5));
+        assert_eq!(sample.stack_percent, Some(300.0 * 100.0 / 1024.0));
+    }
+}
@@ -0,0 +1,686 @@
+use crate::config_manager::{self, UserConfig};
+use crate::sdk_manager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::AppHandle;
+
+/// A Zephyr installation that projects can be bound to: the Zephyr source
+/// tree, the Python venv holding west, and the SDK used to compile.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ToolchainProfile {
+    pub id: String,
+    pub name: String,
+    pub zephyr_base: String,
+    #[serde(default)]
+    pub venv_path: Option<String>,
+    #[serde(default)]
+    pub sdk_path: Option<String>,
+    #[serde(default)]
+    pub zephyr_version: Option<String>,
+    #[serde(default)]
+    pub sdk_version: Option<String>,
+}
+
+impl ToolchainProfile {
+    /// Directory holding the venv executables (`bin` or `Scripts`).
+    pub fn venv_bin(&self) -> Option<PathBuf> {
+        self.venv_path
+            .as_ref()
+            .map(|venv| venv_bin_dir(Path::new(venv)))
+    }
+
+    /// The venv's `west` if it exists, otherwise `west` from PATH.
+    pub fn west_program(&self) -> String {
+        self.venv_bin()
+            .map(|bin| bin.join(exe_name("west")))
+            .filter(|p| p.exists())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| "west".to_string())
+    }
+
+    /// The venv's python if it exists, otherwise the system python.
+    pub fn python_program(&self) -> String {
+        self.venv_bin()
+            .map(|bin| bin.join(exe_name("python")))
+            .filter(|p| p.exists())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| default_python().to_string())
+    }
+
+    /// Make a subprocess see this toolchain: activate the venv and point
+    /// ZEPHYR_BASE / ZEPHYR_SDK_INSTALL_DIR at this profile.
+    pub fn apply_env(&self, command: &mut Command) {
+        if let Some(bin) = self.venv_bin() {
+            let path_var = std::env::var_os("PATH").unwrap_or_default();
+            let mut paths = vec![bin];
+            paths.extend(std::env::split_paths(&path_var));
+            if let Ok(joined) = std::env::join_paths(paths) {
+                command.env("PATH", joined);
+            }
+        }
+        if let Some(venv) = &self.venv_path {
+            command.env("VIRTUAL_ENV", venv);
+        }
+        if !self.zephyr_base.is_empty() {
+            command.env("ZEPHYR_BASE", &self.zephyr_base);
+        }
+        if let Some(sdk) = &self.sdk_path {
+            command.env("ZEPHYR_SDK_INSTALL_DIR", sdk);
+        }
+    }
+}
+
+pub fn venv_bin_dir(venv: &Path) -> PathBuf {
+    if cfg!(windows) {
+        venv.join("Scripts")
+    } else {
+        venv.join("bin")
+    }
+}
+
+pub fn exe_name(name: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn default_python() -> &'static str {
+    if cfg!(windows) {
+        "python"
+    } else {
+        "python3"
+    }
+}
+
+/// Read `<zephyr_base>/VERSION` into a `major.minor.patch[-extra]` string.
+pub fn read_zephyr_version(zephyr_base: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(zephyr_base.join("VERSION")).ok()?;
+    parse_zephyr_version(&content)
+}
+
+fn parse_zephyr_version(content: &str) -> Option<String> {
+    let mut major = None;
+    let mut minor = None;
+    let mut patch = None;
+    let mut extra = String::new();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "VERSION_MAJOR" => major = Some(value.to_string()),
+            "VERSION_MINOR" => minor = Some(value.to_string()),
+            "PATCHLEVEL" => patch = Some(value.to_string()),
+            "EXTRAVERSION" => extra = value.to_string(),
+            _ => {}
+        }
+    }
+
+    let mut version = format!(
+        "{}.{}.{}",
+        major?,
+        minor?,
+        patch.unwrap_or_else(|| "0".into())
+    );
+    if !extra.is_empty() {
+        version.push('-');
+        version.push_str(&extra);
+    }
+    Some(version)
+}
+
+/// Read the `sdk_version` file shipped at the root of every Zephyr SDK.
+pub fn read_sdk_version(sdk_path: &Path) -> Option<String> {
+    std::fs::read_to_string(sdk_path.join("sdk_version"))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Build a profile for a west workspace (the directory containing `.west`).
+fn profile_from_workspace(workspace: &Path, sdk_path: Option<String>) -> Option<ToolchainProfile> {
+    let zephyr_base = workspace.join("zephyr");
+    if !zephyr_base.join("VERSION").exists() {
+        return None;
+    }
+
+    let venv = workspace.join(".venv");
+    let venv_path = venv.is_dir().then(|| venv.to_string_lossy().to_string());
+    let zephyr_version = read_zephyr_version(&zephyr_base);
+    let sdk_version = sdk_path
+        .as_ref()
+        .and_then(|s| read_sdk_version(Path::new(s)));
+
+    let dir_name = workspace
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "zephyr".to_string());
+    let name = match &zephyr_version {
+        Some(v) => format!("{} (Zephyr {})", dir_name, v),
+        None => dir_name.clone(),
+    };
+
+    Some(ToolchainProfile {
+        id: slugify(&dir_name),
+        name,
+        zephyr_base: zephyr_base.to_string_lossy().to_string(),
+        venv_path,
+        sdk_path,
+        zephyr_version,
+        sdk_version,
+    })
+}
+
+fn slugify(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() {
+        "toolchain".to_string()
+    } else {
+        slug
+    }
+}
+
+fn unique_id(base: &str, existing: &[ToolchainProfile]) -> String {
+    let taken = |id: &str| existing.iter().any(|p| p.id == id);
+    if !taken(base) {
+        return base.to_string();
+    }
+    let mut n = 2;
+    loop {
+        let candidate = format!("{}-{}", base, n);
+        if !taken(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// West workspaces found in the home directory (up to two levels deep, so
+/// both `~/zephyrproject` and `~/ncs/v2.6.0` are covered) and next to the
+/// legacy configured Zephyr base.
+fn find_workspaces(config: &UserConfig) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home);
+    }
+    if let Some(base) = &config.zephyr_base {
+        if let Some(parent) = Path::new(base).parent().and_then(|p| p.parent()) {
+            roots.push(parent.to_path_buf());
+        }
+    }
+
+    let mut found = Vec::new();
+    for root in roots {
+        scan_dir(&root, 2, &mut found);
+    }
+    found
+}
+
+fn scan_dir(dir: &Path, depth: u32, found: &mut Vec<PathBuf>) {
+    if dir.join(".west").is_dir() && dir.join("zephyr").join("VERSION").exists() {
+        if !found.iter().any(|p| p == dir) {
+            found.push(dir.to_path_buf());
+        }
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_dir() && !hidden {
+            scan_dir(&path, depth - 1, found);
+        }
+    }
+}
+
+/// `path` with `.`/`..` resolved and trailing separators dropped, lowercased
+/// where file names are case-insensitive, for comparing project paths.
+fn path_key(path: &str) -> PathBuf {
+    let path = if cfg!(any(windows, target_os = "macos")) {
+        path.to_lowercase()
+    } else {
+        path.to_string()
+    };
+    let mut key = PathBuf::new();
+    for component in Path::new(&path).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                key.pop();
+            }
+            other => key.push(other),
+        }
+    }
+    key
+}
+
+/// The toolchain a project should run with: its bound profile, otherwise a
+/// profile synthesised from the global `zephyr_base` / `venv_path` settings.
+pub fn resolve_for_project(
+    config: &UserConfig,
+    project_path: Option<&str>,
+) -> Option<ToolchainProfile> {
+    if let Some(path) = project_path {
+        let path = path_key(path);
+        // The deepest bound project containing `path`, so commands run from
+        // `app/` or `build/` use the project's toolchain
+        let bound = config
+            .project_toolchains
+            .iter()
+            .filter_map(|(project, id)| {
+                let project = path_key(project);
+                let profile = config.toolchains.iter().find(|p| &p.id == id)?;
+                path.starts_with(&project)
+                    .then(|| (project.components().count(), profile))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, profile)| profile);
+        if let Some(profile) = bound {
+            return Some(with_legacy_venv(config, profile));
+        }
+    }
+
+    let default = config
+        .default_toolchain
+        .as_ref()
+        .and_then(|id| config.toolchains.iter().find(|p| &p.id == id));
+    if let Some(profile) = default {
+        return Some(with_legacy_venv(config, profile));
+    }
+
+    if config.zephyr_base.is_none() && config.venv_path.is_none() {
+        return None;
+    }
+    let zephyr_base = config.zephyr_base.clone().unwrap_or_default();
+    Some(ToolchainProfile {
+        id: "legacy".to_string(),
+        name: "Default".to_string(),
+        zephyr_version: read_zephyr_version(Path::new(&zephyr_base)),
+        zephyr_base,
+        venv_path: config.venv_path.clone(),
+        sdk_path: None,
+        sdk_version: None,
+    })
+}
+
+/// A profile without a venv of its own uses the one set with `set_venv_path`.
+fn with_legacy_venv(config: &UserConfig, profile: &ToolchainProfile) -> ToolchainProfile {
+    let mut profile = profile.clone();
+    if profile.venv_path.is_none() {
+        profile.venv_path = config.venv_path.clone();
+    }
+    profile
+}
+
+/// Profile to make the default when none is chosen: the first one, unless
+/// Zephyr or venv paths are set in the settings, which then stay in effect.
+fn fallback_default(config: &UserConfig) -> Option<String> {
+    if config.zephyr_base.is_some() || config.venv_path.is_some() {
+        return None;
+    }
+    config.toolchains.first().map(|p| p.id.clone())
+}
+
+/// Convenience wrapper around [`resolve_for_project`] that loads the config.
+pub fn resolve_for_app(app: &AppHandle, project_path: Option<&str>) -> Option<ToolchainProfile> {
+    let config = config_manager::get_config(app.clone()).unwrap_or_default();
+    resolve_for_project(&config, project_path)
+}
+
+/// Add a freshly installed workspace to the registry (used by `install_zephyr`).
+pub fn register_workspace(
+    app: &AppHandle,
+    workspace: &Path,
+    sdk_path: Option<String>,
+) -> Result<Option<ToolchainProfile>, String> {
+    let Some(profile) = profile_from_workspace(workspace, sdk_path) else {
+        return Ok(None);
+    };
+    let mut config = config_manager::get_config(app.clone())?;
+    let profile = merge_profile(&mut config.toolchains, profile);
+    config_manager::save_config(app.clone(), config)?;
+    Ok(Some(profile))
+}
+
+/// Insert a discovered profile, refreshing the stored one if the same Zephyr
+/// base is already registered. Returns the profile as stored.
+fn merge_profile(
+    profiles: &mut Vec<ToolchainProfile>,
+    mut discovered: ToolchainProfile,
+) -> ToolchainProfile {
+    if let Some(existing) = profiles
+        .iter_mut()
+        .find(|p| p.zephyr_base == discovered.zephyr_base)
+    {
+        existing.zephyr_version = discovered.zephyr_version;
+        if existing.venv_path.is_none() {
+            existing.venv_path = discovered.venv_path;
+        }
+        if discovered.sdk_path.is_some() && existing.sdk_path.is_none() {
+            existing.sdk_path = discovered.sdk_path;
+            existing.sdk_version = discovered.sdk_version;
+        }
+        return existing.clone();
+    }
+
+    discovered.id = unique_id(&discovered.id, profiles);
+    profiles.push(discovered.clone());
+    discovered
+}
+
+#[tauri::command]
+pub fn list_toolchains(app: AppHandle) -> Result<Vec<ToolchainProfile>, String> {
+    Ok(config_manager::get_config(app)?.toolchains)
+}
+
+#[tauri::command]
+pub fn scan_toolchains(app: AppHandle) -> Result<Vec<ToolchainProfile>, String> {
+    let mut config = config_manager::get_config(app.clone())?;
+    let sdk_path = sdk_manager::discover_sdks(&config)
+        .into_iter()
+        .find(|sdk| sdk.valid)
+        .map(|sdk| sdk.path);
+
+    for workspace in find_workspaces(&config) {
+        if let Some(profile) = profile_from_workspace(&workspace, sdk_path.clone()) {
+            merge_profile(&mut config.toolchains, profile);
+        }
+    }
+
+    if config.default_toolchain.is_none() {
+        config.default_toolchain = fallback_default(&config);
+    }
+
+    let toolchains = config.toolchains.clone();
+    config_manager::save_config(app, config)?;
+    Ok(toolchains)
+}
+
+#[tauri::command]
+pub fn save_toolchain(
+    app: AppHandle,
+    profile: ToolchainProfile,
+) -> Result<ToolchainProfile, String> {
+    if !Path::new(&profile.zephyr_base).join("VERSION").exists() {
+        return Err(format!(
+            "{} is not a Zephyr source tree",
+            profile.zephyr_base
+        ));
+    }
+
+    let mut config = config_manager::get_config(app.clone())?;
+    let mut profile = profile;
+    profile.zephyr_version = read_zephyr_version(Path::new(&profile.zephyr_base));
+    profile.sdk_version = profile
+        .sdk_path
+        .as_ref()
+        .and_then(|s| read_sdk_version(Path::new(s)));
+
+    if let Some(existing) = config.toolchains.iter_mut().find(|p| p.id == profile.id) {
+        *existing = profile.clone();
+    } else {
+        let base = if profile.id.is_empty() {
+            slugify(&profile.name)
+        } else {
+            profile.id.clone()
+        };
+        profile.id = unique_id(&base, &config.toolchains);
+        config.toolchains.push(profile.clone());
+    }
+
+    config_manager::save_config(app, config)?;
+    Ok(profile)
+}
+
+#[tauri::command]
+pub fn remove_toolchain(app: AppHandle, id: String) -> Result<(), String> {
+    let mut config = config_manager::get_config(app.clone())?;
+    config.toolchains.retain(|p| p.id != id);
+    config.project_toolchains.retain(|_, bound| bound != &id);
+    if config.default_toolchain.as_deref() == Some(id.as_str()) {
+        config.default_toolchain = fallback_default(&config);
+    }
+    config_manager::save_config(app, config)
+}
+
+#[tauri::command]
+pub fn set_default_toolchain(app: AppHandle, id: String) -> Result<(), String> {
+    let mut config = config_manager::get_config(app.clone())?;
+    if !config.toolchains.iter().any(|p| p.id == id) {
+        return Err(format!("Unknown toolchain: {}", id));
+    }
+    config.default_toolchain = Some(id);
+    config_manager::save_config(app, config)
+}
+
+#[tauri::command]
+pub fn bind_project_toolchain(
+    app: AppHandle,
+    project_path: String,
+    toolchain_id: Option<String>,
+) -> Result<(), String> {
+    let mut config = config_manager::get_config(app.clone())?;
+    match toolchain_id {
+        Some(id) => {
+            if !config.toolchains.iter().any(|p| p.id == id) {
+                return Err(format!("Unknown toolchain: {}", id));
+            }
+            config.project_toolchains.insert(project_path, id);
+        }
+        None => {
+            config.project_toolchains.remove(&project_path);
+        }
+    }
+    config_manager::save_config(app, config)
+}
+
+#[tauri::command]
+pub fn get_project_toolchain(
+    app: AppHandle,
+    project_path: String,
+) -> Result<Option<ToolchainProfile>, String> {
+    Ok(resolve_for_app(&app, Some(&project_path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(id: &str, zephyr_base: &str) -> ToolchainProfile {
+        ToolchainProfile {
+            id: id.to_string(),
+            name: id.to_string(),
+            zephyr_base: zephyr_base.to_string(),
+            venv_path: None,
+            sdk_path: None,
+            zephyr_version: None,
+            sdk_version: None,
+        }
+    }
+
+    fn config() -> UserConfig {
+        UserConfig {
+            toolchains: vec![
+                profile("zephyrproject", "/ws/zephyrproject/zephyr"),
+                profile("ncs", "/ws/ncs/zephyr"),
+            ],
+            project_toolchains: [("/projects/blinky".to_string(), "ncs".to_string())].into(),
+            default_toolchain: Some("zephyrproject".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn resolved_id(config: &UserConfig, project: Option<&str>) -> Option<String> {
+        resolve_for_project(config, project).map(|p| p.id)
+    }
+
+    #[test]
+    fn parses_zephyr_version_file() {
+        let content = "VERSION_MAJOR = 3\nVERSION_MINOR = 7\nPATCHLEVEL = 0\n\
+                       VERSION_TWEAK = 0\nEXTRAVERSION =\n";
+        assert_eq!(parse_zephyr_version(content).as_deref(), Some("3.7.0"));
+        assert_eq!(
+            parse_zephyr_version("VERSION_MAJOR = 4\nVERSION_MINOR = 0\nEXTRAVERSION = rc1\n")
+                .as_deref(),
+            Some("4.0.0-rc1")
+        );
+        assert_eq!(parse_zephyr_version("VERSION_MAJOR = 3\n"), None);
+    }
+
+    #[test]
+    fn resolves_bound_then_default_toolchain() {
+        let config = config();
+        assert_eq!(
+            resolved_id(&config, Some("/projects/blinky")).as_deref(),
+            Some("ncs")
+        );
+        assert_eq!(
+            resolved_id(&config, Some("/projects/other")).as_deref(),
+            Some("zephyrproject")
+        );
+        assert_eq!(resolved_id(&config, None).as_deref(), Some("zephyrproject"));
+
+        // Subdirectories and spellings of a bound project
+        for path in [
+            "/projects/blinky/",
+            "/projects/blinky/build",
+            "/projects/blinky/app/./src",
+            "/projects/other/../blinky",
+        ] {
+            assert_eq!(
+                resolved_id(&config, Some(path)).as_deref(),
+                Some("ncs"),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(
+            resolved_id(&config, Some("/projects/blinky2")).as_deref(),
+            Some("zephyrproject")
+        );
+
+        // The deepest binding wins
+        let mut nested = config.clone();
+        nested.project_toolchains.insert(
+            "/projects/blinky/samples/hello".to_string(),
+            "zephyrproject".to_string(),
+        );
+        nested
+            .project_toolchains
+            .insert("/projects".to_string(), "ncs".to_string());
+        assert_eq!(
+            resolved_id(&nested, Some("/projects/blinky/samples/hello/build")).as_deref(),
+            Some("zephyrproject")
+        );
+        assert_eq!(
+            resolved_id(&nested, Some("/projects/blinky/samples")).as_deref(),
+            Some("ncs")
+        );
+        assert_eq!(
+            resolved_id(&nested, Some("/projects/other")).as_deref(),
+            Some("ncs")
+        );
+
+        // A binding to a removed profile falls back to the default
+        let mut stale = config.clone();
+        stale.toolchains.retain(|p| p.id != "ncs");
+        assert_eq!(
+            resolved_id(&stale, Some("/projects/blinky")).as_deref(),
+            Some("zephyrproject")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_legacy_settings() {
+        let legacy = UserConfig {
+            zephyr_base: Some("/opt/zephyrproject/zephyr".to_string()),
+            venv_path: Some("/opt/zephyrproject/.venv".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolve_for_project(&legacy, Some("/projects/blinky")).unwrap();
+        assert_eq!(resolved.id, "legacy");
+        assert_eq!(resolved.zephyr_base, "/opt/zephyrproject/zephyr");
+        assert_eq!(
+            resolved.venv_path.as_deref(),
+            Some("/opt/zephyrproject/.venv")
+        );
+
+        assert_eq!(resolve_for_project(&UserConfig::default(), None), None);
+
+        // Rescanning must not hide the configured paths behind a default
+        let scanned = UserConfig {
+            toolchains: config().toolchains,
+            ..legacy.clone()
+        };
+        assert_eq!(fallback_default(&scanned), None);
+        assert_eq!(resolved_id(&scanned, None).as_deref(), Some("legacy"));
+        assert_eq!(
+            fallback_default(&config()).as_deref(),
+            Some("zephyrproject")
+        );
+
+        // A chosen profile without a venv borrows the configured one
+        let chosen = UserConfig {
+            default_toolchain: Some("ncs".to_string()),
+            ..scanned
+        };
+        let resolved = resolve_for_project(&chosen, None).unwrap();
+        assert_eq!(resolved.id, "ncs");
+        assert_eq!(
+            resolved.venv_path.as_deref(),
+            Some("/opt/zephyrproject/.venv")
+        );
+    }
+
+    #[test]
+    fn merges_rescanned_profiles() {
+        let mut profiles = vec![ToolchainProfile {
+            venv_path: Some("/custom/venv".to_string()),
+            zephyr_version: Some("3.6.0".to_string()),
+            ..profile("zephyrproject", "/ws/zephyrproject/zephyr")
+        }];
+
+        let rescanned = ToolchainProfile {
+            venv_path: Some("/ws/zephyrproject/.venv".to_string()),
+            sdk_path: Some("/opt/zephyr-sdk-0.16.8".to_string()),
+            sdk_version: Some("0.16.8".to_string()),
+            zephyr_version: Some("3.7.0".to_string()),
+            ..profile("zephyrproject", "/ws/zephyrproject/zephyr")
+        };
+        let stored = merge_profile(&mut profiles, rescanned);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(stored.zephyr_version.as_deref(), Some("3.7.0"));
+        // A venv the user chose is kept; a missing SDK is filled in
+        assert_eq!(stored.venv_path.as_deref(), Some("/custom/venv"));
+        assert_eq!(stored.sdk_version.as_deref(), Some("0.16.8"));
+
+        // Same directory name, different tree: stored under a fresh id
+        let other = merge_profile(
+            &mut profiles,
+            profile("zephyrproject", "/mnt/zephyrproject/zephyr"),
+        );
+        assert_eq!(other.id, "zephyrproject-2");
+        assert_eq!(unique_id("zephyrproject", &profiles), "zephyrproject-3");
+        assert_eq!(unique_id("ncs", &profiles), "ncs");
+    }
+}
@@ -0,0 +1,1049 @@
+use crate::cmd_zephyr;
+use crate::config_manager::{self, UserConfig};
+use crate::executor::{CommandExecutor, SystemExecutor};
+use crate::toolchain_manager::{self, exe_name, venv_bin_dir};
+use crate::version;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+/// Oldest Python the current Zephyr releases support.
+const MIN_PYTHON: &str = "3.10";
+
+/// Where a venv candidate was found. Ordered from most to least relevant.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum VenvSource {
+    Workspace,
+    Configured,
+    ZephyrBase,
+    VirtualEnv,
+    Conda,
+    Default,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VenvCandidate {
+    pub path: String,
+    pub source: VenvSource,
+    pub python_path: Option<String>,
+    pub python_version: Option<String>,
+    pub west_version: Option<String>,
+    pub missing_packages: Vec<String>,
+    pub valid: bool,
+    /// Why the candidate is unusable (or degraded)
+    pub diagnostics: Vec<String>,
+}
+
+/// Python interpreter inside a venv or conda env. Conda puts `python.exe`
+/// at the env root on Windows, venvs put it in `Scripts/`.
+pub fn venv_python(exec: &dyn CommandExecutor, venv: &Path) -> Option<PathBuf> {
+    [
+        venv_bin_dir(venv).join(exe_name("python")),
+        venv.join(exe_name("python")),
+        venv_bin_dir(venv).join(exe_name("python3")),
+    ]
+    .into_iter()
+    .find(|p| exec.exists(p))
+}
+
+fn run_python(exec: &dyn CommandExecutor, python: &Path, args: &[&str]) -> Option<String> {
+    let output = exec.run(&python.to_string_lossy(), args)?;
+    if !output.success {
+        return None;
+    }
+    // Old Pythons print --version on stderr
+    let stdout = output.stdout.trim();
+    if stdout.is_empty() {
+        Some(output.stderr.trim().to_string())
+    } else {
+        Some(stdout.to_string())
+    }
+}
+
+/// Installed packages as `normalized name -> version`, via `pip list`.
+pub fn installed_packages(
+    exec: &dyn CommandExecutor,
+    python: &Path,
+) -> Option<HashMap<String, String>> {
+    #[derive(Deserialize)]
+    struct PipPackage {
+        name: String,
+        version: String,
+    }
+
+    let json = run_python(exec, python, &["-m", "pip", "list", "--format", "json"])?;
+    let packages: Vec<PipPackage> = serde_json::from_str(&json).ok()?;
+    Some(
+        packages
+            .into_iter()
+            .map(|p| (normalize_package_name(&p.name), p.version))
+            .collect(),
+    )
+}
+
+/// PEP 503 normalisation: case-insensitive, `_` and `.` equivalent to `-`.
+pub fn normalize_package_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Interpreter details that requirement markers can test, taken from the
+/// venv's python rather than the one running this app.
+#[derive(Debug, Clone, Default)]
+pub struct MarkerEnv {
+    /// `3.12.4`; `python_version` is its `major.minor`
+    pub python_full_version: Option<String>,
+    /// `cpython`, `pypy`, ...
+    pub implementation_name: Option<String>,
+}
+
+impl MarkerEnv {
+    pub fn of(exec: &dyn CommandExecutor, python: &Path) -> Self {
+        let output = run_python(
+            exec,
+            python,
+            &[
+                "-c",
+                "import platform, sys; print(platform.python_version()); print(sys.implementation.name)",
+            ],
+        )
+        .unwrap_or_default();
+        let mut lines = output.lines().map(|l| l.trim().to_string());
+        Self {
+            python_full_version: lines.next().filter(|v| !v.is_empty()),
+            implementation_name: lines.next().filter(|v| !v.is_empty()),
+        }
+    }
+
+    fn python_version(&self) -> Option<String> {
+        let full = self.python_full_version.as_deref()?;
+        let key = version::version_key(full);
+        Some(format!("{}.{}", key.first()?, key.get(1)?))
+    }
+}
+
+/// Package names listed in Zephyr's base requirements file (the set needed
+/// to configure and build; test/run-only extras are not required here).
+fn zephyr_base_requirements(zephyr_base: &Path, env: &MarkerEnv) -> Vec<String> {
+    let file = zephyr_base.join("scripts").join("requirements-base.txt");
+    parse_requirements_file(&file)
+        .into_iter()
+        .filter(|r| r.applies(env))
+        .map(|r| r.name)
+        .collect()
+}
+
+/// Validate one candidate: Python version, west, and the Zephyr base
+/// requirements when a Zephyr tree is known.
+pub fn inspect_venv(
+    exec: &dyn CommandExecutor,
+    path: &Path,
+    source: VenvSource,
+    zephyr_base: Option<&Path>,
+) -> VenvCandidate {
+    let mut candidate = VenvCandidate {
+        path: path.to_string_lossy().to_string(),
+        source,
+        python_path: None,
+        python_version: None,
+        west_version: None,
+        missing_packages: Vec::new(),
+        valid: false,
+        diagnostics: Vec::new(),
+    };
+
+    let Some(python) = venv_python(exec, path) else {
+        candidate
+            .diagnostics
+            .push("no python interpreter in this environment".to_string());
+        return candidate;
+    };
+    candidate.python_path = Some(python.to_string_lossy().to_string());
+
+    let Some(version_output) = run_python(exec, &python, &["--version"]) else {
+        candidate
+            .diagnostics
+            .push("python interpreter does not run (broken venv?)".to_string());
+        return candidate;
+    };
+    candidate.python_version = version::extract_version(&version_output);
+    let python_ok = match &candidate.python_version {
+        Some(v) if version::at_least(v, MIN_PYTHON) => true,
+        Some(v) => {
+            candidate
+                .diagnostics
+                .push(format!("Python {} is older than {}", v, MIN_PYTHON));
+            false
+        }
+        None => {
+            candidate
+                .diagnostics
+                .push(format!("unrecognised python version: {}", version_output));
+            false
+        }
+    };
+
+    candidate.west_version = run_python(exec, &python, &["-m", "west", "--version"])
+        .and_then(|out| version::extract_version(&out));
+    if candidate.west_version.is_none() {
+        candidate
+            .diagnostics
+            .push("west is not installed".to_string());
+    }
+
+    if let Some(base) = zephyr_base {
+        let required = zephyr_base_requirements(base, &MarkerEnv::of(exec, &python));
+        match installed_packages(exec, &python) {
+            Some(installed) => {
+                candidate.missing_packages = required
+                    .into_iter()
+                    .filter(|name| !installed.contains_key(name))
+                    .collect();
+                if !candidate.missing_packages.is_empty() {
+                    candidate.diagnostics.push(format!(
+                        "missing Zephyr requirements: {}",
+                        candidate.missing_packages.join(", ")
+                    ));
+                }
+            }
+            None => candidate
+                .diagnostics
+                .push("pip is not available".to_string()),
+        }
+    }
+
+    candidate.valid = python_ok && candidate.west_version.is_some();
+    candidate
+}
+
+/// Conda environments: the base install plus everything under `envs/`.
+fn conda_envs(exec: &dyn CommandExecutor) -> Vec<PathBuf> {
+    let mut envs = Vec::new();
+
+    if let Some(prefix) = exec.env_var("CONDA_PREFIX") {
+        envs.push(PathBuf::from(prefix));
+    }
+
+    let Some(home) = dirs::home_dir() else {
+        return envs;
+    };
+    for root in [
+        "miniconda3",
+        "anaconda3",
+        "miniforge3",
+        "mambaforge",
+        ".conda",
+    ] {
+        let root = home.join(root);
+        if root.join("conda-meta").is_dir() {
+            envs.push(root.clone());
+        }
+        if let Ok(entries) = std::fs::read_dir(root.join("envs")) {
+            envs.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+        }
+    }
+    envs
+}
+
+fn candidate_paths(
+    exec: &dyn CommandExecutor,
+    config: &UserConfig,
+    workspace: Option<&Path>,
+) -> Vec<(PathBuf, VenvSource)> {
+    let mut paths = Vec::new();
+
+    if let Some(ws) = workspace {
+        paths.push((ws.join(".venv"), VenvSource::Workspace));
+        // The project may itself sit inside the west workspace owning the venv
+        if let Some(parent) = ws.parent() {
+            paths.push((parent.join(".venv"), VenvSource::Workspace));
+        }
+    }
+
+    if let Some(venv) = &config.venv_path {
+        paths.push((PathBuf::from(venv), VenvSource::Configured));
+    }
+    for profile in &config.toolchains {
+        if let Some(venv) = &profile.venv_path {
+            paths.push((PathBuf::from(venv), VenvSource::Configured));
+        }
+    }
+
+    let bases = config
+        .zephyr_base
+        .iter()
+        .chain(config.toolchains.iter().map(|p| &p.zephyr_base));
+    for base in bases {
+        // <workspace>/zephyr -> <workspace>/.venv
+        if let Some(ws) = Path::new(base).parent() {
+            paths.push((ws.join(".venv"), VenvSource::ZephyrBase));
+        }
+    }
+
+    if let Some(venv) = exec.env_var("VIRTUAL_ENV") {
+        paths.push((PathBuf::from(venv), VenvSource::VirtualEnv));
+    }
+
+    for env in conda_envs(exec) {
+        paths.push((env, VenvSource::Conda));
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        paths.push((
+            home.join("zephyrproject").join(".venv"),
+            VenvSource::Default,
+        ));
+    }
+
+    paths
+}
+
+/// Find and validate every Python environment that could run west for the
+/// given workspace, best candidate first.
+pub fn discover_venvs(
+    exec: &dyn CommandExecutor,
+    config: &UserConfig,
+    workspace: Option<&Path>,
+) -> Vec<VenvCandidate> {
+    let mut seen: Vec<PathBuf> = Vec::new();
+    let mut candidates = Vec::new();
+
+    let zephyr_base = workspace
+        .map(|ws| ws.join("zephyr"))
+        .filter(|base| exec.exists(&base.join("VERSION")))
+        .or_else(|| {
+            // The toolchain the workspace's project would build with
+            let project = workspace.map(|ws| ws.to_string_lossy().to_string());
+            toolchain_manager::resolve_for_project(config, project.as_deref())
+                .map(|t| PathBuf::from(t.zephyr_base))
+                .filter(|base| !base.as_os_str().is_empty())
+        });
+
+    for (path, source) in candidate_paths(exec, config, workspace) {
+        if !exec.exists(&path) {
+            continue;
+        }
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        candidates.push(inspect_venv(exec, &path, source, zephyr_base.as_deref()));
+    }
+
+    candidates.sort_by(|a, b| {
+        b.valid
+            .cmp(&a.valid)
+            .then_with(|| a.missing_packages.len().cmp(&b.missing_packages.len()))
+            .then_with(|| a.source.cmp(&b.source))
+    });
+    candidates
+}
+
+#[tauri::command]
+pub fn discover_python_envs(
+    app: AppHandle,
+    workspace_path: Option<String>,
+) -> Result<Vec<VenvCandidate>, String> {
+    let config = config_manager::get_config(app)?;
+    Ok(discover_venvs(
+        &SystemExecutor,
+        &config,
+        workspace_path.as_deref().map(Path::new),
+    ))
+}
+
+/// One line of a pip requirements file, e.g. `pyelftools>=0.29`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Requirement {
+    pub name: String,
+    /// Comma separated PEP 440 specifiers, empty when any version will do
+    pub specifier: String,
+    pub marker: Option<String>,
+    pub file: String,
+}
+
+impl Requirement {
+    fn parse(line: &str, file: &str) -> Option<Requirement> {
+        let line = strip_comment(line);
+        if line.is_empty() || line.starts_with('#') || line.starts_with('-') {
+            return None;
+        }
+
+        let (body, marker) = match line.split_once(';') {
+            Some((body, marker)) => (body.trim(), Some(marker.trim().to_string())),
+            None => (line, None),
+        };
+        let end = body
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'))
+            .unwrap_or(body.len());
+        let name = &body[..end];
+        if name.is_empty() {
+            return None;
+        }
+
+        // Drop extras (`pkg[extra]>=1`), the legacy `pkg (>=1)` parentheses
+        // and whitespace from the specifier
+        let mut rest = body[end..].trim();
+        if rest.starts_with('[') {
+            rest = rest.split_once(']').map(|(_, r)| r.trim()).unwrap_or("");
+        }
+        let rest = rest
+            .strip_prefix('(')
+            .and_then(|r| r.strip_suffix(')'))
+            .unwrap_or(rest);
+
+        Some(Requirement {
+            name: normalize_package_name(name),
+            specifier: rest.replace(' ', ""),
+            marker,
+            file: file.to_string(),
+        })
+    }
+
+    /// Evaluate the environment marker for this host and the venv's
+    /// interpreter. Markers on interpreter details that could not be read
+    /// do not apply; other unknown markers are assumed to.
+    pub fn applies(&self, env: &MarkerEnv) -> bool {
+        let Some(marker) = &self.marker else {
+            return true;
+        };
+        evaluate_marker(marker, env)
+    }
+
+    pub fn is_satisfied_by(&self, installed: &str) -> bool {
+        self.specifier
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .all(|spec| specifier_matches(spec, installed))
+    }
+}
+
+/// Drop a `#` comment, which pip only recognises after whitespace or at the
+/// start of the line.
+fn strip_comment(line: &str) -> &str {
+    let end = line
+        .char_indices()
+        .find(|&(i, c)| c == '#' && (i == 0 || line[..i].ends_with(char::is_whitespace)))
+        .map_or(line.len(), |(i, _)| i);
+    line[..end].trim()
+}
+
+/// Split `expr` on the boolean operator `word` outside parentheses and
+/// quotes.
+fn split_marker<'a>(expr: &'a str, word: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in expr.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, c) if depth == 0 && c.is_whitespace() => {
+                let rest = &expr[i + 1..];
+                if rest.starts_with(word) && rest[word.len()..].starts_with(char::is_whitespace) {
+                    parts.push(&expr[start..i]);
+                    start = i + 1 + word.len();
+                }
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expr[start..]);
+    parts
+}
+
+/// `expr` without parentheses wrapping all of it.
+fn strip_parens(mut expr: &str) -> &str {
+    loop {
+        expr = expr.trim();
+        let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) else {
+            return expr;
+        };
+        // `(a) and (b)` starts and ends with parentheses that do not pair up
+        let mut depth = 0;
+        for c in inner.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return expr,
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        expr = inner;
+    }
+}
+
+/// Evaluate a PEP 508 marker; `and` binds tighter than `or`.
+fn evaluate_marker(expr: &str, env: &MarkerEnv) -> bool {
+    let expr = strip_parens(expr);
+    let any = split_marker(expr, "or");
+    if any.len() > 1 {
+        return any.into_iter().any(|e| evaluate_marker(e, env));
+    }
+    let all = split_marker(expr, "and");
+    if all.len() > 1 {
+        return all.into_iter().all(|e| evaluate_marker(e, env));
+    }
+    evaluate_marker_clause(expr, env)
+}
+
+fn evaluate_marker_clause(clause: &str, env: &MarkerEnv) -> bool {
+    use std::cmp::Ordering;
+
+    let clause = clause.trim();
+    for op in ["==", "!=", ">=", "<=", ">", "<"] {
+        let Some((var, value)) = clause.split_once(op) else {
+            continue;
+        };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        let var = var.trim();
+
+        if matches!(var, "python_version" | "python_full_version") {
+            let actual = match var {
+                "python_version" => env.python_version(),
+                _ => env.python_full_version.clone(),
+            };
+            let Some(actual) = actual else {
+                return false;
+            };
+            let ord = version::compare(&actual, value);
+            return match op {
+                "==" => ord == Ordering::Equal,
+                "!=" => ord != Ordering::Equal,
+                ">=" => ord != Ordering::Less,
+                "<=" => ord != Ordering::Greater,
+                ">" => ord == Ordering::Greater,
+                _ => ord == Ordering::Less,
+            };
+        }
+
+        let actual = match var {
+            "sys_platform" => match std::env::consts::OS {
+                "windows" => "win32",
+                "macos" => "darwin",
+                other => other,
+            },
+            "platform_system" => match std::env::consts::OS {
+                "windows" => "Windows",
+                "macos" => "Darwin",
+                _ => "Linux",
+            },
+            "platform_machine" => std::env::consts::ARCH,
+            "os_name" => {
+                if cfg!(windows) {
+                    "nt"
+                } else {
+                    "posix"
+                }
+            }
+            "implementation_name" => match &env.implementation_name {
+                Some(name) => name.as_str(),
+                None => return false,
+            },
+            _ => return true,
+        };
+        return match op {
+            "==" => actual == value,
+            "!=" => actual != value,
+            _ => true,
+        };
+    }
+    true
+}
+
+fn specifier_matches(spec: &str, installed: &str) -> bool {
+    use std::cmp::Ordering;
+
+    for op in ["===", "~=", "==", "!=", ">=", "<=", ">", "<"] {
+        let Some(wanted) = spec.strip_prefix(op) else {
+            continue;
+        };
+        if let Some(prefix) = wanted.strip_suffix(".*") {
+            let matches = release_starts_with(installed, &version::version_key(prefix));
+            return if op == "!=" { !matches } else { matches };
+        }
+        let ord = version::compare(installed, wanted);
+        return match op {
+            "===" => installed == wanted,
+            "==" => ord == Ordering::Equal,
+            "!=" => ord != Ordering::Equal,
+            ">=" => ord != Ordering::Less,
+            "<=" => ord != Ordering::Greater,
+            ">" => ord == Ordering::Greater,
+            "<" => ord == Ordering::Less,
+            "~=" => {
+                // ~=2.2.1 means >=2.2.1, ==2.2.*
+                let mut prefix = version::version_key(wanted);
+                prefix.pop();
+                ord != Ordering::Less && release_starts_with(installed, &prefix)
+            }
+            _ => true,
+        };
+    }
+    true
+}
+
+/// Whether the release components of `installed`, padded with zeros, start
+/// with `prefix`: `1` matches `1.0.*`.
+fn release_starts_with(installed: &str, prefix: &[u64]) -> bool {
+    let mut key = version::version_key(installed);
+    if key.len() < prefix.len() {
+        key.resize(prefix.len(), 0);
+    }
+    key.starts_with(prefix)
+}
+
+/// Parse a requirements file, following `-r other.txt` includes.
+pub fn parse_requirements_file(path: &Path) -> Vec<Requirement> {
+    let mut visited = Vec::new();
+    let mut requirements = Vec::new();
+    collect_requirements(path, &mut visited, &mut requirements);
+    requirements
+}
+
+fn collect_requirements(path: &Path, visited: &mut Vec<PathBuf>, out: &mut Vec<Requirement>) {
+    // `../scripts/a.txt` and `a.txt` are the same file
+    let key = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if visited.contains(&key) {
+        return;
+    }
+    visited.push(key);
+
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    for line in content.lines() {
+        let trimmed = strip_comment(line);
+        let include = trimmed
+            .strip_prefix("--requirement")
+            .or_else(|| trimmed.strip_prefix("-r"))
+            .map(|rest| rest.trim_start_matches('=').trim())
+            .filter(|rest| !rest.is_empty());
+        if let Some(include) = include {
+            if let Some(dir) = path.parent() {
+                collect_requirements(&dir.join(include), visited, out);
+            }
+            continue;
+        }
+        if let Some(req) = Requirement::parse(trimmed, &file_name) {
+            if !out
+                .iter()
+                .any(|r| r.name == req.name && r.specifier == req.specifier)
+            {
+                out.push(req);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RequirementStatus {
+    Ok,
+    Missing,
+    VersionMismatch,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RequirementCheck {
+    pub requirement: Requirement,
+    pub installed_version: Option<String>,
+    pub status: RequirementStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RequirementsReport {
+    pub zephyr_base: String,
+    pub python_path: String,
+    pub checks: Vec<RequirementCheck>,
+    pub missing: usize,
+    pub mismatched: usize,
+    pub satisfied: bool,
+}
+
+impl RequirementsReport {
+    /// `name<spec>` strings for everything that needs (re)installing.
+    pub fn unsatisfied_specs(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|c| c.status != RequirementStatus::Ok)
+            .map(|c| format!("{}{}", c.requirement.name, c.requirement.specifier))
+            .collect()
+    }
+}
+
+/// All `requirements*.txt` files in `<zephyr_base>/scripts`, starting from
+/// the umbrella `requirements.txt` so includes are attributed correctly.
+fn zephyr_requirements(zephyr_base: &Path, env: &MarkerEnv) -> Vec<Requirement> {
+    let scripts = zephyr_base.join("scripts");
+    let mut requirements = parse_requirements_file(&scripts.join("requirements.txt"));
+
+    if let Ok(entries) = std::fs::read_dir(&scripts) {
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                name.starts_with("requirements") && name.ends_with(".txt")
+            })
+            .collect();
+        files.sort();
+        for file in files {
+            for req in parse_requirements_file(&file) {
+                if !requirements.iter().any(|r| r.name == req.name) {
+                    requirements.push(req);
+                }
+            }
+        }
+    }
+
+    requirements.retain(|r| r.applies(env));
+    requirements
+}
+
+fn check_requirements(
+    exec: &dyn CommandExecutor,
+    zephyr_base: &Path,
+    python: &Path,
+) -> Result<RequirementsReport, String> {
+    let requirements = zephyr_requirements(zephyr_base, &MarkerEnv::of(exec, python));
+    if requirements.is_empty() {
+        return Err(format!(
+            "No requirements files found in {}",
+            zephyr_base.join("scripts").display()
+        ));
+    }
+    let installed = installed_packages(exec, python)
+        .ok_or_else(|| format!("Failed to run pip list with {}", python.display()))?;
+
+    let checks: Vec<RequirementCheck> = requirements
+        .into_iter()
+        .map(|requirement| {
+            let installed_version = installed.get(&requirement.name).cloned();
+            let status = match &installed_version {
+                None => RequirementStatus::Missing,
+                Some(v) if requirement.is_satisfied_by(v) => RequirementStatus::Ok,
+                Some(_) => RequirementStatus::VersionMismatch,
+            };
+            RequirementCheck {
+                requirement,
+                installed_version,
+                status,
+            }
+        })
+        .collect();
+
+    let missing = checks
+        .iter()
+        .filter(|c| c.status == RequirementStatus::Missing)
+        .count();
+    let mismatched = checks
+        .iter()
+        .filter(|c| c.status == RequirementStatus::VersionMismatch)
+        .count();
+
+    Ok(RequirementsReport {
+        zephyr_base: zephyr_base.to_string_lossy().to_string(),
+        python_path: python.to_string_lossy().to_string(),
+        checks,
+        missing,
+        mismatched,
+        satisfied: missing == 0 && mismatched == 0,
+    })
+}
+
+/// Zephyr tree and venv python of the toolchain bound to a project.
+fn resolve_python_and_base(
+    app: &AppHandle,
+    project_path: Option<&str>,
+) -> Result<(PathBuf, PathBuf), String> {
+    let toolchain = toolchain_manager::resolve_for_app(app, project_path)
+        .ok_or("No toolchain is configured for this project")?;
+    if toolchain.zephyr_base.is_empty() {
+        return Err("The toolchain has no Zephyr base configured".to_string());
+    }
+    Ok((
+        PathBuf::from(toolchain.python_program()),
+        PathBuf::from(toolchain.zephyr_base),
+    ))
+}
+
+#[tauri::command]
+pub async fn check_python_requirements(
+    app: AppHandle,
+    project_path: Option<String>,
+) -> Result<RequirementsReport, String> {
+    let (python, zephyr_base) = resolve_python_and_base(&app, project_path.as_deref())?;
+    check_requirements(&SystemExecutor, &zephyr_base, &python)
+}
+
+/// Install only the missing / mismatched packages, then check again.
+#[tauri::command]
+pub async fn install_missing_requirements(
+    app: AppHandle,
+    project_path: Option<String>,
+) -> Result<RequirementsReport, String> {
+    let (python, zephyr_base) = resolve_python_and_base(&app, project_path.as_deref())?;
+    let report = check_requirements(&SystemExecutor, &zephyr_base, &python)?;
+
+    let specs = report.unsatisfied_specs();
+    if specs.is_empty() {
+        return Ok(report);
+    }
+
+    cmd_zephyr::emit_log(&app, &format!("Installing: {}", specs.join(" ")));
+    let python_str = python.to_string_lossy().to_string();
+    let mut args = vec!["-m", "pip", "install"];
+    args.extend(specs.iter().map(|s| s.as_str()));
+    cmd_zephyr::run_command_stream(&app, &python_str, &args, None)?;
+
+    check_requirements(&SystemExecutor, &zephyr_base, &python)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(line: &str) -> Requirement {
+        Requirement::parse(line, "requirements.txt").unwrap()
+    }
+
+    #[test]
+    fn parses_requirement_lines() {
+        let r = req("PyYAML[cli] >= 5.1 ; sys_platform == 'linux'  # config files");
+        assert_eq!(r.name, "pyyaml");
+        assert_eq!(r.specifier, ">=5.1");
+        assert_eq!(r.marker.as_deref(), Some("sys_platform == 'linux'"));
+
+        let r = req("pyelftools>=0.29,<1\t# tabs too");
+        assert_eq!(
+            (r.name.as_str(), r.specifier.as_str()),
+            ("pyelftools", ">=0.29,<1")
+        );
+        assert_eq!(req("anytree").specifier, "");
+        assert_eq!(req("intelhex (>=2.3)").specifier, ">=2.3");
+
+        for skipped in ["", "   # only a comment", "-c constraints.txt", "--pre"] {
+            assert!(Requirement::parse(skipped, "requirements.txt").is_none());
+        }
+    }
+
+    #[test]
+    fn evaluates_markers_with_and_or() {
+        let env = MarkerEnv {
+            python_full_version: Some("3.12.4".to_string()),
+            implementation_name: Some("cpython".to_string()),
+        };
+        let applies = |marker: &str| {
+            Requirement {
+                marker: Some(marker.to_string()),
+                ..req("pkg")
+            }
+            .applies(&env)
+        };
+        let yes = r#"platform_system != "Plan9""#;
+        let no = r#"platform_system == "Plan9""#;
+
+        assert!(applies(&format!("{no} or {yes}")));
+        assert!(!applies(&format!("{yes} and {no}")));
+        assert!(applies(&format!("{no} and {no} or {yes}")));
+        assert!(!applies(&format!("{no} and ({yes} or {yes})")));
+        assert!(!applies(&format!("({yes}) and ({no})")));
+        assert!(!applies(r#"platform_system == "Plan9 or Linux""#));
+    }
+
+    #[test]
+    fn evaluates_interpreter_markers_against_the_venv() {
+        let env = MarkerEnv {
+            python_full_version: Some("3.12.4".to_string()),
+            implementation_name: Some("cpython".to_string()),
+        };
+        let requirements = [
+            "importlib-metadata; python_version < \"3.8\"",
+            "tomli>=1.1.0; python_version < '3.11'",
+            "typing-extensions; python_full_version >= '3.12.1'",
+            "mypy; python_version == \"3.12\" and implementation_name == \"cpython\"",
+            "pypy-only; implementation_name == 'pypy'",
+        ]
+        .map(req);
+        let applies: Vec<&str> = requirements
+            .iter()
+            .filter(|r| r.applies(&env))
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(applies, ["typing-extensions", "mypy"]);
+
+        // Interpreter could not be queried: skip rather than install
+        assert!(!requirements[0].applies(&MarkerEnv::default()));
+        assert!(req("west>=0.14").applies(&MarkerEnv::default()));
+    }
+
+    #[test]
+    fn matches_version_specifiers() {
+        assert!(specifier_matches("~=2.2", "2.5"));
+        assert!(!specifier_matches("~=2.2", "3.0"));
+        assert!(specifier_matches("~=2.2.1", "2.2.5"));
+        assert!(!specifier_matches("~=2.2.1", "2.2.0"));
+        assert!(!specifier_matches("~=2.2.1", "2.3"));
+        assert!(!specifier_matches("!=1.0.*", "1.0.5"));
+        assert!(specifier_matches("!=1.0.*", "1.1"));
+        assert!(specifier_matches("==1.0.*", "1"));
+        assert!(specifier_matches("==3.10", "3.10.0"));
+        assert!(!specifier_matches(">=1.0", "1.0rc1"));
+        assert!(specifier_matches("===1.0", "1.0"));
+
+        let r = req("pyelftools>=0.29,<1");
+        assert!(r.is_satisfied_by("0.31"));
+        assert!(!r.is_satisfied_by("1.0"));
+    }
+
+    /// A venv at `path` whose python runs; `west` and `pip list` as given.
+    fn fake_venv(
+        exec: crate::executor::FakeExecutor,
+        path: &str,
+        west: bool,
+        packages: &str,
+    ) -> crate::executor::FakeExecutor {
+        let python = format!("{}/bin/python", path);
+        let exec = exec
+            .file(&python, "")
+            .output(&format!("{} --version", python), "Python 3.12.4")
+            .output(
+                &format!("{} -m pip list --format json", python),
+                &format!("[{}]", packages),
+            );
+        if west {
+            exec.output(
+                &format!("{} -m west --version", python),
+                "West version: v1.2.0",
+            )
+        } else {
+            exec.failure(
+                &format!("{} -m west --version", python),
+                1,
+                "No module named west",
+            )
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn ranks_valid_venvs_with_fewest_missing_packages_first() {
+        let base = std::env::temp_dir().join(format!("venv-discovery-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("scripts")).unwrap();
+        std::fs::write(
+            base.join("scripts").join("requirements-base.txt"),
+            "pyelftools>=0.29\nPyYAML>=5.1\n",
+        )
+        .unwrap();
+
+        let all =
+            r#"{"name": "pyelftools", "version": "0.31"}, {"name": "PyYAML", "version": "6.0.1"}"#;
+        let exec = crate::executor::FakeExecutor::new().env("VIRTUAL_ENV", "/active/venv");
+        let exec = fake_venv(exec, "/ws/.venv", false, all);
+        let exec = fake_venv(exec, "/active/venv", true, all);
+        let exec = fake_venv(
+            exec,
+            "/configured/venv",
+            true,
+            r#"{"name": "pyelftools", "version": "0.31"}"#,
+        );
+        let exec = fake_venv(exec, "/profile/venv", true, all);
+
+        let profile =
+            |id: &str, zephyr_base: &str, venv: Option<&str>| toolchain_manager::ToolchainProfile {
+                id: id.to_string(),
+                name: id.to_string(),
+                zephyr_base: zephyr_base.to_string(),
+                venv_path: venv.map(str::to_string),
+                sdk_path: None,
+                zephyr_version: None,
+                sdk_version: None,
+            };
+        // The workspace is bound to the second toolchain, whose Zephyr tree
+        // decides which packages are missing
+        let config = UserConfig {
+            venv_path: Some("/configured/venv".to_string()),
+            toolchains: vec![
+                profile("ncs", "/ncs/zephyr", None),
+                profile(
+                    "zephyrproject",
+                    &base.to_string_lossy(),
+                    Some("/profile/venv"),
+                ),
+            ],
+            project_toolchains: [("/ws".to_string(), "zephyrproject".to_string())].into(),
+            ..Default::default()
+        };
+        let found = discover_venvs(&exec, &config, Some(Path::new("/ws")));
+        std::fs::remove_dir_all(&base).unwrap();
+
+        let ranked: Vec<(&str, VenvSource, bool)> = found
+            .iter()
+            .map(|c| (c.path.as_str(), c.source, c.valid))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("/profile/venv", VenvSource::Configured, true),
+                ("/active/venv", VenvSource::VirtualEnv, true),
+                // A better source does not outweigh a missing package
+                ("/configured/venv", VenvSource::Configured, true),
+                ("/ws/.venv", VenvSource::Workspace, false),
+            ]
+        );
+        assert!(found[0].missing_packages.is_empty());
+        assert_eq!(found[1].west_version.as_deref(), Some("1.2.0"));
+        assert_eq!(found[2].missing_packages, vec!["pyyaml"]);
+        assert!(found[3]
+            .diagnostics
+            .contains(&"west is not installed".to_string()));
+    }
+
+    #[test]
+    fn follows_includes_without_looping() {
+        let dir = std::env::temp_dir().join(format!("venv-requirements-{}", std::process::id()));
+        let scripts = dir.join("scripts");
+        std::fs::create_dir_all(&scripts).unwrap();
+        std::fs::write(
+            scripts.join("requirements.txt"),
+            "-r requirements-base.txt # shared\npyelftools>=0.29\nwest>=1.2\n",
+        )
+        .unwrap();
+        std::fs::write(
+            scripts.join("requirements-base.txt"),
+            "--requirement=../scripts/requirements.txt\nPyYAML>=5.1\npyelftools>=0.29\n",
+        )
+        .unwrap();
+
+        let found: Vec<(String, String)> =
+            parse_requirements_file(&scripts.join("requirements.txt"))
+                .into_iter()
+                .map(|r| (r.name, r.file))
+                .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            found,
+            vec![
+                ("pyyaml".to_string(), "requirements-base.txt".to_string()),
+                (
+                    "pyelftools".to_string(),
+                    "requirements-base.txt".to_string()
+                ),
+                ("west".to_string(), "requirements.txt".to_string()),
+            ]
+        );
+    }
+}
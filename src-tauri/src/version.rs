@@ -0,0 +1,145 @@
+use std::cmp::Ordering;
+
+/// Split a version into its release part and whatever follows it:
+/// `"v0.17.0-rc1"` -> `("0.17.0", "-rc1")`.
+fn split_release(version: &str) -> (&str, &str) {
+    let version = version.trim().trim_start_matches('v');
+    let end = version
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(version.len());
+    let release = version[..end].trim_end_matches('.');
+    (release, &version[release.len()..])
+}
+
+/// Numeric release components, without any pre-release suffix:
+/// `"0.17.0-rc1"` -> `[0, 17, 0]`.
+pub fn version_key(version: &str) -> Vec<u64> {
+    split_release(version)
+        .0
+        .split('.')
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+/// Rank and number of a pre-release suffix (`dev` < `a`/`alpha` < `b`/`beta`
+/// < `rc`), or `None` for a final release. Build metadata (`+...`), post
+/// releases and distribution revisions (`-1ubuntu1`) count as final.
+fn pre_release(version: &str) -> Option<(u8, u64)> {
+    let suffix = split_release(version)
+        .1
+        .trim_start_matches(['-', '.', '_'])
+        .to_ascii_lowercase();
+    let tag: String = suffix
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    let rank = match tag.as_str() {
+        "dev" => 0,
+        "a" | "alpha" => 1,
+        "b" | "beta" => 2,
+        "c" | "rc" | "pre" | "preview" => 3,
+        _ => return None,
+    };
+    let number = suffix[tag.len()..]
+        .trim_start_matches(['-', '.', '_'])
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0);
+    Some((rank, number))
+}
+
+/// Compare two dotted versions, treating missing components as zero so that
+/// `3.10` == `3.10.0`. A pre-release sorts below its release:
+/// `0.17.0-rc1` < `0.17.0`.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let x = version_key(a);
+    let y = version_key(b);
+    let len = x.len().max(y.len());
+    for i in 0..len {
+        let p = x.get(i).copied().unwrap_or(0);
+        let q = y.get(i).copied().unwrap_or(0);
+        match p.cmp(&q) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    match (pre_release(a), pre_release(b)) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(p), Some(q)) => p.cmp(&q),
+    }
+}
+
+pub fn at_least(version: &str, minimum: &str) -> bool {
+    compare(version, minimum) != Ordering::Less
+}
+
+/// First `N.N[.N...]` token in tool output, without build suffixes, e.g.
+/// `"gcc (Ubuntu 11.4.0-1ubuntu1~22.04) 11.4.0"` -> `"11.4.0"`.
+pub fn extract_version(text: &str) -> Option<String> {
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == '(' || c == ')')
+        .map(|token| token.trim_start_matches('v'))
+        .find_map(|token| {
+            let numeric: String = token
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || *c == '.')
+                .collect();
+            let numeric = numeric.trim_end_matches('.');
+            let mut parts = numeric.split('.');
+            let valid = parts.next().is_some_and(|p| !p.is_empty())
+                && parts.next().is_some_and(|p| !p.is_empty());
+            valid.then(|| numeric.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_release_components_only() {
+        assert_eq!(version_key("0.17.0-rc1"), vec![0, 17, 0]);
+        assert_eq!(version_key("v3.7.0"), vec![3, 7, 0]);
+        assert_eq!(version_key("1.0rc1"), vec![1, 0]);
+        assert_eq!(version_key("3.12.4+"), vec![3, 12, 4]);
+        assert!(version_key("unknown").is_empty());
+    }
+
+    #[test]
+    fn pre_releases_sort_below_release() {
+        assert_eq!(compare("0.17.0-rc1", "0.17.0"), Ordering::Less);
+        assert_eq!(compare("0.17.0", "0.17.0-rc1"), Ordering::Greater);
+        assert_eq!(compare("0.17.0-rc1", "0.16.8"), Ordering::Greater);
+        assert_eq!(compare("0.17.0-rc2", "0.17.0-rc1"), Ordering::Greater);
+        assert_eq!(compare("1.0.dev1", "1.0a1"), Ordering::Less);
+        assert_eq!(compare("1.0a2", "1.0b1"), Ordering::Less);
+        assert_eq!(compare("1.0b1", "1.0rc1"), Ordering::Less);
+        assert!(!at_least("1.0rc1", "1.0"));
+        assert!(at_least("1.0", "1.0rc1"));
+    }
+
+    #[test]
+    fn compares_padded_and_final_versions() {
+        assert_eq!(compare("3.10", "3.10.0"), Ordering::Equal);
+        assert_eq!(compare("3.9", "3.12"), Ordering::Less);
+        assert_eq!(compare("1.0.post1", "1.0"), Ordering::Equal);
+        assert_eq!(compare("11.4.0-1ubuntu1", "11.4.0"), Ordering::Equal);
+        assert!(at_least("3.12.4+", "3.12"));
+    }
+
+    #[test]
+    fn extracts_version_from_tool_output() {
+        assert_eq!(
+            extract_version("gcc (Ubuntu 11.4.0-1ubuntu1~22.04) 11.4.0").as_deref(),
+            Some("11.4.0")
+        );
+        assert_eq!(
+            extract_version("West version: v1.2.0").as_deref(),
+            Some("1.2.0")
+        );
+        assert_eq!(extract_version("Python 3"), None);
+    }
+}
@@ -0,0 +1,508 @@
+use crate::serial_comm::{self, Subscription};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Output is considered complete once the port has been quiet this long.
+const QUIET_PERIOD: Duration = Duration::from_millis(200);
+/// `help` nesting explored when building the completion tree.
+const MAX_TREE_DEPTH: usize = 3;
+
+/// A command as listed by the shell's `help` / `<cmd> -h`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ShellCommand {
+    pub name: String,
+    pub help: String,
+    pub subcommands: Vec<ShellCommand>,
+}
+
+/// Byte transport the shell runs over.
+pub trait ShellIo {
+    fn send(&mut self, data: &[u8]) -> Result<(), String>;
+    /// Next chunk of received bytes, `None` after `timeout` without any.
+    fn recv(&mut self, timeout: Duration) -> Option<Vec<u8>>;
+}
+
+struct SerialShellIo {
+    port: String,
+    subscription: Subscription,
+}
+
+impl ShellIo for SerialShellIo {
+    fn send(&mut self, data: &[u8]) -> Result<(), String> {
+        serial_comm::write_port(&self.port, data).map(|_| ())
+    }
+
+    fn recv(&mut self, timeout: Duration) -> Option<Vec<u8>> {
+        self.subscription.rx.recv_timeout(timeout).ok()
+    }
+}
+
+/// Remove ANSI/VT100 escape sequences (colours, cursor movement, OSC
+/// titles) and carriage returns.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters and intermediates up to a final byte
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: terminated by BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // Two-byte sequences such as ESC 7 / ESC 8
+                _ => {}
+            },
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The response to `command`: without the echoed command line and the
+/// trailing prompt. `None` until the prompt has come back.
+pub fn extract_response(text: &str, command: &str, prompt: &str) -> Option<String> {
+    let prompt = prompt.trim_end();
+    let body = match text.split_once('\n') {
+        Some((first, rest)) if first.contains(command.trim()) => rest,
+        // Echo turned off
+        _ => text,
+    };
+    let body = body.trim_end().strip_suffix(prompt)?;
+    Some(body.trim_end_matches('\n').to_string())
+}
+
+/// Entries of the `Available commands:` / `Subcommands:` section of help
+/// output. Wrapped help text lines are joined onto their entry.
+pub fn parse_help(text: &str) -> Vec<ShellCommand> {
+    let mut commands: Vec<ShellCommand> = Vec::new();
+    let mut indent = None;
+    let mut in_list = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("Available commands:") || trimmed.starts_with("Subcommands:") {
+            in_list = true;
+            indent = None;
+            continue;
+        }
+        if !in_list {
+            continue;
+        }
+        if trimmed.is_empty() {
+            in_list = false;
+            continue;
+        }
+
+        let line_indent = line.len() - trimmed.len();
+        let entry = trimmed
+            .split_once(':')
+            .map(|(name, help)| (name.trim(), help.trim()))
+            .filter(|(name, _)| !name.is_empty() && !name.contains(char::is_whitespace));
+        match (entry, indent) {
+            (Some((name, help)), None) => {
+                indent = Some(line_indent);
+                commands.push(ShellCommand {
+                    name: name.to_string(),
+                    help: help.to_string(),
+                    subcommands: Vec::new(),
+                });
+            }
+            (Some((name, help)), Some(i)) if i == line_indent => commands.push(ShellCommand {
+                name: name.to_string(),
+                help: help.to_string(),
+                subcommands: Vec::new(),
+            }),
+            // Continuation of the previous entry's help
+            _ if indent.is_some_and(|i| line_indent > i) => {
+                if let Some(last) = commands.last_mut() {
+                    last.help.push(' ');
+                    last.help.push_str(trimmed.trim_start_matches(':').trim());
+                }
+            }
+            _ => in_list = false,
+        }
+    }
+    commands
+}
+
+/// Candidates for the last word of `line`.
+pub fn complete(tree: &[ShellCommand], line: &str) -> Vec<String> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let partial = if line.is_empty() || line.ends_with(char::is_whitespace) {
+        ""
+    } else {
+        words.pop().unwrap_or("")
+    };
+
+    let mut level = tree;
+    for word in words {
+        match level.iter().find(|c| c.name == word) {
+            Some(command) => level = &command.subcommands,
+            None => return Vec::new(),
+        }
+    }
+    level
+        .iter()
+        .filter(|c| c.name.starts_with(partial))
+        .map(|c| c.name.clone())
+        .collect()
+}
+
+/// Collect output until `done` accepts it, or with `until_quiet` until
+/// nothing has arrived for `QUIET_PERIOD`. Also reports whether it finished
+/// before `timeout`.
+fn collect(
+    io: &mut dyn ShellIo,
+    timeout: Duration,
+    until_quiet: bool,
+    done: impl Fn(&str) -> bool,
+) -> (String, bool) {
+    let deadline = Instant::now() + timeout;
+    let mut raw = Vec::new();
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match io.recv(remaining.min(QUIET_PERIOD)) {
+            Some(chunk) => {
+                raw.extend(chunk);
+                let text = strip_ansi(&String::from_utf8_lossy(&raw));
+                if done(&text) {
+                    return (text, true);
+                }
+            }
+            None if until_quiet && !raw.is_empty() => {
+                return (strip_ansi(&String::from_utf8_lossy(&raw)), true);
+            }
+            None => {}
+        }
+    }
+    (strip_ansi(&String::from_utf8_lossy(&raw)), false)
+}
+
+/// Press enter and take the last line that comes back as the prompt.
+pub fn detect_prompt(io: &mut dyn ShellIo, timeout: Duration) -> Result<String, String> {
+    io.send(b"\r")?;
+    let (text, _) = collect(io, timeout, true, |_| false);
+    text.lines()
+        .map(str::trim_end)
+        .rfind(|l| !l.is_empty())
+        .map(|l| format!("{} ", l))
+        .ok_or_else(|| "No shell prompt received".to_string())
+}
+
+pub fn exec(
+    io: &mut dyn ShellIo,
+    prompt: &str,
+    command: &str,
+    timeout: Duration,
+) -> Result<String, String> {
+    io.send(format!("{}\r", command.trim()).as_bytes())?;
+    let (text, finished) = collect(io, timeout, false, |text| {
+        extract_response(text, command, prompt).is_some()
+    });
+    if finished {
+        return Ok(extract_response(&text, command, prompt).unwrap_or_default());
+    }
+    Err(format!(
+        "Timed out waiting for the shell prompt after `{}`. Output so far:\n{}",
+        command.trim(),
+        text.trim_end()
+    ))
+}
+
+/// Walk `help` and `<cmd> -h` down to `MAX_TREE_DEPTH` levels.
+pub fn build_command_tree(
+    io: &mut dyn ShellIo,
+    prompt: &str,
+    timeout: Duration,
+) -> Result<Vec<ShellCommand>, String> {
+    let mut tree = parse_help(&exec(io, prompt, "help", timeout)?);
+    for command in tree.iter_mut() {
+        fill_subcommands(io, prompt, timeout, command, &command.name.clone(), 1)?;
+    }
+    Ok(tree)
+}
+
+fn fill_subcommands(
+    io: &mut dyn ShellIo,
+    prompt: &str,
+    timeout: Duration,
+    command: &mut ShellCommand,
+    path: &str,
+    depth: usize,
+) -> Result<(), String> {
+    if depth >= MAX_TREE_DEPTH || command.name == "help" {
+        return Ok(());
+    }
+    command.subcommands = parse_help(&exec(io, prompt, &format!("{} -h", path), timeout)?);
+    for sub in command.subcommands.iter_mut() {
+        let sub_path = format!("{} {}", path, sub.name);
+        fill_subcommands(io, prompt, timeout, sub, &sub_path, depth + 1)?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct ShellSession {
+    prompt: Option<String>,
+    tree: Option<Vec<ShellCommand>>,
+}
+
+// Per port, so one command runs at a time on each.
+static SESSIONS: Mutex<BTreeMap<String, Arc<Mutex<ShellSession>>>> = Mutex::new(BTreeMap::new());
+
+fn session(port: &str) -> Arc<Mutex<ShellSession>> {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .entry(port.to_string())
+        .or_default()
+        .clone()
+}
+
+fn serial_io(port: &str) -> Result<SerialShellIo, String> {
+    Ok(SerialShellIo {
+        port: port.to_string(),
+        subscription: serial_comm::subscribe(port)?,
+    })
+}
+
+fn session_prompt(
+    session: &mut ShellSession,
+    io: &mut dyn ShellIo,
+    port: &str,
+) -> Result<String, String> {
+    if let Some(prompt) = &session.prompt {
+        return Ok(prompt.clone());
+    }
+    let prompt = detect_prompt(io, DEFAULT_TIMEOUT).map_err(|e| format!("{} on {}", e, port))?;
+    session.prompt = Some(prompt.clone());
+    Ok(prompt)
+}
+
+#[tauri::command]
+pub async fn shell_detect_prompt(port: String) -> Result<String, String> {
+    let session = session(&port);
+    let mut session = session.lock().unwrap();
+    session.prompt = None;
+    let mut io = serial_io(&port)?;
+    session_prompt(&mut session, &mut io, &port)
+}
+
+/// Run one command on a port's shell, waiting for other commands on it.
+pub(crate) fn run(port: &str, cmd: &str, timeout: Duration) -> Result<String, String> {
+    let session = session(port);
+    let mut session = session.lock().unwrap();
+    let mut io = serial_io(port)?;
+    let prompt = session_prompt(&mut session, &mut io, port)?;
+    exec(&mut io, &prompt, cmd, timeout)
+}
+
+#[tauri::command]
+pub async fn shell_exec(
+    port: String,
+    cmd: String,
+    timeout_ms: Option<u64>,
+) -> Result<String, String> {
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_TIMEOUT);
+    run(&port, &cmd, timeout)
+}
+
+/// The command tree for completion, queried once per port unless `refresh`.
+#[tauri::command]
+pub async fn shell_command_tree(
+    port: String,
+    refresh: Option<bool>,
+) -> Result<Vec<ShellCommand>, String> {
+    let session = session(&port);
+    let mut session = session.lock().unwrap();
+    if let (Some(tree), false) = (&session.tree, refresh.unwrap_or(false)) {
+        return Ok(tree.clone());
+    }
+    let mut io = serial_io(&port)?;
+    let prompt = session_prompt(&mut session, &mut io, &port)?;
+    let tree = build_command_tree(&mut io, &prompt, DEFAULT_TIMEOUT)?;
+    session.tree = Some(tree.clone());
+    Ok(tree)
+}
+
+#[tauri::command]
+pub async fn shell_complete(port: String, line: String) -> Result<Vec<String>, String> {
+    let tree = shell_command_tree(port, None).await?;
+    Ok(complete(&tree, &line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    const PROMPT: &str = "\x1b[1;32muart:~$ \x1b[m";
+
+    const HELP: &str = "Please press the <Tab> button to see all available commands.\r\n\
+        You can also use the <Tab> button to prompt or auto-complete all commands or its subcommands.\r\n\
+        \r\n\
+        Available commands:\r\n  \
+          clear    :Clear screen.\r\n  \
+          device   :Device commands\r\n  \
+          help     :Prints the help message.\r\n  \
+          kernel   :Kernel commands\r\n";
+
+    const KERNEL_HELP: &str = "kernel - Kernel commands\r\n\
+        Subcommands:\r\n  \
+          stacks   :List threads stack usage.\r\n  \
+          threads  :List kernel threads.\r\n  \
+          uptime   :Kernel uptime. Can be called with the -p or --pretty\r\n            \
+                    options\r\n";
+
+    /// Answers commands like the Zephyr shell: colored echo, the response in
+    /// two chunks, then the prompt.
+    struct FakeShell {
+        responses: BTreeMap<String, String>,
+        pending: VecDeque<Vec<u8>>,
+        sent: Vec<String>,
+    }
+
+    impl FakeShell {
+        fn new(responses: &[(&str, &str)]) -> Self {
+            Self {
+                responses: responses
+                    .iter()
+                    .map(|(c, r)| (c.to_string(), r.to_string()))
+                    .collect(),
+                pending: VecDeque::new(),
+                sent: Vec::new(),
+            }
+        }
+    }
+
+    impl ShellIo for FakeShell {
+        fn send(&mut self, data: &[u8]) -> Result<(), String> {
+            let line = String::from_utf8_lossy(data).trim().to_string();
+            self.sent.push(line.clone());
+            if line.is_empty() {
+                self.pending
+                    .push_back(format!("\r\n{}", PROMPT).into_bytes());
+                return Ok(());
+            }
+            let response = self
+                .responses
+                .get(&line)
+                .cloned()
+                .unwrap_or_else(|| format!("{}: command not found\r\n", line));
+            let (first, second) = response.split_at(response.len() / 2);
+            self.pending
+                .push_back(format!("{}\x1b[J\r\n{}", line, first).into_bytes());
+            self.pending
+                .push_back(format!("{}{}", second, PROMPT).into_bytes());
+            Ok(())
+        }
+
+        fn recv(&mut self, _timeout: Duration) -> Option<Vec<u8>> {
+            self.pending.pop_front()
+        }
+    }
+
+    #[test]
+    fn strips_escape_sequences() {
+        assert_eq!(
+            strip_ansi("\x1b[1;32muart:~$ \x1b[m\x1b]0;title\x07ok\r\n\x1b7x\x1b8"),
+            "uart:~$ ok\nx"
+        );
+    }
+
+    #[test]
+    fn detects_prompt() {
+        let mut shell = FakeShell::new(&[]);
+        let prompt = detect_prompt(&mut shell, Duration::from_millis(500)).unwrap();
+        assert_eq!(prompt, "uart:~$ ");
+    }
+
+    #[test]
+    fn captures_one_response_per_command() {
+        let mut shell = FakeShell::new(&[("kernel uptime", "Uptime: 12345 ms\r\n")]);
+        let output = exec(&mut shell, "uart:~$ ", "kernel uptime", DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(output, "Uptime: 12345 ms");
+
+        let output = exec(&mut shell, "uart:~$ ", "bogus", DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(output, "bogus: command not found");
+        assert_eq!(shell.sent, ["kernel uptime", "bogus"]);
+    }
+
+    #[test]
+    fn times_out_without_prompt() {
+        struct Silent;
+        impl ShellIo for Silent {
+            fn send(&mut self, _data: &[u8]) -> Result<(), String> {
+                Ok(())
+            }
+            fn recv(&mut self, timeout: Duration) -> Option<Vec<u8>> {
+                std::thread::sleep(timeout);
+                None
+            }
+        }
+        let result = exec(
+            &mut Silent,
+            "uart:~$ ",
+            "kernel threads",
+            Duration::from_millis(50),
+        );
+        assert!(result.unwrap_err().contains("Timed out"));
+    }
+
+    #[test]
+    fn parses_help_with_wrapped_lines() {
+        let commands = parse_help(&strip_ansi(KERNEL_HELP));
+        let names: Vec<&str> = commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["stacks", "threads", "uptime"]);
+        assert_eq!(
+            commands[2].help,
+            "Kernel uptime. Can be called with the -p or --pretty options"
+        );
+    }
+
+    #[test]
+    fn builds_tree_and_completes() {
+        let mut shell = FakeShell::new(&[
+            ("help", HELP),
+            ("kernel -h", KERNEL_HELP),
+            (
+                "device -h",
+                "device - Device commands\r\nSubcommands:\r\n  list  :List configured devices\r\n",
+            ),
+        ]);
+        let tree = build_command_tree(&mut shell, "uart:~$ ", DEFAULT_TIMEOUT).unwrap();
+
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree[3].subcommands.len(), 3);
+        assert!(tree[0].subcommands.is_empty());
+        assert!(shell.sent.contains(&"kernel threads -h".to_string()));
+        assert!(!shell.sent.contains(&"help -h".to_string()));
+
+        assert_eq!(complete(&tree, "ke"), ["kernel"]);
+        assert_eq!(complete(&tree, "kernel "), ["stacks", "threads", "uptime"]);
+        assert_eq!(complete(&tree, "kernel t"), ["threads"]);
+        assert_eq!(complete(&tree, "device l"), ["list"]);
+        assert!(complete(&tree, "nope ").is_empty());
+    }
+}